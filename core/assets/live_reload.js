@@ -0,0 +1,7 @@
+(() => {
+  const source = new EventSource('/_dev/live-reload');
+  source.addEventListener('reload', () => {
+    source.close();
+    window.location.reload();
+  });
+})();
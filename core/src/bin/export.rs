@@ -1,5 +1,6 @@
+use anyhow::bail;
 use rust_blog::{
-    static_site::{ExportPaths, export_site},
+    static_site::{ExportOptions, ExportPaths, ExportTarget, export_site, link_check::check_site},
     utils::config::load_config_from_file,
 };
 use sea_orm::Database;
@@ -33,8 +34,22 @@ fn resolve_dir(env_key: &str, candidates: &[PathBuf]) -> PathBuf {
 #[rocket::main]
 async fn main() -> anyhow::Result<()> {
     let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let output_dir = env::args()
-        .nth(1)
+    let args: Vec<String> = env::args().skip(1).collect();
+    let check = args.iter().any(|arg| arg == "--check");
+    let target = match args.iter().find_map(|arg| arg.strip_prefix("--target=")) {
+        Some(value) => match ExportTarget::parse(value) {
+            Some(target) => target,
+            None => bail!("unknown --target {value:?}, expected cloudflare or nginx"),
+        },
+        None => ExportTarget::default(),
+    };
+    let options = ExportOptions {
+        minify: args.iter().any(|arg| arg == "--minify"),
+        target,
+    };
+    let output_dir = args
+        .iter()
+        .find(|arg| !arg.starts_with("--"))
         .map(PathBuf::from)
         .unwrap_or_else(|| PathBuf::from("dist"));
     let current_dir = env::current_dir()?;
@@ -58,12 +73,33 @@ async fn main() -> anyhow::Result<()> {
         &[current_dir.join("content"), executable_dir.join("content")],
     );
 
+    let article_dir = resolve_dir("ARTICLE_PATH", &[content_dir.join("articles")]);
+    let variant_dir = resolve_dir("IMAGE_VARIANT_PATH", &[content_dir.join("variants")]);
+
     let db = Database::connect(&database_url).await?;
     let config_map = load_config_from_file(&config_path);
     let paths = ExportPaths {
         templates_dir,
         content_dir,
+        article_dir,
+        variant_dir,
     };
-    export_site(&db, &config_map, output_dir, &paths).await?;
+    let report = export_site(&db, &config_map, &output_dir, &paths, &options).await?;
+    println!("📊 export: {} ページ, {}", report.pages, report.summary);
+    println!("⏱️ {}", report.timings);
+    if check {
+        let report = check_site(&output_dir)?;
+        if !report.is_empty() {
+            eprintln!("⚠️ リンクチェックで問題が見つかりました\n{report}");
+        }
+        let broken = report.broken_count();
+        if broken > 0 {
+            bail!("{broken} broken link(s) in {output_dir:?}");
+        }
+        println!(
+            "✅ リンクチェック完了: {} ページ / {} リンク",
+            report.pages, report.links
+        );
+    }
     Ok(())
 }
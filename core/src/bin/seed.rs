@@ -1,10 +1,15 @@
-use rust_blog::seed::run_all;
+use rust_blog::seed::{run_all, watch::watch};
 use sea_orm::{Database, DatabaseConnection, DbErr};
 
 #[rocket::main]
 async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let dry_run = args.iter().any(|arg| arg == "--dry-run");
     let db = connect_db().await?;
-    run_all(db).await?;
+    if args.iter().any(|arg| arg == "--watch") {
+        return watch(db, dry_run).await;
+    }
+    run_all(&db, dry_run).await?;
     Ok(())
 }
 
@@ -57,40 +57,6 @@ impl PageInfo {
             },
         }
     }
-    pub fn get_prev_url(&self, base_path: &str, sort_key: Option<&String>) -> String {
-        if self.has_prev {
-            format!(
-                "{}?page={}&per={}{}",
-                base_path,
-                self.prev_page,
-                self.per,
-                if let Some(key) = sort_key {
-                    "&sort_key=".to_owned() + &key
-                } else {
-                    "".to_owned()
-                }
-            )
-        } else {
-            String::new()
-        }
-    }
-    pub fn get_next_url(&self, base_path: &str, sort_key: Option<&String>) -> String {
-        if self.has_next {
-            format!(
-                "{}?page={}&per={}{}",
-                base_path,
-                self.next_page,
-                self.per,
-                if let Some(key) = sort_key {
-                    "&sort_key=".to_owned() + &key
-                } else {
-                    "".to_owned()
-                }
-            )
-        } else {
-            String::new()
-        }
-    }
 }
 
 #[cfg(test)]
@@ -153,7 +119,13 @@ mod tests {
 
     #[test]
     fn page_info_new_sets_bounds_and_navigation_flags() {
-        let info = PageInfo::new(Page { number: 99, per: 10 }, 95);
+        let info = PageInfo::new(
+            Page {
+                number: 99,
+                per: 10,
+            },
+            95,
+        );
         assert_eq!(info.current_page, 10);
         assert_eq!(info.total_pages, 10);
         assert!(info.has_prev);
@@ -172,25 +144,4 @@ mod tests {
         assert_eq!(info.prev_page, 1);
         assert_eq!(info.next_page, 1);
     }
-
-    #[test]
-    fn page_info_prev_next_url_include_sort_key() {
-        let info = PageInfo::new(Page { number: 2, per: 10 }, 50);
-        let sort_key = "updated_at".to_string();
-        assert_eq!(
-            info.get_prev_url("/tags/rust", Some(&sort_key)),
-            "/tags/rust?page=1&per=10&sort_key=updated_at"
-        );
-        assert_eq!(
-            info.get_next_url("/tags/rust", Some(&sort_key)),
-            "/tags/rust?page=3&per=10&sort_key=updated_at"
-        );
-    }
-
-    #[test]
-    fn page_info_prev_next_url_return_empty_when_no_navigation() {
-        let info = PageInfo::new(Page { number: 1, per: 10 }, 5);
-        assert_eq!(info.get_prev_url("/articles", None), "");
-        assert_eq!(info.get_next_url("/articles", None), "");
-    }
 }
@@ -6,5 +6,7 @@ pub mod article;
 pub mod article_category;
 pub mod article_tag;
 pub mod category;
+pub mod data_version;
 pub mod fixed_content;
+pub mod image;
 pub mod tag;
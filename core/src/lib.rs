@@ -7,3 +7,4 @@ pub mod seed;
 pub mod slug_config;
 pub mod static_site;
 pub mod utils;
+pub mod view;
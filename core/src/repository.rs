@@ -1,6 +1,8 @@
 pub mod article;
 pub mod category;
+pub mod data_version;
 pub mod fixed_content;
+pub mod image;
 pub mod tag;
 
 #[cfg(test)]
@@ -24,6 +26,7 @@ mod repository_tests {
             created_at: Utc::now(),
             updated_at: Utc::now(),
             icatch_path: None,
+            content_hash: None,
         };
         let page = Page { number: 1, per: 10 };
         let db = MockDatabase::new(DatabaseBackend::Sqlite)
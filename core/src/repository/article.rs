@@ -4,10 +4,121 @@ use chrono_tz::Asia::Tokyo;
 use sea_orm::{
     ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
     prelude::*,
-    sea_query::{Expr, SimpleExpr},
+    sea_query::{Alias, Expr, Func, SimpleExpr},
 };
 
-use crate::entity::{article, category, tag};
+use crate::{
+    entity::{article, category, tag},
+    repository::{category::get_categories_by_articles, tag::get_tags_by_articles},
+    utils::markdown::{EXCERPT_LENGTH, markdown_to_text},
+};
+use std::collections::HashMap;
+
+/// Listings only derive an excerpt from the body, which keeps at most
+/// [`EXCERPT_LENGTH`] characters of its text, so they load just the beginning of it.
+const LISTING_CONTENT_CHARS: u32 = 1000;
+
+/// Selects the article columns for list pages. `content` is cut down to its
+/// first [`LISTING_CONTENT_CHARS`] characters, or left empty when the article
+/// has its own excerpt. Pass the result through [`complete_listing_bodies`].
+fn listing_columns(query: Select<article::Entity>) -> Select<article::Entity> {
+    let content: SimpleExpr = Expr::case(
+        Expr::col((article::Entity, article::Column::Excerpt)).is_null(),
+        Func::cust(Alias::new("substr"))
+            .arg(Expr::col((article::Entity, article::Column::Content)))
+            .arg(1)
+            .arg(LISTING_CONTENT_CHARS),
+    )
+    .finally("")
+    .into();
+    query
+        .select_only()
+        .columns([
+            article::Column::Id,
+            article::Column::Title,
+            article::Column::Slug,
+            article::Column::Excerpt,
+            article::Column::CreatedAt,
+            article::Column::UpdatedAt,
+            article::Column::IcatchPath,
+            article::Column::ContentHash,
+        ])
+        .column_as(content, "content")
+}
+
+/// Drops the block a truncated body was cut in the middle of, since it may be
+/// parsed differently from the full body, and loads the whole body of articles
+/// whose remaining text is too short for an excerpt.
+async fn complete_listing_bodies(
+    db: &DatabaseConnection,
+    mut articles: Vec<article::Model>,
+) -> Result<Vec<article::Model>, DbErr> {
+    let mut short = Vec::new();
+    for article in &mut articles {
+        if article.excerpt.is_some()
+            || article.content.chars().count() < LISTING_CONTENT_CHARS as usize
+        {
+            continue;
+        }
+        match article
+            .content
+            .rfind("\n\n")
+            .max(article.content.rfind("\n\r\n"))
+        {
+            Some(end) => article.content.truncate(end),
+            None => article.content.clear(),
+        }
+        if markdown_to_text(&article.content).chars().count() < EXCERPT_LENGTH {
+            short.push(article.id);
+        }
+    }
+    if short.is_empty() {
+        return Ok(articles);
+    }
+    let mut bodies: HashMap<i32, String> = article::Entity::find()
+        .select_only()
+        .columns([article::Column::Id, article::Column::Content])
+        .filter(article::Column::Id.is_in(short))
+        .into_tuple()
+        .all(db)
+        .await?
+        .into_iter()
+        .collect();
+    for article in &mut articles {
+        if let Some(body) = bodies.remove(&article.id) {
+            article.content = body;
+        }
+    }
+    Ok(articles)
+}
+
+/// Tags and categories of a set of articles, keyed by article id.
+#[derive(Debug, Default)]
+pub struct ArticleTerms {
+    pub tags: HashMap<i32, Vec<tag::Model>>,
+    pub categories: HashMap<i32, Vec<category::Model>>,
+}
+
+impl ArticleTerms {
+    pub fn tags_of(&self, article_id: i32) -> &[tag::Model] {
+        self.tags.get(&article_id).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn categories_of(&self, article_id: i32) -> &[category::Model] {
+        self.categories.get(&article_id).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Loads the tags and categories of `articles` with one query each.
+pub async fn get_article_terms(
+    db: &DatabaseConnection,
+    articles: &[article::Model],
+) -> Result<ArticleTerms, DbErr> {
+    Ok(ArticleTerms {
+        tags: get_tags_by_articles(db, articles).await?,
+        categories: get_categories_by_articles(db, articles).await?,
+    })
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ArticlePeriod {
@@ -76,12 +187,13 @@ pub async fn get_all_articles(
     let page = page.normalize(50);
     let page_info = PageInfo::new(page, total);
     let offset = (page_info.current_page - 1) * page_info.per;
-    let articles = base_query
+    let articles = listing_columns(base_query)
         .order_by_desc(article::Column::CreatedAt)
         .offset(offset)
         .limit(page_info.per)
         .all(db)
         .await?;
+    let articles = complete_listing_bodies(db, articles).await?;
     Ok((articles, page_info))
 }
 
@@ -148,13 +260,27 @@ pub async fn get_latest_articles(
     limit: u64,
 ) -> Result<Vec<article::Model>, DbErr> {
     let now = Utc::now();
-    let articles = article::Entity::find()
+    let articles = listing_columns(article::Entity::find())
         .filter(article::Column::CreatedAt.lte(now))
         .order_by_desc(article::Column::CreatedAt)
         .limit(limit)
         .all(db)
         .await?;
-    Ok(articles)
+    complete_listing_bodies(db, articles).await
+}
+
+/// When the next scheduled article goes live, which changes the pages listing
+/// published articles. `None` without scheduled articles.
+pub async fn get_next_publication(db: &DatabaseConnection) -> Result<Option<DateTime<Utc>>, DbErr> {
+    let now = Utc::now();
+    article::Entity::find()
+        .select_only()
+        .column(article::Column::CreatedAt)
+        .filter(article::Column::CreatedAt.gt(now))
+        .order_by_asc(article::Column::CreatedAt)
+        .into_tuple()
+        .one(db)
+        .await
 }
 
 pub async fn get_articles_by_tag_slug(
@@ -180,7 +306,7 @@ pub async fn get_articles_by_tag_slug(
         let offset = (page_info.current_page - 1) * page_info.per;
         let articles = match sort_key {
             "updated_at" => {
-                tag.find_related(article::Entity)
+                listing_columns(tag.find_related(article::Entity))
                     .filter(article::Column::CreatedAt.lte(now))
                     .distinct()
                     .order_by_desc(article::Column::UpdatedAt)
@@ -190,7 +316,7 @@ pub async fn get_articles_by_tag_slug(
                     .await?
             }
             "created_at" => {
-                tag.find_related(article::Entity)
+                listing_columns(tag.find_related(article::Entity))
                     .filter(article::Column::CreatedAt.lte(now))
                     .distinct()
                     .order_by_desc(article::Column::CreatedAt)
@@ -200,7 +326,7 @@ pub async fn get_articles_by_tag_slug(
                     .await?
             }
             _ => {
-                tag.find_related(article::Entity)
+                listing_columns(tag.find_related(article::Entity))
                     .filter(article::Column::CreatedAt.lte(now))
                     .distinct()
                     .order_by_desc(article::Column::UpdatedAt)
@@ -210,6 +336,7 @@ pub async fn get_articles_by_tag_slug(
                     .await?
             }
         };
+        let articles = complete_listing_bodies(db, articles).await?;
         Ok((articles, page_info))
     } else {
         Err(DbErr::RecordNotFound("tag not found".into()))
@@ -239,8 +366,7 @@ pub async fn get_article_by_category_slug(
         let offset = (page_info.current_page - 1) * page_info.per;
         let articles = match sort_key {
             "updated_at" => {
-                category
-                    .find_related(article::Entity)
+                listing_columns(category.find_related(article::Entity))
                     .filter(article::Column::CreatedAt.lte(now))
                     .distinct()
                     .order_by_desc(article::Column::UpdatedAt)
@@ -250,8 +376,7 @@ pub async fn get_article_by_category_slug(
                     .await?
             }
             "created_at" => {
-                category
-                    .find_related(article::Entity)
+                listing_columns(category.find_related(article::Entity))
                     .filter(article::Column::CreatedAt.lte(now))
                     .distinct()
                     .order_by_desc(article::Column::CreatedAt)
@@ -261,8 +386,7 @@ pub async fn get_article_by_category_slug(
                     .await?
             }
             _ => {
-                category
-                    .find_related(article::Entity)
+                listing_columns(category.find_related(article::Entity))
                     .filter(article::Column::CreatedAt.lte(now))
                     .distinct()
                     .order_by_desc(article::Column::UpdatedAt)
@@ -272,6 +396,7 @@ pub async fn get_article_by_category_slug(
                     .await?
             }
         };
+        let articles = complete_listing_bodies(db, articles).await?;
         Ok((articles, page_info))
     } else {
         Err(DbErr::RecordNotFound("category not found".into()))
@@ -280,8 +405,127 @@ pub async fn get_article_by_category_slug(
 
 #[cfg(test)]
 mod tests {
-    use super::ArticlePeriod;
+    use super::{
+        ArticlePeriod, LISTING_CONTENT_CHARS, get_article_terms, get_latest_articles,
+        get_next_publication,
+    };
+    use crate::utils::{
+        cut_out_string,
+        markdown::{EXCERPT_LENGTH, markdown_to_text},
+    };
     use chrono::{TimeZone, Utc};
+    use sea_orm::{ConnectionTrait, Database, DatabaseConnection, DbBackend, Statement};
+
+    async fn prepare_db() -> DatabaseConnection {
+        let db = Database::connect("sqlite::memory:")
+            .await
+            .expect("failed to connect sqlite memory");
+        let long_body = long_body();
+        let near_cut_off_body = near_cut_off_body();
+        for sql in [
+            "CREATE TABLE article (id INTEGER PRIMARY KEY, title TEXT NOT NULL, slug TEXT NOT NULL, excerpt TEXT NULL, content TEXT NOT NULL, created_at TEXT NOT NULL, updated_at TEXT NOT NULL, icatch_path TEXT NULL, content_hash TEXT NULL);".to_string(),
+            "CREATE TABLE tag (id INTEGER PRIMARY KEY, name TEXT NOT NULL, slug TEXT NOT NULL);".to_string(),
+            "CREATE TABLE category (id INTEGER PRIMARY KEY, name TEXT NOT NULL, slug TEXT NOT NULL);".to_string(),
+            "CREATE TABLE article_tag (article_id INTEGER NOT NULL, tag_id INTEGER NOT NULL);".to_string(),
+            "CREATE TABLE article_category (article_id INTEGER NOT NULL, category_id INTEGER NOT NULL);".to_string(),
+            format!(
+                "INSERT INTO article (id, title, slug, excerpt, content, created_at, updated_at) VALUES
+                (1, 'Long', 'long', NULL, '{long_body}', '2025-12-01T00:00:00Z', '2025-12-01T00:00:00Z'),
+                (2, 'Own excerpt', 'own', 'Given', 'body', '2025-12-02T00:00:00Z', '2025-12-02T00:00:00Z'),
+                (3, 'Untagged', 'untagged', NULL, 'body', '2025-12-03T00:00:00Z', '2025-12-03T00:00:00Z'),
+                (4, 'Near cut-off', 'near', NULL, '{near_cut_off_body}', '2025-12-04T00:00:00Z', '2025-12-04T00:00:00Z');"
+            ),
+            "INSERT INTO tag (id, name, slug) VALUES (1, 'Rust', 'rust'), (2, 'Web', 'web');".to_string(),
+            "INSERT INTO category (id, name, slug) VALUES (1, 'Tech', 'tech');".to_string(),
+            "INSERT INTO article_tag (article_id, tag_id) VALUES (1, 2), (1, 1), (2, 1);".to_string(),
+            "INSERT INTO article_category (article_id, category_id) VALUES (2, 1);".to_string(),
+        ] {
+            db.execute(Statement::from_string(DbBackend::Sqlite, sql))
+                .await
+                .expect("failed to prepare articles");
+        }
+        db
+    }
+
+    fn long_body() -> String {
+        "A paragraph of body text.\n\n".repeat(100)
+    }
+
+    /// Markup with little text up to the cut-off, with the excerpt text after it.
+    fn near_cut_off_body() -> String {
+        format!(
+            "[link](https://example.com/{})\n\n{}",
+            "a".repeat(LISTING_CONTENT_CHARS as usize - 30),
+            "Text after the cut-off. ".repeat(10)
+        )
+    }
+
+    fn excerpt(content: &str) -> String {
+        cut_out_string(&markdown_to_text(content), EXCERPT_LENGTH)
+    }
+
+    #[rocket::async_test]
+    async fn listing_queries_load_only_the_start_of_the_body() {
+        let db = prepare_db().await;
+        let articles = get_latest_articles(&db, 5).await.expect("failed to list");
+
+        let content = |slug: &str| {
+            articles
+                .iter()
+                .find(|article| article.slug == slug)
+                .map(|article| article.content.clone())
+                .expect("article should be listed")
+        };
+        assert!(content("long").len() < LISTING_CONTENT_CHARS as usize);
+        assert_eq!(excerpt(&content("long")), excerpt(&long_body()));
+        assert_eq!(content("near"), near_cut_off_body());
+        assert_eq!(content("own"), "");
+        assert_eq!(content("untagged"), "body");
+    }
+
+    #[rocket::async_test]
+    async fn next_publication_is_the_earliest_scheduled_article() {
+        let db = prepare_db().await;
+        assert_eq!(
+            get_next_publication(&db).await.expect("failed to query"),
+            None
+        );
+
+        db.execute(Statement::from_string(
+            DbBackend::Sqlite,
+            "INSERT INTO article (id, title, slug, excerpt, content, created_at, updated_at) VALUES
+            (5, 'Later', 'later', NULL, 'body', '2099-02-01T00:00:00Z', '2099-02-01T00:00:00Z'),
+            (6, 'Sooner', 'sooner', NULL, 'body', '2099-01-01T00:00:00Z', '2099-01-01T00:00:00Z');",
+        ))
+        .await
+        .expect("failed to schedule articles");
+        assert_eq!(
+            get_next_publication(&db).await.expect("failed to query"),
+            Utc.with_ymd_and_hms(2099, 1, 1, 0, 0, 0).single()
+        );
+    }
+
+    #[rocket::async_test]
+    async fn article_terms_are_loaded_for_all_articles_at_once() {
+        let db = prepare_db().await;
+        let articles = get_latest_articles(&db, 5).await.expect("failed to list");
+        let terms = get_article_terms(&db, &articles)
+            .await
+            .expect("failed to load terms");
+
+        let tag_slugs = |id| {
+            terms
+                .tags_of(id)
+                .iter()
+                .map(|tag| tag.slug.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(tag_slugs(1), ["rust", "web"]);
+        assert_eq!(tag_slugs(2), ["rust"]);
+        assert!(tag_slugs(3).is_empty());
+        assert_eq!(terms.categories_of(2)[0].slug, "tech");
+        assert!(terms.categories_of(1).is_empty());
+    }
 
     #[test]
     fn article_period_new_rejects_invalid_month() {
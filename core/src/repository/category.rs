@@ -1,7 +1,8 @@
-use crate::entity::{article, category};
+use crate::entity::{article, article_category, category};
 use sea_orm::{
     ColumnTrait, DatabaseConnection, DbErr, EntityTrait, ModelTrait, QueryFilter, QueryOrder,
 };
+use std::collections::HashMap;
 
 pub async fn get_all_categories(db: &DatabaseConnection) -> Result<Vec<category::Model>, DbErr> {
     category::Entity::find()
@@ -27,3 +28,31 @@ pub async fn get_categories_by_article(
 ) -> Result<Vec<category::Model>, DbErr> {
     article.find_related(category::Entity).all(db).await
 }
+
+/// Categories of each of `articles` keyed by article id, loaded in one query.
+pub async fn get_categories_by_articles(
+    db: &DatabaseConnection,
+    articles: &[article::Model],
+) -> Result<HashMap<i32, Vec<category::Model>>, DbErr> {
+    let mut categories: HashMap<i32, Vec<category::Model>> = HashMap::new();
+    if articles.is_empty() {
+        return Ok(categories);
+    }
+    let rows = article_category::Entity::find()
+        .filter(
+            article_category::Column::ArticleId.is_in(articles.iter().map(|article| article.id)),
+        )
+        .find_also_related(category::Entity)
+        .order_by_asc(article_category::Column::CategoryId)
+        .all(db)
+        .await?;
+    for (link, category) in rows {
+        if let Some(category) = category {
+            categories
+                .entry(link.article_id)
+                .or_default()
+                .push(category);
+        }
+    }
+    Ok(categories)
+}
@@ -0,0 +1,63 @@
+use crate::entity::data_version;
+use sea_orm::{
+    ActiveValue::Set,
+    ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    sea_query::{Expr, OnConflict},
+};
+
+/// Row the seeder bumps after every run that writes to the database.
+pub const DATA_VERSION_ID: i32 = 1;
+
+/// Current data version, `0` before the first seed.
+pub async fn get_data_version(db: &DatabaseConnection) -> Result<i64, DbErr> {
+    Ok(data_version::Entity::find_by_id(DATA_VERSION_ID)
+        .one(db)
+        .await?
+        .map_or(0, |model| model.version))
+}
+
+/// Tells running servers that pages rendered so far are stale.
+// Used by the seeder in the library target.
+#[allow(dead_code)]
+pub async fn bump_data_version<C: ConnectionTrait>(db: &C) -> Result<(), DbErr> {
+    data_version::Entity::insert(data_version::ActiveModel {
+        id: Set(DATA_VERSION_ID),
+        version: Set(1),
+    })
+    .on_conflict(
+        OnConflict::column(data_version::Column::Id)
+            .value(
+                data_version::Column::Version,
+                Expr::col(data_version::Column::Version).add(1),
+            )
+            .to_owned(),
+    )
+    .exec(db)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bump_data_version, get_data_version};
+    use rocket::tokio;
+    use sea_orm::{ConnectionTrait, Database, DbBackend, Statement};
+
+    #[tokio::test]
+    async fn bump_data_version_counts_up_from_zero() {
+        let db = Database::connect("sqlite::memory:")
+            .await
+            .expect("failed to connect sqlite memory");
+        db.execute(Statement::from_string(
+            DbBackend::Sqlite,
+            "CREATE TABLE data_version (id INTEGER PRIMARY KEY, version BIGINT NOT NULL);",
+        ))
+        .await
+        .expect("failed to create data_version table");
+
+        assert_eq!(get_data_version(&db).await.expect("failed to read"), 0);
+        bump_data_version(&db).await.expect("failed to bump");
+        bump_data_version(&db).await.expect("failed to bump");
+        assert_eq!(get_data_version(&db).await.expect("failed to read"), 2);
+    }
+}
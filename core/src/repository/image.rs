@@ -0,0 +1,39 @@
+use crate::{
+    entity::image,
+    utils::image::{ImageIndex, ImageInfo},
+};
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter};
+
+// Used by the static export pipeline in the library target.
+#[allow(dead_code)]
+pub async fn get_image_index(db: &DatabaseConnection) -> Result<ImageIndex, DbErr> {
+    let models = image::Entity::find().all(db).await?;
+    Ok(into_index(models))
+}
+
+/// Only the images a page references, out of the whole `image` table.
+pub async fn get_images(db: &DatabaseConnection, urls: &[String]) -> Result<ImageIndex, DbErr> {
+    if urls.is_empty() {
+        return Ok(ImageIndex::new());
+    }
+    let models = image::Entity::find()
+        .filter(image::Column::Path.is_in(urls))
+        .all(db)
+        .await?;
+    Ok(into_index(models))
+}
+
+fn into_index(models: Vec<image::Model>) -> ImageIndex {
+    models
+        .into_iter()
+        .map(|model| {
+            let info = ImageInfo {
+                width: model.width.max(0) as u32,
+                height: model.height.max(0) as u32,
+                // Rows are only written by seed; fall back to the original if they ever don't parse.
+                variants: serde_json::from_str(&model.variants).unwrap_or_default(),
+            };
+            (model.path, info)
+        })
+        .collect()
+}
@@ -1,7 +1,8 @@
-use crate::entity::{article, tag};
+use crate::entity::{article, article_tag, tag};
 use sea_orm::{
     ColumnTrait, DatabaseConnection, DbErr, EntityTrait, ModelTrait, QueryFilter, QueryOrder,
 };
+use std::collections::HashMap;
 
 pub async fn get_all_tags(db: &DatabaseConnection) -> Result<Vec<tag::Model>, DbErr> {
     tag::Entity::find()
@@ -27,3 +28,26 @@ pub async fn get_tags_by_article(
 ) -> Result<Vec<tag::Model>, DbErr> {
     article.find_related(tag::Entity).all(db).await
 }
+
+/// Tags of each of `articles` keyed by article id, loaded in one query.
+pub async fn get_tags_by_articles(
+    db: &DatabaseConnection,
+    articles: &[article::Model],
+) -> Result<HashMap<i32, Vec<tag::Model>>, DbErr> {
+    let mut tags: HashMap<i32, Vec<tag::Model>> = HashMap::new();
+    if articles.is_empty() {
+        return Ok(tags);
+    }
+    let rows = article_tag::Entity::find()
+        .filter(article_tag::Column::ArticleId.is_in(articles.iter().map(|article| article.id)))
+        .find_also_related(tag::Entity)
+        .order_by_asc(article_tag::Column::TagId)
+        .all(db)
+        .await?;
+    for (link, tag) in rows {
+        if let Some(tag) = tag {
+            tags.entry(link.article_id).or_default().push(tag);
+        }
+    }
+    Ok(tags)
+}
@@ -1,16 +1,23 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, path::PathBuf};
 
 use rocket::fairing::{Fairing, Info, Kind};
-use rocket::fs::FileServer;
+use rocket::fs::{FileServer, Options};
 use rocket::http::Header;
-use rocket::{Ignite, Request, Response, Rocket};
+use rocket::{Build, Ignite, Request, Response, Rocket};
 use rocket_dyn_templates::Template;
 use sea_orm::DatabaseConnection;
 
+mod compression;
+mod conditional;
 mod get;
+mod live_reload;
+mod page_cache;
+#[cfg(test)]
+mod parity;
 
+use compression::Compression;
 use get::{
-    article::article_detail,
+    article::{ArticleDir, article_asset, article_detail},
     category::{category_detail, category_list},
     error::{
         bad_gateway::bad_gateway, bad_request::bad_request, forbidden::forbidden,
@@ -20,11 +27,17 @@ use get::{
     },
     fixed_content::fixed_content_detail,
     index::{index, index_archive, index_archive_page},
-    static_asset::{bulma_css, nav_js, site_css},
+    static_asset::{bulma_css, fingerprinted_asset, nav_js, site_css},
+    status,
     tag::{tag_detail, tag_list},
 };
+use page_cache::PageCache;
 
-use crate::utils::config::CommonConfig;
+use crate::utils::{
+    asset::{ASSET_URL_FUNCTION, AssetManifest},
+    config::CommonConfig,
+    image::VARIANT_URL_PREFIX,
+};
 
 pub struct SecurityHeaders;
 
@@ -50,19 +63,74 @@ impl Fairing for SecurityHeaders {
     }
 }
 
+/// Directories the server reads files from at request time.
+pub struct ContentPaths {
+    pub article_dir: PathBuf,
+    pub image_dir: PathBuf,
+    pub icon_dir: PathBuf,
+    pub variant_dir: PathBuf,
+}
+
+impl ContentPaths {
+    pub fn from_env() -> Self {
+        ContentPaths {
+            article_dir: std::env::var("ARTICLE_PATH")
+                .unwrap_or_else(|_| "content/articles".to_string())
+                .into(),
+            image_dir: "content/image".into(),
+            icon_dir: "content/icon".into(),
+            variant_dir: std::env::var("IMAGE_VARIANT_PATH")
+                .unwrap_or_else(|_| "content/variants".to_string())
+                .into(),
+        }
+    }
+}
+
 pub async fn launch(
     db: DatabaseConnection,
     config_map: HashMap<String, String>,
 ) -> Result<Rocket<Ignite>, rocket::Error> {
-    return rocket::build()
+    // Live reload rewrites the page body, so it has to come before compression.
+    let rocket = live_reload::stage(rocket::build());
+    return build(rocket, db, config_map, ContentPaths::from_env())
+        .launch()
+        .await;
+}
+
+/// Templates with `asset_url` resolving to the fingerprinted URLs in `assets`.
+pub fn templates(assets: AssetManifest) -> impl Fairing {
+    Template::custom(move |engines| {
+        engines
+            .tera
+            .register_function(ASSET_URL_FUNCTION, assets.clone());
+    })
+}
+
+/// Mounts the blog on `rocket`. Dev-only additions such as live reload are left to `launch`.
+pub fn build(
+    rocket: Rocket<Build>,
+    db: DatabaseConnection,
+    config_map: HashMap<String, String>,
+    paths: ContentPaths,
+) -> Rocket<Build> {
+    // Images added while the server runs are linked under their plain URL until a restart.
+    let assets = AssetManifest::load(&paths.image_dir).unwrap_or_else(|error| {
+        error!("failed to fingerprint {:?}: {}", paths.image_dir, error);
+        AssetManifest::embedded()
+    });
+    status::stage(rocket)
         .manage(db)
+        .manage(ArticleDir(paths.article_dir))
         .manage(CommonConfig {
             site_name: config_map.get("site_name").cloned(),
             default_icatch_path: config_map.get("default_icatch_path").cloned(),
             favicon_path: config_map.get("favicon_path").cloned(),
         })
+        .manage(assets.clone())
+        .manage(PageCache::from_env())
         .attach(SecurityHeaders)
-        .attach(Template::fairing())
+        .attach(Compression)
+        .attach(templates(assets))
         .mount(
             "/",
             routes![
@@ -71,17 +139,24 @@ pub async fn launch(
                 index_archive,
                 index_archive_page,
                 article_detail,
+                article_asset,
                 bulma_css,
                 site_css,
                 nav_js,
+                fingerprinted_asset,
                 tag_list,
                 tag_detail,
                 category_list,
                 category_detail
             ],
         )
-        .mount("/image", FileServer::from("content/image"))
-        .mount("/icon", FileServer::from("content/icon"))
+        .mount("/image", FileServer::from(paths.image_dir))
+        .mount("/icon", FileServer::from(paths.icon_dir))
+        // Generated by seed, so the directory may not exist yet.
+        .mount(
+            VARIANT_URL_PREFIX,
+            FileServer::new(paths.variant_dir, Options::Missing),
+        )
         .register(
             "/",
             catchers![
@@ -96,6 +171,4 @@ pub async fn launch(
                 unauthorized
             ],
         )
-        .launch()
-        .await;
 }
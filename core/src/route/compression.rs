@@ -0,0 +1,323 @@
+use std::{
+    collections::HashMap,
+    io::Cursor,
+    sync::{Arc, LazyLock, Mutex},
+};
+
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::{ContentType, Header, Status};
+use rocket::response::{self, Responder};
+use rocket::{Request, Response};
+
+use crate::utils::compression::{brotli, gzip};
+
+/// Bodies smaller than this gain less than the header costs.
+const MIN_COMPRESS_SIZE: usize = 1024;
+/// Pages are compressed on every request, so speed wins over size here.
+const GZIP_LEVEL: u32 = 6;
+const BROTLI_QUALITY: u32 = 5;
+/// Static bodies are compressed once, so they get smaller output. Brotli stays
+/// below 11, which takes seconds on bulma while blocking the request.
+const STATIC_GZIP_LEVEL: u32 = 9;
+const STATIC_BROTLI_QUALITY: u32 = 9;
+
+/// Compressed copies of static bodies, keyed by the address and length of the
+/// bytes, which identify a `'static` slice.
+type CompressedBodies = HashMap<(usize, usize, Encoding), Arc<[u8]>>;
+static COMPRESSED: LazyLock<Mutex<CompressedBodies>> = LazyLock::new(Mutex::default);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Encoding {
+    Brotli,
+    Gzip,
+}
+
+impl Encoding {
+    fn name(self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+        }
+    }
+}
+
+/// Compresses text responses with brotli or gzip, whichever the client accepts.
+pub struct Compression;
+
+#[rocket::async_trait]
+impl Fairing for Compression {
+    fn info(&self) -> Info {
+        Info {
+            name: "Response Compression",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        if res.status() != Status::Ok
+            || res.headers().contains("Content-Encoding")
+            || !res.content_type().is_some_and(|ct| is_compressible(&ct))
+        {
+            return;
+        }
+        res.adjoin_header(Header::new("Vary", "Accept-Encoding"));
+        let Some(encoding) = preferred_encoding(req.headers().get("Accept-Encoding")) else {
+            return;
+        };
+        // Streamed bodies such as the live reload events are left alone.
+        let size = res.body_mut().size().await;
+        if size.is_none_or(|size| size < MIN_COMPRESS_SIZE) {
+            return;
+        }
+        let Ok(body) = res.body_mut().to_bytes().await else {
+            return;
+        };
+        let compressed = match encoding {
+            Encoding::Brotli => brotli(&body, BROTLI_QUALITY),
+            Encoding::Gzip => gzip(&body, GZIP_LEVEL),
+        };
+        match compressed {
+            Ok(compressed) => {
+                res.set_header(Header::new("Content-Encoding", encoding.name()));
+                res.set_sized_body(compressed.len(), Cursor::new(compressed));
+            }
+            Err(e) => {
+                error!("failed to compress {}: {}", req.uri(), e);
+                res.set_sized_body(body.len(), Cursor::new(body));
+            }
+        }
+    }
+}
+
+/// A body compiled into the binary, such as an embedded stylesheet. It is
+/// compressed the first time a client accepts an encoding and kept for the
+/// life of the process, instead of by [`Compression`] on every response.
+/// Otherwise the fairing sees it like any other response.
+pub struct StaticBody {
+    pub content_type: ContentType,
+    pub bytes: &'static [u8],
+}
+
+impl StaticBody {
+    fn compressed(&self, encoding: Encoding) -> std::io::Result<Arc<[u8]>> {
+        let key = (self.bytes.as_ptr() as usize, self.bytes.len(), encoding);
+        if let Some(compressed) = lock_compressed().get(&key) {
+            return Ok(compressed.clone());
+        }
+        let compressed: Arc<[u8]> = match encoding {
+            Encoding::Brotli => brotli(self.bytes, STATIC_BROTLI_QUALITY)?,
+            Encoding::Gzip => gzip(self.bytes, STATIC_GZIP_LEVEL)?,
+        }
+        .into();
+        lock_compressed().insert(key, compressed.clone());
+        Ok(compressed)
+    }
+}
+
+fn lock_compressed() -> std::sync::MutexGuard<'static, CompressedBodies> {
+    // Entries are only inserted whole, so a panicking holder leaves them usable.
+    COMPRESSED
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl<'r> Responder<'r, 'static> for StaticBody {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let mut response = (self.content_type.clone(), self.bytes).respond_to(req)?;
+        if self.bytes.len() < MIN_COMPRESS_SIZE || !is_compressible(&self.content_type) {
+            return Ok(response);
+        }
+        let Some(encoding) = preferred_encoding(req.headers().get("Accept-Encoding")) else {
+            return Ok(response);
+        };
+        match self.compressed(encoding) {
+            Ok(compressed) => {
+                response.adjoin_header(Header::new("Vary", "Accept-Encoding"));
+                response.set_header(Header::new("Content-Encoding", encoding.name()));
+                response.set_sized_body(compressed.len(), Cursor::new(compressed));
+            }
+            // Sent as is; the fairing compresses it instead.
+            Err(e) => error!("failed to compress {}: {}", req.uri(), e),
+        }
+        Ok(response)
+    }
+}
+
+fn is_compressible(content_type: &ContentType) -> bool {
+    content_type.is_html()
+        || content_type.is_css()
+        || content_type.is_javascript()
+        || content_type.is_json()
+        || content_type.is_plain()
+        || content_type.is_svg()
+        || content_type.is_xml()
+}
+
+/// Brotli when the client accepts it, then gzip. Codings with `q=0` are refused.
+fn preferred_encoding<'a>(accept_encoding: impl Iterator<Item = &'a str>) -> Option<Encoding> {
+    let mut accepted = Vec::new();
+    for coding in accept_encoding.flat_map(|value| value.split(',')) {
+        let mut params = coding.split(';').map(str::trim);
+        let name = params.next().unwrap_or_default();
+        let refused = params.any(|param| {
+            param
+                .strip_prefix("q=")
+                .and_then(|q| q.parse::<f32>().ok())
+                .is_some_and(|q| q <= 0.0)
+        });
+        if !refused {
+            accepted.push(name.to_ascii_lowercase());
+        }
+    }
+    [Encoding::Brotli, Encoding::Gzip]
+        .into_iter()
+        .find(|encoding| accepted.iter().any(|name| name == encoding.name()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Compression, Encoding, StaticBody, lock_compressed, preferred_encoding};
+    use flate2::read::GzDecoder;
+    use rocket::http::{ContentType, Header};
+    use rocket::local::asynchronous::Client;
+    use std::{io::Read, sync::LazyLock};
+
+    #[get("/page")]
+    fn page() -> (ContentType, String) {
+        (ContentType::HTML, "<p>hello</p>\n".repeat(200))
+    }
+
+    #[get("/small")]
+    fn small() -> (ContentType, &'static str) {
+        (ContentType::HTML, "<p>hi</p>")
+    }
+
+    #[get("/image")]
+    fn image() -> (ContentType, Vec<u8>) {
+        (ContentType::PNG, vec![0; 4096])
+    }
+
+    static STYLESHEET: LazyLock<String> = LazyLock::new(|| "body { margin: 0 }\n".repeat(100));
+
+    #[get("/static.css")]
+    fn static_css() -> StaticBody {
+        StaticBody {
+            content_type: ContentType::CSS,
+            bytes: STYLESHEET.as_bytes(),
+        }
+    }
+
+    async fn client() -> Client {
+        let rocket = rocket::build()
+            .attach(Compression)
+            .mount("/", routes![page, small, image, static_css]);
+        Client::tracked(rocket)
+            .await
+            .expect("failed to build client")
+    }
+
+    #[test]
+    fn preferred_encoding_takes_brotli_first_and_skips_refused_codings() {
+        assert_eq!(
+            preferred_encoding(["gzip, deflate, br"].into_iter()),
+            Some(Encoding::Brotli)
+        );
+        assert_eq!(
+            preferred_encoding(["br;q=0, GZIP;q=0.5"].into_iter()),
+            Some(Encoding::Gzip)
+        );
+        assert_eq!(preferred_encoding(["identity"].into_iter()), None);
+        assert_eq!(preferred_encoding(std::iter::empty()), None);
+    }
+
+    #[rocket::async_test]
+    async fn compresses_text_responses_the_client_accepts() {
+        let client = client().await;
+        let expected = "<p>hello</p>\n".repeat(200);
+
+        let response = client
+            .get("/page")
+            .header(Header::new("Accept-Encoding", "gzip"))
+            .dispatch()
+            .await;
+        assert_eq!(response.headers().get_one("Content-Encoding"), Some("gzip"));
+        assert_eq!(response.headers().get_one("Vary"), Some("Accept-Encoding"));
+        let body = response.into_bytes().await.expect("body should exist");
+        let mut decoded = String::new();
+        GzDecoder::new(body.as_slice())
+            .read_to_string(&mut decoded)
+            .expect("failed to gunzip");
+        assert_eq!(decoded, expected);
+
+        let response = client
+            .get("/page")
+            .header(Header::new("Accept-Encoding", "gzip, br"))
+            .dispatch()
+            .await;
+        assert_eq!(response.headers().get_one("Content-Encoding"), Some("br"));
+        let body = response.into_bytes().await.expect("body should exist");
+        let mut decoded = String::new();
+        brotli::Decompressor::new(body.as_slice(), 4096)
+            .read_to_string(&mut decoded)
+            .expect("failed to decompress brotli");
+        assert_eq!(decoded, expected);
+    }
+
+    #[rocket::async_test]
+    async fn static_bodies_are_compressed_once_and_not_again_by_the_fairing() {
+        let client = client().await;
+        let key = (
+            STYLESHEET.as_ptr() as usize,
+            STYLESHEET.len(),
+            Encoding::Brotli,
+        );
+
+        for _ in 0..2 {
+            let response = client
+                .get("/static.css")
+                .header(Header::new("Accept-Encoding", "br"))
+                .dispatch()
+                .await;
+            assert_eq!(response.headers().get_one("Content-Encoding"), Some("br"));
+            assert_eq!(
+                response.headers().get("Vary").collect::<Vec<_>>(),
+                ["Accept-Encoding"]
+            );
+            let body = response.into_bytes().await.expect("body should exist");
+            let mut decoded = String::new();
+            brotli::Decompressor::new(body.as_slice(), 4096)
+                .read_to_string(&mut decoded)
+                .expect("failed to decompress brotli");
+            assert_eq!(decoded, *STYLESHEET);
+            assert!(lock_compressed().contains_key(&key));
+        }
+
+        let response = client.get("/static.css").dispatch().await;
+        assert_eq!(response.headers().get_one("Content-Encoding"), None);
+        assert_eq!(
+            response.into_string().await.as_deref(),
+            Some(STYLESHEET.as_str())
+        );
+    }
+
+    #[rocket::async_test]
+    async fn leaves_small_binary_and_unaccepted_responses_alone() {
+        let client = client().await;
+        for (uri, accept_encoding) in [
+            ("/page", None),
+            ("/small", Some("br")),
+            ("/image", Some("br")),
+        ] {
+            let mut request = client.get(uri);
+            if let Some(accept_encoding) = accept_encoding {
+                request = request.header(Header::new("Accept-Encoding", accept_encoding));
+            }
+            let response = request.dispatch().await;
+            assert_eq!(
+                response.headers().get_one("Content-Encoding"),
+                None,
+                "{uri}"
+            );
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use std::{convert::Infallible, sync::LazyLock, time::SystemTime};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use rocket::http::{Header, Status};
+use rocket::request::{FromRequest, Outcome};
+use rocket::response::{self, Responder};
+use rocket::{Request, Response};
+use sha2::{Digest, Sha256};
+
+use crate::{entity::article, repository::article::ArticleTerms};
+
+/// `Last-Modified` / `If-Modified-Since` format, always in GMT.
+const HTTP_DATE_FORMAT: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+/// Templates and assets only change with a restart, so pages cached by an
+/// earlier process must not validate.
+static STARTED: LazyLock<String> = LazyLock::new(|| {
+    let started = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    started.as_nanos().to_string()
+});
+
+/// What a page was rendered from, sent as `ETag` and `Last-Modified`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Validators {
+    etag: String,
+    last_modified: DateTime<Utc>,
+}
+
+impl Validators {
+    /// The `ETag` is weak, as the compression fairing may change the bytes sent.
+    pub fn new<'a>(
+        parts: impl IntoIterator<Item = &'a [u8]>,
+        last_modified: DateTime<Utc>,
+    ) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(STARTED.as_bytes());
+        for part in parts {
+            hasher.update([0]);
+            hasher.update(part);
+        }
+        Validators {
+            etag: format!("W/\"{:x}\"", hasher.finalize()),
+            last_modified,
+        }
+    }
+
+    pub fn set_headers(&self, response: &mut Response<'_>) {
+        for header in self.headers() {
+            response.set_header(header);
+        }
+    }
+
+    fn headers(&self) -> [Header<'static>; 3] {
+        [
+            Header::new("ETag", self.etag.clone()),
+            Header::new(
+                "Last-Modified",
+                self.last_modified.format(HTTP_DATE_FORMAT).to_string(),
+            ),
+            // Cache, but ask every time whether the page changed.
+            Header::new("Cache-Control", "no-cache"),
+        ]
+    }
+}
+
+/// Validator part for a seeded row. The content hash covers the front matter,
+/// `updated_at` the dates taken from git history and rows without a hash.
+pub fn revision(content_hash: Option<&str>, updated_at: DateTime<Utc>) -> String {
+    format!(
+        "{}@{}",
+        content_hash.unwrap_or_default(),
+        updated_at.to_rfc3339()
+    )
+}
+
+/// `Last-Modified` of a page showing rows updated at `updated_ats`.
+pub fn newest(updated_ats: impl IntoIterator<Item = DateTime<Utc>>) -> DateTime<Utc> {
+    updated_ats
+        .into_iter()
+        .max()
+        .unwrap_or(DateTime::UNIX_EPOCH)
+}
+
+/// Validators of an article listing: the listed articles, the tags and
+/// categories shown with them, and `extra` for the rest of the page such as
+/// the pagination.
+pub fn listing_validators(
+    articles: &[article::Model],
+    terms: Option<&ArticleTerms>,
+    extra: &[String],
+) -> Validators {
+    let mut parts: Vec<String> = articles
+        .iter()
+        .map(|article| revision(article.content_hash.as_deref(), article.updated_at))
+        .collect();
+    if let Some(terms) = terms {
+        for article in articles {
+            parts.extend(
+                terms
+                    .tags_of(article.id)
+                    .iter()
+                    .map(|tag| format!("tag:{}:{}", tag.slug, tag.name)),
+            );
+            parts.extend(
+                terms
+                    .categories_of(article.id)
+                    .iter()
+                    .map(|category| format!("category:{}:{}", category.slug, category.name)),
+            );
+        }
+    }
+    parts.extend_from_slice(extra);
+    Validators::new(
+        parts.iter().map(String::as_bytes),
+        newest(articles.iter().map(|article| article.updated_at)),
+    )
+}
+
+/// `If-None-Match` / `If-Modified-Since` of the request.
+#[derive(Debug, Default)]
+pub struct Preconditions<'r> {
+    if_none_match: Option<&'r str>,
+    if_modified_since: Option<&'r str>,
+}
+
+impl Preconditions<'_> {
+    /// Whether the client already has the page `validators` describe.
+    /// `If-Modified-Since` only counts without `If-None-Match`.
+    pub fn not_modified(&self, validators: &Validators) -> bool {
+        if let Some(if_none_match) = self.if_none_match {
+            return if_none_match
+                .split(',')
+                .map(str::trim)
+                .any(|tag| tag == "*" || weak_eq(tag, &validators.etag));
+        }
+        self.if_modified_since
+            .and_then(|since| NaiveDateTime::parse_from_str(since.trim(), HTTP_DATE_FORMAT).ok())
+            .is_some_and(|since| {
+                validators.last_modified.timestamp() <= since.and_utc().timestamp()
+            })
+    }
+}
+
+fn weak_eq(a: &str, b: &str) -> bool {
+    a.trim_start_matches("W/") == b.trim_start_matches("W/")
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Preconditions<'r> {
+    type Error = Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(Preconditions {
+            if_none_match: req.headers().get_one("If-None-Match"),
+            if_modified_since: req.headers().get_one("If-Modified-Since"),
+        })
+    }
+}
+
+/// A page, or `304 Not Modified` when the client has it. The page sends its
+/// own validators.
+pub enum Conditional<R> {
+    NotModified(Validators),
+    Modified(R),
+}
+
+impl<'r, 'o: 'r, R: Responder<'r, 'o>> Responder<'r, 'o> for Conditional<R> {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'o> {
+        match self {
+            Conditional::NotModified(validators) => {
+                let mut response = Response::build().status(Status::NotModified).finalize();
+                validators.set_headers(&mut response);
+                Ok(response)
+            }
+            Conditional::Modified(page) => page.respond_to(req),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Conditional, Preconditions, Validators, newest};
+    use crate::route::page_cache::RenderedPage;
+    use chrono::{DateTime, TimeZone, Utc};
+    use rocket::http::{Header, Status};
+    use rocket::local::asynchronous::Client;
+
+    fn validators() -> Validators {
+        let updated_at = Utc
+            .with_ymd_and_hms(2025, 1, 2, 3, 4, 5)
+            .single()
+            .expect("valid date");
+        Validators::new([b"hash".as_slice()], updated_at)
+    }
+
+    #[get("/page")]
+    fn page(preconditions: Preconditions<'_>) -> Conditional<RenderedPage> {
+        RenderedPage {
+            html: "page".to_string(),
+            validators: Some(validators()),
+        }
+        .or_not_modified(&preconditions)
+    }
+
+    #[test]
+    fn etag_changes_with_the_parts() {
+        let updated_at = Utc::now();
+        assert_eq!(
+            Validators::new([b"a".as_slice()], updated_at),
+            Validators::new([b"a".as_slice()], updated_at)
+        );
+        assert_ne!(
+            Validators::new([b"a".as_slice(), b"b"], updated_at),
+            Validators::new([b"ab".as_slice()], updated_at)
+        );
+    }
+
+    #[test]
+    fn newest_takes_the_latest_update() {
+        let at = |day| {
+            Utc.with_ymd_and_hms(2025, 1, day, 0, 0, 0)
+                .single()
+                .expect("valid date")
+        };
+        assert_eq!(newest([at(2), at(5), at(3)]), at(5));
+        assert_eq!(newest([]), DateTime::UNIX_EPOCH);
+    }
+
+    #[test]
+    fn if_none_match_wins_over_if_modified_since() {
+        let validators = validators();
+        let etag = validators.etag.clone();
+        let matching = |if_none_match, if_modified_since| {
+            Preconditions {
+                if_none_match,
+                if_modified_since,
+            }
+            .not_modified(&validators)
+        };
+        assert!(matching(Some(&etag), None));
+        assert!(matching(Some(etag.trim_start_matches("W/")), None));
+        let listed = format!("W/\"other\", {etag}");
+        assert!(matching(Some(&listed), None));
+        assert!(matching(Some("*"), None));
+        assert!(!matching(
+            Some("W/\"other\""),
+            Some("Thu, 02 Jan 2025 03:04:05 GMT")
+        ));
+        assert!(matching(None, Some("Thu, 02 Jan 2025 03:04:05 GMT")));
+        assert!(!matching(None, Some("Thu, 02 Jan 2025 03:04:04 GMT")));
+        assert!(!matching(None, Some("yesterday")));
+        assert!(!matching(None, None));
+    }
+
+    #[rocket::async_test]
+    async fn answers_304_to_a_client_with_the_current_page() {
+        let client = Client::tracked(rocket::build().mount("/", routes![page]))
+            .await
+            .expect("failed to build client");
+
+        let response = client.get("/page").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(
+            response.headers().get_one("Last-Modified"),
+            Some("Thu, 02 Jan 2025 03:04:05 GMT")
+        );
+        let etag = response
+            .headers()
+            .get_one("ETag")
+            .expect("etag should be set")
+            .to_string();
+        assert_eq!(response.into_string().await.as_deref(), Some("page"));
+
+        let response = client
+            .get("/page")
+            .header(Header::new("If-None-Match", etag.clone()))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotModified);
+        assert_eq!(response.headers().get_one("ETag"), Some(etag.as_str()));
+        assert_eq!(response.into_string().await, None);
+    }
+}
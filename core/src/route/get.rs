@@ -4,4 +4,5 @@ pub mod error;
 pub mod fixed_content;
 pub mod index;
 pub mod static_asset;
+pub mod status;
 pub mod tag;
@@ -1,94 +1,166 @@
 use crate::{
+    entity::{article, category, tag},
     repository::{
         article::{get_article_by_slug, get_latest_articles},
         category::get_categories_by_article,
+        image::get_images,
         tag::get_tags_by_article,
     },
-    utils::{config::CommonConfig, markdown::markdown_to_html, utc_to_jst},
+    route::{
+        conditional::{Conditional, Validators, newest, revision},
+        page_cache::{PageRequest, RenderedPage},
+    },
+    utils::{
+        asset::AssetManifest,
+        bundle::{bundle_dir, bundle_url, is_bundle_asset},
+        config::CommonConfig,
+        markdown::image_urls,
+    },
+    view::{LiveUrls, View},
 };
-use rocket::{State, http::Status};
-use rocket_dyn_templates::{Template, context};
+use rocket::{State, fs::NamedFile, http::Status};
+use rocket_dyn_templates::Metadata;
 use sea_orm::DatabaseConnection;
-use serde_json::json;
+use std::path::PathBuf;
+
+/// Root of the article sources, where page bundles keep their colocated files.
+pub struct ArticleDir(pub PathBuf);
 
 #[get("/posts/<slug>")]
 pub async fn article_detail(
     config: &State<CommonConfig>,
+    assets: &State<AssetManifest>,
     db: &State<DatabaseConnection>,
+    request: PageRequest<'_>,
     slug: &str,
-) -> Result<Template, Status> {
-    let conn = db.inner();
-    let maybe = get_article_by_slug(conn, slug)
+) -> Result<Conditional<RenderedPage>, Status> {
+    let metadata = &request.metadata;
+    request
+        .respond_loaded(
+            db,
+            ArticleDetail::load(db, slug),
+            |detail| {
+                article_validators(
+                    &detail.article,
+                    &detail.tags,
+                    &detail.categories,
+                    &detail.latest_articles,
+                )
+            },
+            |detail| async move {
+                render_article_detail(config, assets, db, metadata, &detail).await
+            },
+        )
         .await
-        .map_err(|_| Status::InternalServerError)?
-        .ok_or(Status::NotFound);
-
-    let article = match maybe {
-        Ok(model) => model,
-        Err(_) => return Err(Status::NotFound),
-    };
+}
 
-    let content = markdown_to_html(&article.content);
+/// Rows an article page shows, read before rendering to answer conditional
+/// requests the page cache cannot.
+struct ArticleDetail {
+    article: article::Model,
+    tags: Vec<tag::Model>,
+    categories: Vec<category::Model>,
+    latest_articles: Vec<article::Model>,
+}
 
-    let tags: Vec<_> = get_tags_by_article(conn, &article)
-        .await
-        .map_err(|_| Status::InternalServerError)?
-        .into_iter()
-        .map(|tag| {
-            let slug = tag.slug;
-            json!({
-                "name": tag.name,
-                "slug": slug.clone(),
-                "url": format!("/tag/{slug}"),
-            })
+impl ArticleDetail {
+    async fn load(db: &DatabaseConnection, slug: &str) -> Result<Self, Status> {
+        let article = get_article_by_slug(db, slug)
+            .await
+            .map_err(|_| Status::InternalServerError)?
+            .ok_or(Status::NotFound)?;
+        let tags = get_tags_by_article(db, &article)
+            .await
+            .map_err(|_| Status::InternalServerError)?;
+        let categories = get_categories_by_article(db, &article)
+            .await
+            .map_err(|_| Status::InternalServerError)?;
+        let latest_articles = get_latest_articles(db, 5)
+            .await
+            .map_err(|_| Status::InternalServerError)?;
+        Ok(ArticleDetail {
+            article,
+            tags,
+            categories,
+            latest_articles,
         })
-        .collect();
+    }
+}
 
-    let categories: Vec<_> = get_categories_by_article(conn, &article)
-        .await
-        .map_err(|_| Status::InternalServerError)?
-        .into_iter()
-        .map(|category| {
-            let slug = category.slug;
-            json!({
-                "name": category.name,
-                "slug": slug.clone(),
-                "url": format!("/category/{slug}"),
-            })
-        })
-        .collect();
-    let created_at = utc_to_jst(article.created_at);
-    let updated_at = utc_to_jst(article.updated_at);
+async fn render_article_detail(
+    config: &CommonConfig,
+    assets: &AssetManifest,
+    db: &DatabaseConnection,
+    metadata: &Metadata<'_>,
+    detail: &ArticleDetail,
+) -> Result<RenderedPage, Status> {
+    let ArticleDetail {
+        article,
+        tags,
+        categories,
+        latest_articles,
+    } = detail;
+    let image_urls = image_urls(&article.content, Some(&bundle_url(&article.slug)));
+    let images = get_images(db, &image_urls).await.unwrap_or_default();
+
+    let view = View::new(config, &images, assets, &LiveUrls::QUERY);
+    RenderedPage::render(
+        metadata,
+        "article_detail",
+        view.article_detail(article, tags, categories, latest_articles),
+    )
+}
+
+/// Everything on the page that the seeder changes, apart from image sizes.
+fn article_validators(
+    article: &article::Model,
+    tags: &[tag::Model],
+    categories: &[category::Model],
+    latest_articles: &[article::Model],
+) -> Validators {
+    let parts: Vec<String> = [revision(
+        article.content_hash.as_deref(),
+        article.updated_at,
+    )]
+    .into_iter()
+    .chain(
+        tags.iter()
+            .map(|tag| format!("tag:{}:{}", tag.slug, tag.name)),
+    )
+    .chain(
+        categories
+            .iter()
+            .map(|category| format!("category:{}:{}", category.slug, category.name)),
+    )
+    .chain(
+        latest_articles
+            .iter()
+            .map(|latest| revision(latest.content_hash.as_deref(), latest.updated_at)),
+    )
+    .collect();
+    let last_modified = newest(
+        std::iter::once(article.updated_at)
+            .chain(latest_articles.iter().map(|latest| latest.updated_at)),
+    );
+    Validators::new(parts.iter().map(String::as_bytes), last_modified)
+}
 
-    let latest_articles: Vec<_> = get_latest_articles(db, 5)
+#[get("/posts/<slug>/<file..>", rank = 2)]
+pub async fn article_asset(
+    article_dir: &State<ArticleDir>,
+    db: &State<DatabaseConnection>,
+    slug: &str,
+    file: PathBuf,
+) -> Result<NamedFile, Status> {
+    if !is_bundle_asset(&file) {
+        return Err(Status::NotFound);
+    }
+    // Files of unpublished articles stay private along with the article itself.
+    get_article_by_slug(db.inner(), slug)
         .await
         .map_err(|_| Status::InternalServerError)?
-        .into_iter()
-        .map(|model| {
-            let slug = model.slug;
-            json!({
-                "title":      model.title,
-                "slug":       slug.clone(),
-                "url":        format!("/posts/{slug}"),
-            })
-        })
-        .collect();
-
-    Ok(Template::render(
-        "article_detail",
-        context! {
-            site_name: &config.site_name,
-            favicon_path: &config.favicon_path,
-            tags_url: "/tags",
-            categories_url: "/categories",
-            about_url: "/about",
-            title: article.title,
-            content_html: content,
-            created_at: created_at,
-            updated_at: updated_at,
-            tags: &tags,
-            categories: &categories,
-            latest_articles: latest_articles
-        },
-    ))
+        .ok_or(Status::NotFound)?;
+    NamedFile::open(bundle_dir(&article_dir.0, slug).join(file))
+        .await
+        .map_err(|_| Status::NotFound)
 }
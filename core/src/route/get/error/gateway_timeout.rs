@@ -1,7 +1,10 @@
 use rocket::{Request, http::Status};
-use rocket_dyn_templates::{Template, context};
+use rocket_dyn_templates::Template;
 
-use crate::utils::config::CommonConfig;
+use crate::{
+    utils::config::CommonConfig,
+    view::{Layout, LiveUrls},
+};
 
 #[catch(504)]
 pub fn gateway_timeout(_status: Status, req: &Request<'_>) -> Template {
@@ -11,9 +14,6 @@ pub fn gateway_timeout(_status: Status, req: &Request<'_>) -> Template {
         .and_then(|config| config.favicon_path.as_deref());
     Template::render(
         "504",
-        context! {
-            site_name: "504 Gateway Timeout",
-            favicon_path: favicon_path
-        },
+        Layout::new(Some("504 Gateway Timeout"), favicon_path, &LiveUrls::QUERY),
     )
 }
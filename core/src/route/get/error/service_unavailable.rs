@@ -1,7 +1,10 @@
 use rocket::{Request, http::Status};
-use rocket_dyn_templates::{Template, context};
+use rocket_dyn_templates::Template;
 
-use crate::utils::config::CommonConfig;
+use crate::{
+    utils::config::CommonConfig,
+    view::{Layout, LiveUrls},
+};
 
 #[catch(503)]
 pub fn service_unavailable(_status: Status, req: &Request<'_>) -> Template {
@@ -11,9 +14,10 @@ pub fn service_unavailable(_status: Status, req: &Request<'_>) -> Template {
         .and_then(|config| config.favicon_path.as_deref());
     Template::render(
         "503",
-        context! {
-            site_name: "503 Service Unavailable",
-            favicon_path: favicon_path
-        },
+        Layout::new(
+            Some("503 Service Unavailable"),
+            favicon_path,
+            &LiveUrls::QUERY,
+        ),
     )
 }
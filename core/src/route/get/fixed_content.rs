@@ -1,69 +1,100 @@
 use rocket::{State, futures::TryFutureExt, http::Status};
-use rocket_dyn_templates::{Template, context};
-use rust_blog::utils::utc_to_jst;
+use rocket_dyn_templates::Metadata;
 use sea_orm::DatabaseConnection;
-use serde_json::json;
 
 use crate::{
-    repository::{article::get_latest_articles, fixed_content::get_fixed_content_by_slug},
-    utils::{
-        config::CommonConfig,
-        cut_out_string,
-        markdown::{markdown_to_html, markdown_to_text},
+    entity::{article, fixed_content},
+    repository::{
+        article::get_latest_articles, fixed_content::get_fixed_content_by_slug, image::get_images,
     },
+    route::{
+        conditional::{Conditional, Validators, newest, revision},
+        page_cache::{PageRequest, RenderedPage},
+    },
+    utils::{asset::AssetManifest, config::CommonConfig, markdown::image_urls},
+    view::{LiveUrls, View},
 };
 
 #[get("/<slug>")]
 pub async fn fixed_content_detail(
     config: &State<CommonConfig>,
+    assets: &State<AssetManifest>,
     db: &State<DatabaseConnection>,
+    request: PageRequest<'_>,
     slug: &str,
-) -> Result<Template, Status> {
-    let conn = db.inner();
-    let maybe = get_fixed_content_by_slug(conn, slug)
-        .map_err(|_| Status::InternalServerError)
-        .await?
-        .ok_or(Status::NotFound);
-
-    let fixed_content = match maybe {
-        Ok(model) => model,
-        Err(_) => return Err(Status::NotFound),
-    };
-
-    let content = markdown_to_html(&fixed_content.content);
-    let excerpt = match fixed_content.excerpt.as_ref() {
-        Some(value) => value.clone(),
-        None => cut_out_string(&markdown_to_text(&fixed_content.content), 100),
+) -> Result<Conditional<RenderedPage>, Status> {
+    let load = async {
+        let fixed_content = get_fixed_content_by_slug(db, slug)
+            .map_err(|_| Status::InternalServerError)
+            .await?
+            .ok_or(Status::NotFound)?;
+        let latest_articles = get_latest_articles(db, 5)
+            .await
+            .map_err(|_| Status::InternalServerError)?;
+        Ok((fixed_content, latest_articles))
     };
+    let metadata = &request.metadata;
+    request
+        .respond_loaded(
+            db,
+            load,
+            |(fixed_content, latest_articles)| {
+                fixed_content_validators(fixed_content, latest_articles)
+            },
+            |(fixed_content, latest_articles)| async move {
+                render_fixed_content_detail(
+                    config,
+                    assets,
+                    db,
+                    metadata,
+                    &fixed_content,
+                    &latest_articles,
+                )
+                .await
+            },
+        )
+        .await
+}
 
-    let latest_articles: Vec<_> = get_latest_articles(db, 5)
+async fn render_fixed_content_detail(
+    config: &CommonConfig,
+    assets: &AssetManifest,
+    db: &DatabaseConnection,
+    metadata: &Metadata<'_>,
+    fixed_content: &fixed_content::Model,
+    latest_articles: &[article::Model],
+) -> Result<RenderedPage, Status> {
+    let images = get_images(db, &image_urls(&fixed_content.content, None))
         .await
-        .map_err(|_| Status::InternalServerError)?
-        .into_iter()
-        .map(|model| {
-            let slug = model.slug;
-            json!({
-                "title":      model.title,
-                "slug":       slug.clone(),
-                "url":        format!("/posts/{slug}"),
-            })
-        })
-        .collect();
+        .unwrap_or_default();
 
-    Ok(Template::render(
+    let view = View::new(config, &images, assets, &LiveUrls::QUERY);
+    RenderedPage::render(
+        metadata,
         "about",
-        context! {
-            site_name: &config.site_name,
-            favicon_path: &config.favicon_path,
-            tags_url: "/tags",
-            categories_url: "/categories",
-            about_url: "/about",
-            title: fixed_content.title,
-            excerpt: excerpt,
-            content_html: content,
-            created_at: utc_to_jst(fixed_content.created_at),
-            updated_at: utc_to_jst(fixed_content.updated_at),
-            latest_articles: latest_articles,
-        },
-    ))
+        view.fixed_content(fixed_content, latest_articles),
+    )
+}
+
+/// The page itself and the latest articles listed beside it.
+fn fixed_content_validators(
+    fixed_content: &fixed_content::Model,
+    latest_articles: &[article::Model],
+) -> Validators {
+    let parts: Vec<String> = [revision(
+        fixed_content.content_hash.as_deref(),
+        fixed_content.updated_at,
+    )]
+    .into_iter()
+    .chain(
+        latest_articles
+            .iter()
+            .map(|latest| revision(latest.content_hash.as_deref(), latest.updated_at)),
+    )
+    .collect();
+    let last_modified = newest(
+        std::iter::once(fixed_content.updated_at)
+            .chain(latest_articles.iter().map(|latest| latest.updated_at)),
+    );
+    Validators::new(parts.iter().map(String::as_bytes), last_modified)
 }
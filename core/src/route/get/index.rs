@@ -1,92 +1,104 @@
 use rocket::{State, http::Status};
-use rocket_dyn_templates::{Template, context};
+use rocket_dyn_templates::Metadata;
 use sea_orm::DatabaseConnection;
-use serde_json::json;
 
 use crate::{
     domain::{
         page::{Page, PageInfo},
         query::{PagingQuery, index::IndexQuery},
     },
-    repository::article::{ArticlePeriod, get_all_articles, get_article_periods},
-    utils::{config::CommonConfig, cut_out_string, markdown::markdown_to_text, utc_to_jst},
+    repository::{
+        article::{ArticlePeriod, get_all_articles, get_article_periods, get_article_terms},
+        image::get_images,
+    },
+    route::{
+        conditional::{Conditional, listing_validators},
+        page_cache::{PageRequest, RenderedPage},
+    },
+    utils::{asset::AssetManifest, config::CommonConfig},
+    view::{LiveUrls, View, icatch_path},
 };
 
-#[derive(Clone, Copy)]
-enum IndexUrlMode {
-    Query,
-    Archive,
-}
-
-fn build_index_url(
-    page: u64,
-    per: u64,
-    period: Option<ArticlePeriod>,
-    mode: IndexUrlMode,
-) -> String {
-    match (mode, period) {
-        (IndexUrlMode::Archive, Some(period)) if page <= 1 => {
-            format!("/archive/{}/{:02}", period.year, period.month)
-        }
-        (IndexUrlMode::Archive, Some(period)) => {
-            format!("/archive/{}/{:02}/page/{page}", period.year, period.month)
-        }
-        (_, Some(period)) => format!(
-            "/?page={page}&per={per}&year={}&month={}",
-            period.year, period.month
-        ),
-        _ => format!("/?page={page}&per={per}"),
-    }
-}
-
 #[get("/?<query..>")]
 pub async fn index(
     config: &State<CommonConfig>,
+    assets: &State<AssetManifest>,
     db: &State<DatabaseConnection>,
+    request: PageRequest<'_>,
     query: Option<IndexQuery>,
-) -> Result<Template, Status> {
-    render_index(config, db, query, IndexUrlMode::Query).await
+) -> Result<Conditional<RenderedPage>, Status> {
+    let render = render_index(
+        config,
+        assets,
+        db,
+        &request.metadata,
+        query,
+        LiveUrls::QUERY,
+    );
+    request.respond(db, render).await
 }
 
 #[get("/archive/<year>/<month>")]
 pub async fn index_archive(
     config: &State<CommonConfig>,
+    assets: &State<AssetManifest>,
     db: &State<DatabaseConnection>,
+    request: PageRequest<'_>,
     year: i32,
     month: u32,
-) -> Result<Template, Status> {
+) -> Result<Conditional<RenderedPage>, Status> {
     let query = IndexQuery {
         page: None,
         per: None,
         year: Some(year),
         month: Some(month),
     };
-    render_index(config, db, Some(query), IndexUrlMode::Archive).await
+    let render = render_index(
+        config,
+        assets,
+        db,
+        &request.metadata,
+        Some(query),
+        LiveUrls::ARCHIVE,
+    );
+    request.respond(db, render).await
 }
 
 #[get("/archive/<year>/<month>/page/<page>")]
 pub async fn index_archive_page(
     config: &State<CommonConfig>,
+    assets: &State<AssetManifest>,
     db: &State<DatabaseConnection>,
+    request: PageRequest<'_>,
     year: i32,
     month: u32,
     page: u64,
-) -> Result<Template, Status> {
+) -> Result<Conditional<RenderedPage>, Status> {
     let query = IndexQuery {
         page: Some(page),
         per: None,
         year: Some(year),
         month: Some(month),
     };
-    render_index(config, db, Some(query), IndexUrlMode::Archive).await
+    let render = render_index(
+        config,
+        assets,
+        db,
+        &request.metadata,
+        Some(query),
+        LiveUrls::ARCHIVE,
+    );
+    request.respond(db, render).await
 }
 
 async fn render_index(
-    config: &State<CommonConfig>,
-    db: &State<DatabaseConnection>,
+    config: &CommonConfig,
+    assets: &AssetManifest,
+    db: &DatabaseConnection,
+    metadata: &Metadata<'_>,
     query: Option<IndexQuery>,
-    mode: IndexUrlMode,
-) -> Result<Template, Status> {
+    urls: LiveUrls,
+) -> Result<RenderedPage, Status> {
     let query = query.unwrap_or(IndexQuery::new());
     let page = Page::new_from_query(&query);
     let has_period_query = query.year.is_some() || query.month.is_some();
@@ -94,98 +106,55 @@ async fn render_index(
         (Some(year), Some(month)) => ArticlePeriod::new(year, month),
         _ => None,
     };
-    if matches!(mode, IndexUrlMode::Archive) && has_period_query && selected_period.is_none() {
+    if urls.archive_paths() && has_period_query && selected_period.is_none() {
         return Err(Status::NotFound);
     }
     let (models, page_info) = if has_period_query && selected_period.is_none() {
         (Vec::new(), PageInfo::new(page.normalize(50), 0))
     } else {
-        get_all_articles(db.inner(), page, selected_period)
+        get_all_articles(db, page, selected_period)
             .await
             .map_err(|_| Status::InternalServerError)?
     };
-    let prev_url = if page_info.has_prev {
-        build_index_url(page_info.prev_page, page_info.per, selected_period, mode)
-    } else {
-        String::new()
-    };
-    let next_url = if page_info.has_next {
-        build_index_url(page_info.next_page, page_info.per, selected_period, mode)
-    } else {
-        String::new()
-    };
-    let default_icatch_path = config.default_icatch_path.clone().unwrap_or_default();
-    let periods = get_article_periods(db.inner(), None)
+    let terms = get_article_terms(db, &models)
         .await
-        .unwrap_or_default();
-    let period_links: Vec<_> = periods
+        .map_err(|_| Status::InternalServerError)?;
+    let icatch_paths: Vec<String> = models
         .iter()
-        .map(|period| {
-            json!({
-                "label": format!("{}/{:02}", period.year, period.month),
-                "href": build_index_url(1, page.per, Some(*period), IndexUrlMode::Archive),
-                "is_selected": selected_period == Some(*period),
-            })
-        })
-        .collect();
-    let articles: Vec<_> = models
-        .into_iter()
-        .map(|m| {
-            let excerpt = match m.excerpt.as_ref() {
-                Some(value) => value.clone(),
-                None => cut_out_string(&markdown_to_text(&m.content), 100),
-            };
-            let slug = m.slug;
-            let icatch_path = m
-                .icatch_path
-                .clone()
-                .unwrap_or_else(|| default_icatch_path.clone());
-            json!({
-                "title":      m.title,
-                "slug":       slug.clone(),
-                "url":        format!("/posts/{slug}"),
-                "excerpt":    excerpt,
-                "icatch_path": icatch_path,
-                "created_at": utc_to_jst(m.created_at),
-                "updated_at": utc_to_jst(m.updated_at),
-            })
-        })
+        .map(|article| icatch_path(config, article))
         .collect();
+    let images = get_images(db, &icatch_paths).await.unwrap_or_default();
+    let periods = get_article_periods(db, None).await.unwrap_or_default();
 
-    Ok(Template::render(
+    let validators = listing_validators(
+        &models,
+        Some(&terms),
+        &[format!("{page_info:?}"), format!("{periods:?}")],
+    );
+    let view = View::new(config, &images, assets, &urls);
+    let page = RenderedPage::render(
+        metadata,
         "index",
-        context! {
-            site_name: &config.site_name,
-            favicon_path: &config.favicon_path,
-            tags_url: "/tags",
-            categories_url: "/categories",
-            about_url: "/about",
-            articles:  articles,
-            page: page_info.current_page,
-            per: page_info.per,
-            total_pages: page_info.total_pages,
-            has_prev: page_info.has_prev,
-            has_next: page_info.has_next,
-            prev_page: page_info.prev_page,
-            next_page: page_info.next_page,
-            prev_url: prev_url,
-            next_url: next_url,
-            selected_period: selected_period.map(|period| format!("{}/{:02}", period.year, period.month)),
-            period_links: period_links,
-        },
-    ))
+        view.index(&models, &terms, &page_info, selected_period, &periods),
+    )?;
+    Ok(page.with_validators(validators))
 }
 
 #[cfg(test)]
 mod tests {
     use super::{index, index_archive, index_archive_page};
-    use crate::utils::config::CommonConfig;
-    use rocket::http::Status;
+    use crate::repository::data_version::bump_data_version;
+    use crate::route::{get::status::page_cache_stats, page_cache::PageCache, templates};
+    use crate::utils::{asset::AssetManifest, config::CommonConfig};
+    use rocket::http::{Header, Status};
     use rocket::local::asynchronous::Client;
-    use rocket_dyn_templates::Template;
     use sea_orm::{ConnectionTrait, Database, DatabaseConnection, DbBackend, Statement};
 
     async fn client_with_db(db: sea_orm::DatabaseConnection) -> Client {
+        client_with_cache(db, PageCache::new(0)).await
+    }
+
+    async fn client_with_cache(db: sea_orm::DatabaseConnection, cache: PageCache) -> Client {
         let rocket =
             rocket::custom(rocket::Config::figment().merge(("template_dir", "../templates")))
                 .manage(db)
@@ -194,8 +163,13 @@ mod tests {
                     default_icatch_path: Some("/default.png".to_string()),
                     favicon_path: Some("/favicon.ico".to_string()),
                 })
-                .attach(Template::fairing())
-                .mount("/", routes![index, index_archive, index_archive_page]);
+                .manage(AssetManifest::default())
+                .manage(cache)
+                .attach(templates(AssetManifest::default()))
+                .mount(
+                    "/",
+                    routes![index, index_archive, index_archive_page, page_cache_stats],
+                );
         Client::tracked(rocket)
             .await
             .expect("failed to build client")
@@ -207,7 +181,7 @@ mod tests {
             .expect("failed to connect sqlite memory");
         db.execute(Statement::from_string(
             DbBackend::Sqlite,
-            "CREATE TABLE article (id INTEGER PRIMARY KEY, title TEXT NOT NULL, slug TEXT NOT NULL, excerpt TEXT NULL, content TEXT NOT NULL, created_at TEXT NOT NULL, updated_at TEXT NOT NULL, icatch_path TEXT NULL);",
+            "CREATE TABLE article (id INTEGER PRIMARY KEY, title TEXT NOT NULL, slug TEXT NOT NULL, excerpt TEXT NULL, content TEXT NOT NULL, created_at TEXT NOT NULL, updated_at TEXT NOT NULL, icatch_path TEXT NULL, content_hash TEXT NULL);",
         ))
         .await
         .expect("failed to create article table");
@@ -224,9 +198,113 @@ mod tests {
         ))
         .await
         .expect("failed to insert articles");
+
+        for sql in [
+            "CREATE TABLE tag (id INTEGER PRIMARY KEY, name TEXT NOT NULL, slug TEXT NOT NULL);",
+            "CREATE TABLE category (id INTEGER PRIMARY KEY, name TEXT NOT NULL, slug TEXT NOT NULL);",
+            "CREATE TABLE article_tag (article_id INTEGER NOT NULL, tag_id INTEGER NOT NULL);",
+            "CREATE TABLE article_category (article_id INTEGER NOT NULL, category_id INTEGER NOT NULL);",
+            "INSERT INTO tag (id, name, slug) VALUES (1, 'Rust', 'rust'), (2, 'Web', 'web');",
+            "INSERT INTO category (id, name, slug) VALUES (1, 'Tech', 'tech');",
+            "INSERT INTO article_tag (article_id, tag_id) VALUES (1, 2), (1, 1), (2, 1), (4, 2);",
+            "INSERT INTO article_category (article_id, category_id) VALUES (2, 1);",
+        ] {
+            db.execute(Statement::from_string(DbBackend::Sqlite, sql))
+                .await
+                .expect("failed to prepare terms");
+        }
         db
     }
 
+    async fn body(client: &Client, uri: &str) -> String {
+        client
+            .get(uri)
+            .dispatch()
+            .await
+            .into_string()
+            .await
+            .expect("response body should exist")
+    }
+
+    #[rocket::async_test]
+    async fn index_is_cached_per_query_until_the_data_version_changes() {
+        let db = prepare_index_db().await;
+        db.execute(Statement::from_string(
+            DbBackend::Sqlite,
+            "CREATE TABLE data_version (id INTEGER PRIMARY KEY, version BIGINT NOT NULL);",
+        ))
+        .await
+        .expect("failed to create data_version table");
+        let client = client_with_cache(db, PageCache::new(8)).await;
+        let db = client
+            .rocket()
+            .state::<DatabaseConnection>()
+            .expect("db should be managed");
+
+        assert!(
+            body(&client, "/?year=2025&month=12")
+                .await
+                .contains("Dec 1")
+        );
+        db.execute(Statement::from_string(
+            DbBackend::Sqlite,
+            "UPDATE article SET title = 'Renamed' WHERE id = 1;",
+        ))
+        .await
+        .expect("failed to rename article");
+        assert!(
+            body(&client, "/?year=2025&month=12")
+                .await
+                .contains("Dec 1")
+        );
+        assert!(
+            body(&client, "/?year=2025&month=12&page=1")
+                .await
+                .contains("Renamed")
+        );
+
+        bump_data_version(db).await.expect("failed to bump");
+        assert!(
+            body(&client, "/?year=2025&month=12")
+                .await
+                .contains("Renamed")
+        );
+        let stats = body(&client, "/_status/page-cache").await;
+        assert!(stats.contains(r#""hits":1"#), "{stats}");
+        assert!(stats.contains(r#""misses":3"#), "{stats}");
+        assert!(stats.contains(r#""data_version":1"#), "{stats}");
+    }
+
+    #[rocket::async_test]
+    async fn index_answers_conditional_requests_from_the_listed_articles() {
+        let db = prepare_index_db().await;
+        let client = client_with_db(db).await;
+
+        let response = client.get("/?year=2025&month=12").dispatch().await;
+        assert_eq!(
+            response.headers().get_one("Last-Modified"),
+            Some("Mon, 15 Dec 2025 00:00:00 GMT")
+        );
+        let etag = response
+            .headers()
+            .get_one("ETag")
+            .expect("listing should have an ETag")
+            .to_string();
+
+        let response = client
+            .get("/?year=2025&month=12")
+            .header(Header::new("If-None-Match", etag.clone()))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotModified);
+        let response = client
+            .get("/?year=2025&month=11")
+            .header(Header::new("If-None-Match", etag))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+    }
+
     #[rocket::async_test]
     async fn index_filters_articles_by_year_and_month() {
         let db = prepare_index_db().await;
@@ -245,6 +323,24 @@ mod tests {
         assert!(!body.contains("Future"));
     }
 
+    #[rocket::async_test]
+    async fn index_lists_tags_and_categories_of_each_article() {
+        let db = prepare_index_db().await;
+        let client = client_with_db(db).await;
+
+        let response = client.get("/?year=2025&month=12").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let body = response
+            .into_string()
+            .await
+            .expect("response body should exist");
+
+        assert_eq!(body.matches("#Rust").count(), 2);
+        assert_eq!(body.matches("#Web").count(), 1);
+        assert!(body.find("#Rust") < body.find("#Web"));
+        assert!(body.contains("&#x2F;category&#x2F;tech"));
+    }
+
     #[rocket::async_test]
     async fn index_uses_server_urls_instead_of_static_archive_urls() {
         let db = prepare_index_db().await;
@@ -1,23 +1,121 @@
-use rocket::http::ContentType;
+use std::path::PathBuf;
 
-const BULMA_CSS: &str = include_str!(concat!(
-    env!("CARGO_MANIFEST_DIR"),
-    "/assets/bulma.min.css"
-));
-const SITE_CSS: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/site.css"));
-const NAV_JS: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/nav.js"));
+use rocket::{
+    State,
+    fs::NamedFile,
+    http::{ContentType, Header},
+};
+
+use crate::{
+    route::compression::StaticBody,
+    utils::asset::{AssetManifest, AssetSource, EMBEDDED_ASSETS},
+};
+
+#[derive(Responder)]
+pub enum AssetResponse {
+    Embedded(StaticBody),
+    File(NamedFile),
+}
+
+/// An asset under its fingerprinted URL, which changes with its contents.
+#[derive(Responder)]
+pub struct FingerprintedAsset {
+    asset: AssetResponse,
+    cache_control: Header<'static>,
+}
+
+fn embedded(url: &str, bytes: &'static [u8]) -> StaticBody {
+    let content_type = url
+        .rsplit_once('.')
+        .and_then(|(_, extension)| ContentType::from_extension(extension))
+        .unwrap_or(ContentType::Binary);
+    StaticBody {
+        content_type,
+        bytes,
+    }
+}
+
+fn embedded_asset(url: &str) -> StaticBody {
+    let asset = EMBEDDED_ASSETS
+        .iter()
+        .find(|asset| asset.url == url)
+        .expect("embedded asset is listed in EMBEDDED_ASSETS");
+    embedded(asset.url, asset.bytes)
+}
 
 #[get("/css/bulma.min.css")]
-pub fn bulma_css() -> (ContentType, &'static str) {
-    (ContentType::CSS, BULMA_CSS)
+pub fn bulma_css() -> StaticBody {
+    embedded_asset("/css/bulma.min.css")
 }
 
 #[get("/css/site.css")]
-pub fn site_css() -> (ContentType, &'static str) {
-    (ContentType::CSS, SITE_CSS)
+pub fn site_css() -> StaticBody {
+    embedded_asset("/css/site.css")
 }
 
 #[get("/js/nav.js")]
-pub fn nav_js() -> (ContentType, &'static str) {
-    (ContentType::JavaScript, NAV_JS)
+pub fn nav_js() -> StaticBody {
+    embedded_asset("/js/nav.js")
+}
+
+/// Stylesheets, scripts and images under their fingerprinted URL. Ranked after
+/// everything else so that it only sees URLs no other route or file server knows.
+#[get("/<path..>", rank = 20)]
+pub async fn fingerprinted_asset(
+    assets: &State<AssetManifest>,
+    path: PathBuf,
+) -> Option<FingerprintedAsset> {
+    let url = format!("/{}", path.to_string_lossy().replace('\\', "/"));
+    let asset = assets.get(&url)?;
+    let asset = match &asset.source {
+        AssetSource::Embedded(bytes) => AssetResponse::Embedded(embedded(&asset.url, bytes)),
+        AssetSource::File(file) => AssetResponse::File(NamedFile::open(file).await.ok()?),
+    };
+    Some(FingerprintedAsset {
+        asset,
+        cache_control: Header::new("Cache-Control", "public, max-age=31556952, immutable"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bulma_css, fingerprinted_asset};
+    use crate::utils::asset::{AssetManifest, AssetSource};
+    use rocket::http::{ContentType, Status};
+    use rocket::local::asynchronous::Client;
+
+    #[rocket::async_test]
+    async fn serves_embedded_assets_under_plain_and_fingerprinted_urls() {
+        let mut assets = AssetManifest::default();
+        assets.insert(
+            "/css/site.css",
+            b"body {}",
+            AssetSource::Embedded(b"body {}"),
+        );
+        let fingerprinted = assets.url("/css/site.css").to_string();
+        let rocket = rocket::build()
+            .manage(assets)
+            .mount("/", routes![bulma_css, fingerprinted_asset]);
+        let client = Client::tracked(rocket)
+            .await
+            .expect("failed to build client");
+
+        let response = client.get(fingerprinted).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(ContentType::CSS));
+        assert_eq!(
+            response.headers().get_one("Cache-Control"),
+            Some("public, max-age=31556952, immutable")
+        );
+        assert_eq!(response.into_string().await.as_deref(), Some("body {}"));
+
+        let response = client.get("/css/bulma.min.css").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.headers().get_one("Cache-Control"), None);
+        let response = client
+            .get("/css/site.0000000000000000.css")
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+    }
 }
@@ -0,0 +1,24 @@
+use rocket::{Build, Rocket, State, serde::json::Json};
+
+use crate::route::page_cache::{PageCache, PageCacheStats};
+
+/// Mounts the status routes in debug builds, and in release builds only with
+/// `PAGE_CACHE_STATUS` set, as nothing else keeps them from being public.
+pub fn stage(rocket: Rocket<Build>) -> Rocket<Build> {
+    let enabled = std::env::var("PAGE_CACHE_STATUS").is_ok_and(|value| {
+        matches!(
+            value.trim().to_ascii_lowercase().as_str(),
+            "1" | "true" | "yes" | "on"
+        )
+    });
+    if !cfg!(debug_assertions) && !enabled {
+        return rocket;
+    }
+    rocket.mount("/", routes![page_cache_stats])
+}
+
+/// Hit and miss counts of the rendered-page cache, for monitoring.
+#[get("/_status/page-cache")]
+pub fn page_cache_stats(cache: &State<PageCache>) -> Json<PageCacheStats> {
+    Json(cache.stats())
+}
@@ -1,128 +1,87 @@
 use rocket::{State, http::Status};
-use rocket_dyn_templates::{Template, context};
 use sea_orm::{DatabaseConnection, DbErr};
-use serde_json::json;
 
 use crate::{
     domain::{
-        page::{Page, PageInfo},
+        page::Page,
         query::{PagingQuery, tag::TagQuery},
     },
-    repository::{article::get_articles_by_tag_slug, tag::get_all_tags},
-    utils::{config::CommonConfig, cut_out_string, markdown::markdown_to_text, utc_to_jst},
+    repository::{article::get_articles_by_tag_slug, image::get_images, tag::get_all_tags},
+    route::{
+        conditional::{Conditional, listing_validators},
+        page_cache::{PageRequest, RenderedPage},
+    },
+    utils::{asset::AssetManifest, config::CommonConfig, image::ImageIndex},
+    view::{DEFAULT_SORT_KEY, LiveUrls, Taxonomy, View, icatch_path},
 };
 
-fn sort_url(slug: &str, sort_key: &str) -> String {
-    format!("/tag/{slug}?sort_key={sort_key}")
-}
-
 #[get("/tags")]
 pub async fn tag_list(
     config: &State<CommonConfig>,
+    assets: &State<AssetManifest>,
     db: &State<DatabaseConnection>,
-) -> Result<Template, Status> {
-    let models = get_all_tags(db)
-        .await
-        .map_err(|_| Status::InternalServerError)?;
-    let tags = models
-        .iter()
-        .map(|tag| {
-            let slug = tag.slug.clone();
-            json!({
-                "name": tag.name.clone(),
-                "slug": slug.clone(),
-                "url": format!("/tag/{slug}")
-            })
-        })
-        .collect::<Vec<_>>();
-    Ok(Template::render(
-        "tags",
-        context! {
-            site_name: &config.site_name,
-            favicon_path: &config.favicon_path,
-            tags_url: "/tags",
-            categories_url: "/categories",
-            about_url: "/about",
-            tags
-        },
-    ))
+    request: PageRequest<'_>,
+) -> Result<RenderedPage, Status> {
+    let render = async {
+        let tags = get_all_tags(db)
+            .await
+            .map_err(|_| Status::InternalServerError)?;
+        let images = ImageIndex::new();
+        let view = View::new(config, &images, assets, &LiveUrls::QUERY);
+        RenderedPage::render(&request.metadata, "tags", view.tag_list(&tags))
+    };
+    request.get_or_render(db, render).await
 }
 
 #[get("/tag/<slug>?<query..>")]
 pub async fn tag_detail(
     config: &State<CommonConfig>,
+    assets: &State<AssetManifest>,
     db: &State<DatabaseConnection>,
+    request: PageRequest<'_>,
     slug: &str,
     query: Option<TagQuery>,
-) -> Result<Template, Status> {
-    let query = query.unwrap_or(TagQuery::new());
-    let page = Page::new_from_query(&query);
-    let sort_key = query.sort_key.unwrap_or_else(|| "created_at".to_string());
-    match get_articles_by_tag_slug(&db, page, slug, &sort_key).await {
-        Ok((articles, page_info)) => {
-            let base_path = "/tag/".to_owned() + slug;
-            let prev_url = PageInfo::get_prev_url(&page_info, &base_path, Some(&sort_key));
-            let next_url = PageInfo::get_next_url(&page_info, &base_path, Some(&sort_key));
-            let default_icatch_path = config.default_icatch_path.clone().unwrap_or_default();
-            Ok(Template::render(
-                "tag",
-                context! {
-                    site_name: &config.site_name,
-                    favicon_path: &config.favicon_path,
-                    tags_url: "/tags",
-                    categories_url: "/categories",
-                    about_url: "/about",
-                    tag_slug: slug,
-                    sort_key: sort_key,
-                    sort_created_url: sort_url(slug, "created_at"),
-                    sort_updated_url: sort_url(slug, "updated_at"),
-                    articles: articles.iter().map(|article| {
-                        let icatch_path = article
-                            .icatch_path
-                            .clone()
-                            .unwrap_or_else(|| default_icatch_path.clone());
-                        let excerpt = match article.excerpt.as_ref() {
-                            Some(value) => value.clone(),
-                            None => cut_out_string(&markdown_to_text(&article.content), 100),
-                        };
-                        let slug = article.slug.clone();
-                        json!({
-                            "title": article.title.clone(),
-                            "slug": slug.clone(),
-                            "url": format!("/posts/{slug}"),
-                            "icatch_path": icatch_path,
-                            "excerpt": excerpt,
-                            "created_at": utc_to_jst(article.created_at),
-                        })
-                    }).collect::<Vec<_>>(),
-                    page: page_info.current_page,
-                    per: page_info.per,
-                    total_pages: page_info.total_pages,
-                    has_prev: page_info.has_prev,
-                    has_next: page_info.has_next,
-                    prev_page: page_info.prev_page,
-                    next_page: page_info.next_page,
-                    prev_url: prev_url,
-                    next_url: next_url,
-                },
-            ))
-        }
-        Err(DbErr::RecordNotFound(_)) => Err(Status::NotFound),
-        Err(e) => {
-            error!("tag_detail error for {}: {}", slug, e);
-            Err(Status::InternalServerError)
+) -> Result<Conditional<RenderedPage>, Status> {
+    let render = async {
+        let query = query.unwrap_or(TagQuery::new());
+        let page = Page::new_from_query(&query);
+        let sort_key = query
+            .sort_key
+            .unwrap_or_else(|| DEFAULT_SORT_KEY.to_string());
+        match get_articles_by_tag_slug(db, page, slug, &sort_key).await {
+            Ok((articles, page_info)) => {
+                let icatch_paths: Vec<String> = articles
+                    .iter()
+                    .map(|article| icatch_path(config, article))
+                    .collect();
+                let images = get_images(db, &icatch_paths).await.unwrap_or_default();
+                let validators = listing_validators(&articles, None, &[format!("{page_info:?}")]);
+                let view = View::new(config, &images, assets, &LiveUrls::QUERY);
+                let page = RenderedPage::render(
+                    &request.metadata,
+                    "tag",
+                    view.term(Taxonomy::Tag, slug, &sort_key, &articles, &page_info),
+                )?;
+                Ok(page.with_validators(validators))
+            }
+            Err(DbErr::RecordNotFound(_)) => Err(Status::NotFound),
+            Err(e) => {
+                error!("tag_detail error for {}: {}", slug, e);
+                Err(Status::InternalServerError)
+            }
         }
-    }
+    };
+    request.respond(db, render).await
 }
 
 #[cfg(test)]
 mod tests {
     use super::tag_detail;
     use crate::entity::tag;
-    use crate::utils::config::CommonConfig;
+    use crate::route::{page_cache::PageCache, templates};
+    use crate::utils::{asset::AssetManifest, config::CommonConfig};
     use rocket::http::Status;
     use rocket::local::asynchronous::Client;
-    use rocket_dyn_templates::Template;
     use sea_orm::{
         ConnectionTrait, Database, DatabaseBackend, DatabaseConnection, DbBackend, MockDatabase,
         Statement,
@@ -137,7 +96,9 @@ mod tests {
                     default_icatch_path: Some("/default.png".to_string()),
                     favicon_path: Some("/favicon.ico".to_string()),
                 })
-                .attach(Template::fairing())
+                .manage(AssetManifest::default())
+                .manage(PageCache::new(0))
+                .attach(templates(AssetManifest::default()))
                 .mount("/", routes![tag_detail]);
         Client::tracked(rocket)
             .await
@@ -156,7 +117,7 @@ mod tests {
         .expect("failed to create tag table");
         db.execute(Statement::from_string(
             DbBackend::Sqlite,
-            "CREATE TABLE article (id INTEGER PRIMARY KEY, title TEXT NOT NULL, slug TEXT NOT NULL, excerpt TEXT NULL, content TEXT NOT NULL, created_at TEXT NOT NULL, updated_at TEXT NOT NULL, icatch_path TEXT NULL);",
+            "CREATE TABLE article (id INTEGER PRIMARY KEY, title TEXT NOT NULL, slug TEXT NOT NULL, excerpt TEXT NULL, content TEXT NOT NULL, created_at TEXT NOT NULL, updated_at TEXT NOT NULL, icatch_path TEXT NULL, content_hash TEXT NULL);",
         ))
         .await
         .expect("failed to create article table");
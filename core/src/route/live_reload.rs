@@ -0,0 +1,184 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use rocket::fairing::{AdHoc, Fairing, Info, Kind};
+use rocket::http::ContentType;
+use rocket::response::stream::{Event, EventStream};
+use rocket::tokio::time::{self, MissedTickBehavior};
+use rocket::{Build, Request, Response, Rocket, Shutdown, State};
+use sea_orm::DatabaseConnection;
+use walkdir::WalkDir;
+
+use crate::repository::data_version::get_data_version;
+
+const LIVE_RELOAD_JS: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/assets/live_reload.js"
+));
+const SCRIPT_TAG: &str = r#"<script src="/_dev/live-reload.js" defer></script>"#;
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+pub struct LiveReloadConfig {
+    pub template_dir: PathBuf,
+}
+
+/// Reloads open pages when templates or seeded content change. Only attached in debug builds.
+/// The script is injected by a response fairing, so this goes before any fairing that
+/// encodes the body.
+pub fn stage(rocket: Rocket<Build>) -> Rocket<Build> {
+    if !cfg!(debug_assertions) {
+        return rocket;
+    }
+    rocket
+        .attach(InjectScript)
+        .attach(AdHoc::on_ignite("Live Reload", |rocket| async {
+            let template_dir = rocket
+                .figment()
+                .extract_inner::<PathBuf>("template_dir")
+                .unwrap_or_else(|_| PathBuf::from("templates"));
+            rocket
+                .manage(LiveReloadConfig { template_dir })
+                .mount("/", routes![live_reload_js, live_reload_events])
+        }))
+}
+
+#[get("/_dev/live-reload.js")]
+fn live_reload_js() -> (ContentType, &'static str) {
+    (ContentType::JavaScript, LIVE_RELOAD_JS)
+}
+
+#[get("/_dev/live-reload")]
+fn live_reload_events<'a>(
+    config: &'a State<LiveReloadConfig>,
+    db: &'a State<DatabaseConnection>,
+    mut shutdown: Shutdown,
+) -> EventStream![Event + 'a] {
+    EventStream! {
+        let initial = version(&config.template_dir, db).await;
+        let mut interval = time::interval(POLL_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            rocket::tokio::select! {
+                _ = interval.tick() => {}
+                _ = &mut shutdown => break,
+            }
+            if version(&config.template_dir, db).await != initial {
+                yield Event::data("changed").event("reload");
+                break;
+            }
+        }
+    }
+}
+
+async fn version(template_dir: &Path, db: &DatabaseConnection) -> (Option<SystemTime>, usize, i64) {
+    let (modified, count) = templates_version(template_dir);
+    (modified, count, content_version(db).await)
+}
+
+/// Latest modification time and number of files under `dir`.
+fn templates_version(dir: &Path) -> (Option<SystemTime>, usize) {
+    let mut latest = None;
+    let mut count = 0;
+    for entry in WalkDir::new(dir).into_iter().filter_map(Result::ok) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        count += 1;
+        let modified = entry.metadata().ok().and_then(|m| m.modified().ok());
+        latest = latest.max(modified);
+    }
+    (latest, count)
+}
+
+/// The data version the seeder bumps after every run that writes.
+async fn content_version(db: &DatabaseConnection) -> i64 {
+    get_data_version(db).await.unwrap_or_else(|e| {
+        eprintln!("live reload: {e}");
+        0
+    })
+}
+
+struct InjectScript;
+
+#[rocket::async_trait]
+impl Fairing for InjectScript {
+    fn info(&self) -> Info {
+        Info {
+            name: "Live Reload Script",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, _req: &'r Request<'_>, res: &mut Response<'r>) {
+        if res.content_type() != Some(ContentType::HTML) {
+            return;
+        }
+        let Ok(body) = res.body_mut().to_string().await else {
+            return;
+        };
+        res.set_sized_body(None, std::io::Cursor::new(inject_script(body)));
+    }
+}
+
+fn inject_script(mut html: String) -> String {
+    match html.rfind("</body>") {
+        Some(index) => html.insert_str(index, SCRIPT_TAG),
+        None => html.push_str(SCRIPT_TAG),
+    }
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SCRIPT_TAG, content_version, inject_script, templates_version};
+    use crate::repository::data_version::bump_data_version;
+    use sea_orm::{ConnectionTrait, Database, DbBackend, Statement};
+    use std::fs;
+
+    #[test]
+    fn inject_script_goes_before_closing_body() {
+        let html = inject_script("<html><body><p>x</p></body></html>".to_string());
+        assert_eq!(
+            html,
+            format!("<html><body><p>x</p>{SCRIPT_TAG}</body></html>")
+        );
+    }
+
+    #[test]
+    fn inject_script_appends_without_body_tag() {
+        assert_eq!(
+            inject_script("<p>x</p>".to_string()),
+            format!("<p>x</p>{SCRIPT_TAG}")
+        );
+    }
+
+    #[test]
+    fn templates_version_changes_when_a_file_is_added() {
+        let dir = std::env::temp_dir().join(format!("live_reload_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("failed to create temp dir");
+        fs::write(dir.join("a.html.tera"), "a").expect("failed to write template");
+        let before = templates_version(&dir);
+        fs::write(dir.join("b.html.tera"), "b").expect("failed to write template");
+        let after = templates_version(&dir);
+        assert_eq!(before.1, 1);
+        assert_ne!(before, after);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[rocket::async_test]
+    async fn content_version_follows_the_data_version() {
+        let db = Database::connect("sqlite::memory:")
+            .await
+            .expect("failed to connect sqlite memory");
+        db.execute(Statement::from_string(
+            DbBackend::Sqlite,
+            "CREATE TABLE data_version (id INTEGER PRIMARY KEY, version BIGINT NOT NULL);",
+        ))
+        .await
+        .expect("failed to create data_version table");
+
+        let before = content_version(&db).await;
+        bump_data_version(&db).await.expect("failed to bump");
+        assert_ne!(content_version(&db).await, before);
+    }
+}
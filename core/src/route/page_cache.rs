@@ -0,0 +1,624 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use chrono::{DateTime, Utc};
+use rocket::Request;
+use rocket::http::{ContentType, Status};
+use rocket::request::{FromRequest, Outcome};
+use rocket::response::{self, Responder};
+use rocket::serde::Serialize;
+use rocket_dyn_templates::Metadata;
+use sea_orm::DatabaseConnection;
+
+use super::conditional::{Conditional, Preconditions, Validators};
+use crate::repository::{article::get_next_publication, data_version::get_data_version};
+
+/// Query parameters the page handlers read. The others, such as tracking
+/// parameters, render the same page and are left out of the cache key.
+const KEY_PARAMS: [&str; 5] = ["page", "per", "sort_key", "year", "month"];
+
+/// Pages kept when `PAGE_CACHE_CAPACITY` is not set. Debug builds reload
+/// templates on change, so they keep none.
+const DEFAULT_CAPACITY: usize = if cfg!(debug_assertions) { 0 } else { 256 };
+
+/// The cache and templates for the page a request asks for, which path and
+/// query pick together.
+pub struct PageRequest<'r> {
+    cache: &'r PageCache,
+    key: String,
+    preconditions: Preconditions<'r>,
+    pub metadata: Metadata<'r>,
+}
+
+impl PageRequest<'_> {
+    /// See [`PageCache::get_or_render`].
+    pub async fn get_or_render(
+        &self,
+        db: &DatabaseConnection,
+        render: impl Future<Output = Result<RenderedPage, Status>>,
+    ) -> Result<RenderedPage, Status> {
+        self.cache.get_or_render(db, &self.key, render).await
+    }
+
+    /// Like [`PageRequest::get_or_render`], answering `304 Not Modified` when
+    /// the client already has the page.
+    pub async fn respond(
+        &self,
+        db: &DatabaseConnection,
+        render: impl Future<Output = Result<RenderedPage, Status>>,
+    ) -> Result<Conditional<RenderedPage>, Status> {
+        let page = self.get_or_render(db, render).await?;
+        Ok(page.or_not_modified(&self.preconditions))
+    }
+
+    /// Like [`PageRequest::respond`] for pages whose validators come from the
+    /// rows `load` reads. A cached page answers without loading them, and
+    /// rendering is skipped for `304 Not Modified`.
+    pub async fn respond_loaded<T, F>(
+        &self,
+        db: &DatabaseConnection,
+        load: impl Future<Output = Result<T, Status>>,
+        validators: impl FnOnce(&T) -> Validators,
+        render: impl FnOnce(T) -> F,
+    ) -> Result<Conditional<RenderedPage>, Status>
+    where
+        F: Future<Output = Result<RenderedPage, Status>>,
+    {
+        let miss = match self.cache.lookup(db, &self.key).await {
+            Ok(page) => return Ok(page.or_not_modified(&self.preconditions)),
+            Err(miss) => miss,
+        };
+        let rows = load.await?;
+        let validators = validators(&rows);
+        if self.preconditions.not_modified(&validators) {
+            return Ok(Conditional::NotModified(validators));
+        }
+        let page = render(rows).await?.with_validators(validators);
+        self.cache.store(&self.key, miss, &page);
+        Ok(Conditional::Modified(page))
+    }
+}
+
+/// The path and the [`KEY_PARAMS`] of the query, in a fixed order.
+fn cache_key<'a>(path: &str, query: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+    let mut params: Vec<(&str, &str)> = query
+        .into_iter()
+        .filter(|(name, _)| KEY_PARAMS.contains(name))
+        .collect();
+    params.sort_by_key(|(name, _)| *name);
+    if params.is_empty() {
+        return path.to_string();
+    }
+    format!("{path}?{params:?}")
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for PageRequest<'r> {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Some(cache) = req.rocket().state::<PageCache>() else {
+            error!("PageCache is not managed");
+            return Outcome::Error((Status::InternalServerError, ()));
+        };
+        let metadata = match req.guard::<Metadata<'r>>().await {
+            Outcome::Success(metadata) => metadata,
+            Outcome::Error((status, _)) => return Outcome::Error((status, ())),
+            Outcome::Forward(status) => return Outcome::Forward(status),
+        };
+        let preconditions = match req.guard::<Preconditions<'r>>().await {
+            Outcome::Success(preconditions) => preconditions,
+            _ => Preconditions::default(),
+        };
+        Outcome::Success(PageRequest {
+            cache,
+            key: cache_key(
+                req.uri().path().as_str(),
+                req.uri()
+                    .query()
+                    .into_iter()
+                    .flat_map(|query| query.segments()),
+            ),
+            preconditions,
+            metadata,
+        })
+    }
+}
+
+/// HTML of a rendered page, with the validators of pages that have them.
+#[derive(Debug, Clone)]
+pub struct RenderedPage {
+    pub html: String,
+    pub validators: Option<Validators>,
+}
+
+impl RenderedPage {
+    /// Renders `name` the way `Template::render` would.
+    pub fn render(
+        metadata: &Metadata<'_>,
+        name: &'static str,
+        context: impl Serialize,
+    ) -> Result<Self, Status> {
+        let (_, html) = metadata
+            .render(name, context)
+            .ok_or(Status::InternalServerError)?;
+        Ok(RenderedPage {
+            html,
+            validators: None,
+        })
+    }
+
+    pub fn with_validators(self, validators: Validators) -> Self {
+        RenderedPage {
+            validators: Some(validators),
+            ..self
+        }
+    }
+
+    /// `304 Not Modified` in place of the page when the client already has it.
+    pub fn or_not_modified(self, preconditions: &Preconditions<'_>) -> Conditional<Self> {
+        match &self.validators {
+            Some(validators) if preconditions.not_modified(validators) => {
+                Conditional::NotModified(validators.clone())
+            }
+            _ => Conditional::Modified(self),
+        }
+    }
+}
+
+impl<'r> Responder<'r, 'static> for RenderedPage {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let mut response = (ContentType::HTML, self.html).respond_to(req)?;
+        if let Some(validators) = &self.validators {
+            validators.set_headers(&mut response);
+        }
+        Ok(response)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PageCacheStats {
+    pub capacity: usize,
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub data_version: Option<i64>,
+}
+
+struct Entry {
+    page: RenderedPage,
+    last_used: u64,
+    /// When a scheduled article goes live and the page may list it.
+    expires_at: Option<DateTime<Utc>>,
+}
+
+/// What a page missing from the cache may be stored under.
+struct Miss {
+    /// `None` when the page is not to be kept.
+    data_version: Option<i64>,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+impl Miss {
+    const UNCACHED: Miss = Miss {
+        data_version: None,
+        expires_at: None,
+    };
+}
+
+#[derive(Default)]
+struct Pages {
+    /// Data version the entries were rendered from.
+    data_version: Option<i64>,
+    entries: HashMap<String, Entry>,
+    /// Keys of `entries` by `last_used`, oldest first.
+    recency: BTreeMap<u64, String>,
+    clock: u64,
+}
+
+impl Pages {
+    /// Drops every entry when the seeder has written since they were rendered.
+    fn sync(&mut self, data_version: i64) {
+        if self.data_version != Some(data_version) {
+            self.entries.clear();
+            self.recency.clear();
+            self.data_version = Some(data_version);
+        }
+    }
+
+    fn get(&mut self, key: &str, now: DateTime<Utc>) -> Option<RenderedPage> {
+        self.clock += 1;
+        let entry = self.entries.get_mut(key)?;
+        if entry.expires_at.is_some_and(|expires_at| expires_at <= now) {
+            let last_used = entry.last_used;
+            self.entries.remove(key);
+            self.recency.remove(&last_used);
+            return None;
+        }
+        let key = self
+            .recency
+            .remove(&entry.last_used)
+            .unwrap_or_else(|| key.to_string());
+        entry.last_used = self.clock;
+        self.recency.insert(self.clock, key);
+        Some(entry.page.clone())
+    }
+
+    fn insert(
+        &mut self,
+        key: String,
+        page: RenderedPage,
+        expires_at: Option<DateTime<Utc>>,
+        capacity: usize,
+    ) {
+        if let Some(entry) = self.entries.remove(&key) {
+            self.recency.remove(&entry.last_used);
+        }
+        if self.entries.len() >= capacity
+            && let Some((_, oldest)) = self.recency.pop_first()
+        {
+            self.entries.remove(&oldest);
+        }
+        self.clock += 1;
+        self.recency.insert(self.clock, key.clone());
+        self.entries.insert(
+            key,
+            Entry {
+                page,
+                last_used: self.clock,
+                expires_at,
+            },
+        );
+    }
+}
+
+/// Least recently used pages, dropped as a whole when the data version changes.
+pub struct PageCache {
+    capacity: usize,
+    pages: Mutex<Pages>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl PageCache {
+    /// A capacity of `0` turns the cache off.
+    pub fn new(capacity: usize) -> Self {
+        PageCache {
+            capacity,
+            pages: Mutex::new(Pages::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let capacity = std::env::var("PAGE_CACHE_CAPACITY")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_CAPACITY);
+        PageCache::new(capacity)
+    }
+
+    /// The page cached under `key`, or the one `render` produces, which is kept
+    /// for the next request. `render` is only awaited on a miss.
+    pub async fn get_or_render(
+        &self,
+        db: &DatabaseConnection,
+        key: &str,
+        render: impl Future<Output = Result<RenderedPage, Status>>,
+    ) -> Result<RenderedPage, Status> {
+        let miss = match self.lookup(db, key).await {
+            Ok(page) => return Ok(page),
+            Err(miss) => miss,
+        };
+        let page = render.await?;
+        self.store(key, miss, &page);
+        Ok(page)
+    }
+
+    /// The page cached under `key` for the current data version.
+    async fn lookup(&self, db: &DatabaseConnection, key: &str) -> Result<RenderedPage, Miss> {
+        if self.capacity == 0 {
+            return Err(Miss::UNCACHED);
+        }
+        let data_version = match get_data_version(db).await {
+            Ok(data_version) => data_version,
+            Err(e) => {
+                warn!("page cache bypassed, data version unavailable: {}", e);
+                return Err(Miss::UNCACHED);
+            }
+        };
+        let cached = {
+            let mut pages = self.lock();
+            pages.sync(data_version);
+            pages.get(key, Utc::now())
+        };
+        if let Some(page) = cached {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(page);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        // Read before rendering, so an article going live in between expires
+        // the page rather than being missing from it.
+        match get_next_publication(db).await {
+            Ok(expires_at) => Err(Miss {
+                data_version: Some(data_version),
+                expires_at,
+            }),
+            Err(e) => {
+                warn!("page not cached, next publication unavailable: {}", e);
+                Err(Miss::UNCACHED)
+            }
+        }
+    }
+
+    fn store(&self, key: &str, miss: Miss, page: &RenderedPage) {
+        let Some(data_version) = miss.data_version else {
+            return;
+        };
+        let mut pages = self.lock();
+        // A seed that finished while rendering already cleared the cache for a
+        // newer version; the page may be older than that.
+        if pages.data_version == Some(data_version) {
+            pages.insert(
+                key.to_string(),
+                page.clone(),
+                miss.expires_at,
+                self.capacity,
+            );
+        }
+    }
+
+    pub fn stats(&self) -> PageCacheStats {
+        let pages = self.lock();
+        PageCacheStats {
+            capacity: self.capacity,
+            entries: pages.entries.len(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            data_version: pages.data_version,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Pages> {
+        // Entries are only swapped in whole, so a panicking holder leaves them usable.
+        self.pages
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PageCache, PageCacheStats, PageRequest, RenderedPage, cache_key};
+    use crate::repository::data_version::bump_data_version;
+    use crate::route::conditional::{Conditional, Validators};
+    use chrono::{DateTime, Duration, Utc};
+    use rocket::State;
+    use rocket::http::{Header, Status};
+    use rocket::local::asynchronous::Client;
+    use rocket::tokio;
+    use rocket_dyn_templates::Template;
+    use sea_orm::{ConnectionTrait, Database, DatabaseConnection, DbBackend, Statement};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    async fn prepare_db() -> DatabaseConnection {
+        let db = Database::connect("sqlite::memory:")
+            .await
+            .expect("failed to connect sqlite memory");
+        db.execute(Statement::from_string(
+            DbBackend::Sqlite,
+            "CREATE TABLE data_version (id INTEGER PRIMARY KEY, version BIGINT NOT NULL);",
+        ))
+        .await
+        .expect("failed to create data_version table");
+        db.execute(Statement::from_string(
+            DbBackend::Sqlite,
+            "CREATE TABLE article (id INTEGER PRIMARY KEY, created_at TEXT NOT NULL);",
+        ))
+        .await
+        .expect("failed to create article table");
+        db
+    }
+
+    fn page(html: &str) -> RenderedPage {
+        RenderedPage {
+            html: html.to_string(),
+            validators: None,
+        }
+    }
+
+    async fn html(cache: &PageCache, db: &DatabaseConnection, key: &str, fresh: &str) -> String {
+        cache
+            .get_or_render(db, key, async { Ok(page(fresh)) })
+            .await
+            .expect("failed to render")
+            .html
+    }
+
+    /// Times the rows of `/loaded` were loaded and its page rendered.
+    #[derive(Default)]
+    struct Counts {
+        loads: AtomicU64,
+        renders: AtomicU64,
+    }
+
+    #[get("/loaded?<page>")]
+    async fn loaded(
+        db: &State<DatabaseConnection>,
+        counts: &State<Counts>,
+        request: PageRequest<'_>,
+        page: Option<u64>,
+    ) -> Result<Conditional<RenderedPage>, Status> {
+        let load = async {
+            counts.loads.fetch_add(1, Ordering::Relaxed);
+            Ok(page.unwrap_or(1))
+        };
+        let render = |page| async move {
+            counts.renders.fetch_add(1, Ordering::Relaxed);
+            Ok(RenderedPage {
+                html: format!("page {page}"),
+                validators: None,
+            })
+        };
+        let validators = |_: &u64| Validators::new([b"loaded".as_slice()], DateTime::UNIX_EPOCH);
+        request.respond_loaded(db, load, validators, render).await
+    }
+
+    #[tokio::test]
+    async fn loaded_pages_skip_loading_on_hits_and_rendering_for_304() {
+        let rocket =
+            rocket::custom(rocket::Config::figment().merge(("template_dir", "../templates")))
+                .manage(prepare_db().await)
+                .manage(PageCache::new(8))
+                .manage(Counts::default())
+                .attach(Template::fairing())
+                .mount("/", routes![loaded]);
+        let client = Client::tracked(rocket)
+            .await
+            .expect("failed to build client");
+        let counts = || {
+            let counts = client
+                .rocket()
+                .state::<Counts>()
+                .expect("counts should be managed");
+            (
+                counts.loads.load(Ordering::Relaxed),
+                counts.renders.load(Ordering::Relaxed),
+            )
+        };
+
+        let response = client.get("/loaded").dispatch().await;
+        let etag = response
+            .headers()
+            .get_one("ETag")
+            .expect("page should have an ETag")
+            .to_string();
+        assert_eq!(response.into_string().await.as_deref(), Some("page 1"));
+        let response = client
+            .get("/loaded?utm_source=feed")
+            .header(Header::new("If-None-Match", etag.clone()))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotModified);
+        assert_eq!(counts(), (1, 1));
+
+        let response = client
+            .get("/loaded?page=2&utm_source=feed")
+            .header(Header::new("If-None-Match", etag))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotModified);
+        assert_eq!(counts(), (2, 1));
+
+        let response = client
+            .get("/loaded?utm_source=feed&page=2")
+            .dispatch()
+            .await;
+        assert_eq!(response.into_string().await.as_deref(), Some("page 2"));
+        let response = client
+            .get("/loaded?page=2&utm_source=mail")
+            .dispatch()
+            .await;
+        assert_eq!(response.into_string().await.as_deref(), Some("page 2"));
+        assert_eq!(counts(), (3, 2));
+    }
+
+    #[tokio::test]
+    async fn serves_cached_pages_until_the_data_version_changes() {
+        let db = prepare_db().await;
+        let cache = PageCache::new(8);
+
+        assert_eq!(html(&cache, &db, "/posts/a", "v1").await, "v1");
+        assert_eq!(html(&cache, &db, "/posts/a", "v2").await, "v1");
+        assert_eq!(html(&cache, &db, "/?page=2", "page 2").await, "page 2");
+
+        bump_data_version(&db).await.expect("failed to bump");
+        assert_eq!(html(&cache, &db, "/posts/a", "v2").await, "v2");
+        assert_eq!(
+            cache.stats(),
+            PageCacheStats {
+                capacity: 8,
+                entries: 1,
+                hits: 1,
+                misses: 3,
+                data_version: Some(1),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn pages_expire_when_a_scheduled_article_goes_live() {
+        let db = prepare_db().await;
+        let cache = PageCache::new(8);
+        let publication = Utc::now() + Duration::milliseconds(500);
+        db.execute(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            "INSERT INTO article (id, created_at) VALUES (1, ?);",
+            [publication.into()],
+        ))
+        .await
+        .expect("failed to schedule article");
+
+        assert_eq!(html(&cache, &db, "/", "before").await, "before");
+        assert_eq!(html(&cache, &db, "/", "after").await, "before");
+
+        let wait = publication - Utc::now() + Duration::milliseconds(50);
+        tokio::time::sleep(wait.to_std().unwrap_or_default()).await;
+        assert_eq!(html(&cache, &db, "/", "after").await, "after");
+        assert_eq!(html(&cache, &db, "/", "later").await, "after");
+    }
+
+    #[tokio::test]
+    async fn evicts_the_least_recently_used_page() {
+        let db = prepare_db().await;
+        let cache = PageCache::new(2);
+
+        html(&cache, &db, "/a", "a").await;
+        html(&cache, &db, "/b", "b").await;
+        html(&cache, &db, "/a", "a2").await;
+        html(&cache, &db, "/c", "c").await;
+
+        assert_eq!(html(&cache, &db, "/a", "a3").await, "a");
+        assert_eq!(html(&cache, &db, "/b", "b2").await, "b2");
+    }
+
+    #[tokio::test]
+    async fn errors_and_disabled_caches_are_not_kept() {
+        let db = prepare_db().await;
+        let cache = PageCache::new(8);
+        let result = cache
+            .get_or_render(&db, "/posts/missing", async { Err(Status::NotFound) })
+            .await;
+        assert_eq!(result.map(|page| page.html), Err(Status::NotFound));
+        assert_eq!(cache.stats().entries, 0);
+
+        let cache = PageCache::new(0);
+        assert_eq!(html(&cache, &db, "/a", "a").await, "a");
+        assert_eq!(html(&cache, &db, "/a", "a2").await, "a2");
+        assert_eq!(cache.stats().misses, 0);
+    }
+
+    #[test]
+    fn cache_keys_keep_only_the_parameters_pages_read() {
+        assert_eq!(cache_key("/posts/a", []), "/posts/a");
+        assert_eq!(
+            cache_key(
+                "/",
+                [("month", "12"), ("utm_source", "feed"), ("year", "2025")]
+            ),
+            cache_key("/", [("year", "2025"), ("month", "12")]),
+        );
+        assert_ne!(
+            cache_key("/tag/rust", [("sort_key", "a&page=2")]),
+            cache_key("/tag/rust", [("sort_key", "a"), ("page", "2")]),
+        );
+    }
+}
@@ -0,0 +1,500 @@
+//! Renders one database through the Rocket routes and through `export_site`, then compares
+//! every exported page with the live page at the same place.
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeSet, HashMap},
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use html5ever::tendril::StrTendril;
+use html5ever::tokenizer::{
+    BufferQueue, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer, TokenizerOpts,
+};
+use percent_encoding::percent_decode_str;
+use rocket::http::Status;
+use rocket::local::asynchronous::Client;
+use rust_blog::static_site::{ExportOptions, ExportPaths, export_site, link_check::extract_links};
+use sea_orm::Database;
+use walkdir::WalkDir;
+
+use super::{ContentPaths, build};
+
+/// Page size both modes use when the URL does not say otherwise.
+const DEFAULT_PER: &str = "10";
+/// Requested live to render the same catcher as the exported `404.html`.
+const MISSING_URL: &str = "/_parity/missing/page";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Aspect {
+    /// The live route did not answer with the expected status.
+    Status,
+    /// `<title>`, `<meta>` and `<link>` in the head.
+    Metadata,
+    /// Text of the page content, without navigation and footer.
+    Content,
+    /// Every `href` / `src`, compared in the live URL form.
+    Links,
+}
+
+#[derive(Debug)]
+pub struct PageDiff {
+    /// Page in the export, e.g. `/tag/rust/page/2/`.
+    pub page: String,
+    pub aspect: Aspect,
+    pub live: String,
+    pub exported: String,
+}
+
+impl fmt::Display for PageDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} ({:?})", self.page, self.aspect)?;
+        writeln!(f, "    live:     {}", self.live)?;
+        write!(f, "    exported: {}", self.exported)
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct PageSummary {
+    metadata: Vec<String>,
+    content: String,
+    links: Vec<String>,
+}
+
+#[derive(Default)]
+struct SummaryState {
+    in_head: bool,
+    in_title: bool,
+    in_content: bool,
+    title: String,
+    metadata: Vec<String>,
+    content: String,
+}
+
+struct SummarySink {
+    state: RefCell<SummaryState>,
+}
+
+impl TokenSink for SummarySink {
+    type Handle = ();
+
+    fn process_token(&self, token: Token, _line_number: u64) -> TokenSinkResult<()> {
+        let mut state = self.state.borrow_mut();
+        match token {
+            Token::TagToken(tag) => {
+                // Tags separate words; text split around character references does not.
+                if state.in_content {
+                    state.content.push(' ');
+                }
+                let attr = |name: &str| {
+                    tag.attrs
+                        .iter()
+                        .find(|attr| &*attr.name.local == name)
+                        .map(|attr| attr.value.to_string())
+                        .unwrap_or_default()
+                };
+                match (tag.kind, &*tag.name) {
+                    (TagKind::StartTag, "head") => state.in_head = true,
+                    (TagKind::EndTag, "head") => state.in_head = false,
+                    (TagKind::StartTag, "title") => state.in_title = true,
+                    (TagKind::EndTag, "title") => state.in_title = false,
+                    (TagKind::StartTag, "meta") if state.in_head => {
+                        let key =
+                            format!("{}{}{}", attr("name"), attr("property"), attr("charset"));
+                        state
+                            .metadata
+                            .push(format!("meta {key}={}", attr("content")));
+                    }
+                    (TagKind::StartTag, "link") if state.in_head => {
+                        let href = canonical_link(attr("href").trim());
+                        state.metadata.push(format!("link {}={href}", attr("rel")));
+                    }
+                    (TagKind::StartTag, "section") if attr("class") == "section" => {
+                        state.in_content = true;
+                    }
+                    (TagKind::StartTag, "footer") => state.in_content = false,
+                    _ => {}
+                }
+            }
+            Token::CharacterTokens(text) if state.in_title => state.title.push_str(&text),
+            Token::CharacterTokens(text) if state.in_content => state.content.push_str(&text),
+            _ => {}
+        }
+        TokenSinkResult::Continue
+    }
+}
+
+fn summarize(html: &str) -> PageSummary {
+    let input = BufferQueue::default();
+    input.push_back(StrTendril::from_slice(html));
+    let tokenizer = Tokenizer::new(
+        SummarySink {
+            state: RefCell::new(SummaryState::default()),
+        },
+        TokenizerOpts::default(),
+    );
+    let _ = tokenizer.feed(&input);
+    tokenizer.end();
+    let state = tokenizer.sink.state.take();
+
+    let mut metadata = state.metadata;
+    metadata.push(format!("title {}", collapse_whitespace(&state.title)));
+    metadata.sort();
+    let mut links: Vec<String> = extract_links(html)
+        .into_iter()
+        .map(|link| canonical_link(&link.url))
+        .collect();
+    links.sort();
+    PageSummary {
+        metadata,
+        content: collapse_whitespace(&state.content),
+        links,
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Writes static and live URLs of the same page the same way: the live path and query,
+/// without trailing slash, fragment or parameters left at their default.
+fn canonical_link(url: &str) -> String {
+    let has_scheme = url
+        .split_once(':')
+        .is_some_and(|(scheme, _)| !scheme.contains(['/', '?', '#']));
+    if url.is_empty() || url.starts_with("//") || has_scheme {
+        return url.to_string();
+    }
+    let url = url.split('#').next().unwrap_or_default();
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let path = percent_decode_str(path).decode_utf8_lossy().into_owned();
+    let (path, mut params) = live_path(&path);
+    params.extend(
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(key, value)| (key.to_string(), value.to_string())),
+    );
+    params.retain(|(key, value)| {
+        !matches!(
+            (key.as_str(), value.as_str()),
+            ("page", "1") | ("per", DEFAULT_PER) | ("sort_key", "created_at")
+        )
+    });
+    params.sort();
+    params.dedup();
+    if params.is_empty() {
+        return path;
+    }
+    let query: Vec<String> = params
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect();
+    format!("{path}?{}", query.join("&"))
+}
+
+/// Live route of a path, moving what the export spells as path segments into the query.
+fn live_path(path: &str) -> (String, Vec<(String, String)>) {
+    let param = |key: &str, value: &str| (key.to_string(), value.to_string());
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match segments.as_slice() {
+        ["page", page] => ("/".to_string(), vec![param("page", page)]),
+        [kind @ ("tag" | "category"), slug, rest @ ..] => {
+            let base = format!("/{kind}/{slug}");
+            match rest {
+                [] => (base, Vec::new()),
+                ["updated"] => (base, vec![param("sort_key", "updated_at")]),
+                ["page", page] => (base, vec![param("page", page)]),
+                ["updated", "page", page] => (
+                    base,
+                    vec![param("sort_key", "updated_at"), param("page", page)],
+                ),
+                _ => (path.to_string(), Vec::new()),
+            }
+        }
+        _ if path.len() > 1 => (path.trim_end_matches('/').to_string(), Vec::new()),
+        _ => (path.to_string(), Vec::new()),
+    }
+}
+
+fn compare(page: &str, live: &PageSummary, exported: &PageSummary, diffs: &mut Vec<PageDiff>) {
+    let mut push = |aspect, live: String, exported: String| {
+        diffs.push(PageDiff {
+            page: page.to_string(),
+            aspect,
+            live,
+            exported,
+        });
+    };
+    if live.metadata != exported.metadata {
+        let (live_only, exported_only) = difference(&live.metadata, &exported.metadata);
+        push(Aspect::Metadata, live_only, exported_only);
+    }
+    if live.content != exported.content {
+        push(
+            Aspect::Content,
+            live.content.clone(),
+            exported.content.clone(),
+        );
+    }
+    if live.links != exported.links {
+        let (live_only, exported_only) = difference(&live.links, &exported.links);
+        push(Aspect::Links, live_only, exported_only);
+    }
+}
+
+/// Entries only on one side, each list joined for the report.
+fn difference(live: &[String], exported: &[String]) -> (String, String) {
+    let live_set: BTreeSet<&String> = live.iter().collect();
+    let exported_set: BTreeSet<&String> = exported.iter().collect();
+    let only = |a: &BTreeSet<&String>, b: &BTreeSet<&String>| {
+        let entries: Vec<&str> = a.difference(b).map(|entry| entry.as_str()).collect();
+        if entries.is_empty() {
+            // Same entries, different counts.
+            "(same entries)".to_string()
+        } else {
+            entries.join(", ")
+        }
+    };
+    (
+        only(&live_set, &exported_set),
+        only(&exported_set, &live_set),
+    )
+}
+
+/// Exports `db_url` to a temporary directory and fetches each exported page from the server.
+pub async fn check_parity(
+    db_url: &str,
+    config_map: HashMap<String, String>,
+    root: &Path,
+) -> anyhow::Result<Vec<PageDiff>> {
+    let content_dir = root.join("content");
+    let paths = ContentPaths {
+        article_dir: content_dir.join("articles"),
+        image_dir: content_dir.join("image"),
+        icon_dir: content_dir.join("icon"),
+        variant_dir: content_dir.join("variants"),
+    };
+    for dir in [&paths.article_dir, &paths.image_dir, &paths.icon_dir] {
+        fs::create_dir_all(dir)?;
+    }
+
+    let templates_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../templates");
+    let out_dir = root.join("dist");
+    let export_db = Database::connect(db_url).await?;
+    let export_paths = ExportPaths {
+        templates_dir: PathBuf::from(templates_dir),
+        content_dir: content_dir.clone(),
+        article_dir: paths.article_dir.clone(),
+        variant_dir: paths.variant_dir.clone(),
+    };
+    export_site(
+        &export_db,
+        &config_map,
+        &out_dir,
+        &export_paths,
+        &ExportOptions::default(),
+    )
+    .await?;
+
+    let figment = rocket::Config::figment()
+        .merge(("template_dir", templates_dir))
+        .merge(("log_level", "off"));
+    let rocket = build(
+        rocket::custom(figment),
+        Database::connect(db_url).await?,
+        config_map,
+        paths,
+    );
+    let client = Client::tracked(rocket).await?;
+
+    let mut pages: Vec<String> = WalkDir::new(&out_dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "html"))
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(&out_dir).ok()?;
+            Some(format!("/{}", relative.to_string_lossy()))
+        })
+        .collect();
+    pages.sort();
+
+    let mut diffs = Vec::new();
+    for file in pages {
+        let page = file.strip_suffix("index.html").unwrap_or(&file).to_string();
+        let (url, expected) = if page == "/404.html" {
+            (MISSING_URL.to_string(), Status::NotFound)
+        } else {
+            (canonical_link(&page), Status::Ok)
+        };
+        let response = client.get(url.clone()).dispatch().await;
+        let status = response.status();
+        if status != expected {
+            diffs.push(PageDiff {
+                page,
+                aspect: Aspect::Status,
+                live: format!("{url} -> {status}"),
+                exported: expected.to_string(),
+            });
+            continue;
+        }
+        let live_html = response.into_string().await.unwrap_or_default();
+        let exported_html = fs::read_to_string(out_dir.join(file.trim_start_matches('/')))?;
+        compare(
+            &page,
+            &summarize(&live_html),
+            &summarize(&exported_html),
+            &mut diffs,
+        );
+    }
+    Ok(diffs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Aspect, canonical_link, check_parity, summarize};
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::{ConnectionTrait, Database, DbBackend, Statement};
+    use std::collections::{BTreeSet, HashMap};
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn create_temp_dir() -> PathBuf {
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time before UNIX_EPOCH")
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("parity_test_{}_{}", std::process::id(), ts));
+        fs::create_dir_all(&dir).expect("failed to create temp dir");
+        dir
+    }
+
+    /// Drift found by the harness that is not fixed yet. Remove entries as they get fixed.
+    const KNOWN_DIFFERENCES: &[(&str, Aspect)] = &[];
+
+    /// Twelve articles over two months so that the index and the `rust` tag have a second page,
+    /// plus one scheduled article that neither mode may list.
+    async fn prepare_db(db_url: &str) {
+        let db = Database::connect(db_url)
+            .await
+            .expect("failed to connect sqlite");
+        Migrator::up(&db, None)
+            .await
+            .expect("failed to run migrations");
+        let mut articles = Vec::new();
+        for id in 1..=12 {
+            let month = if id <= 6 { 11 } else { 12 };
+            let icatch = if id == 1 { "'/image/one.png'" } else { "NULL" };
+            articles.push(format!(
+                "({id}, 'Article {id}', 'article-{id}', NULL, '# Article {id}\n\nBody of [article](/posts/article-1) {id}.', '2025-{month}-{:02}T00:00:00Z', '2025-12-{:02}T00:00:00Z', {icatch})",
+                id + 1,
+                13 - id,
+            ));
+        }
+        articles.push(
+            "(13, 'Scheduled', 'scheduled', NULL, 'Later.', '2099-01-01T00:00:00Z', '2099-01-01T00:00:00Z', NULL)"
+                .to_string(),
+        );
+        let statements = [
+            format!(
+                "INSERT INTO article (id, title, slug, excerpt, content, created_at, updated_at, icatch_path) VALUES {};",
+                articles.join(", ")
+            ),
+            "INSERT INTO tag (id, name, slug) VALUES (1, 'Rust', 'rust'), (2, 'Diary', 'diary');"
+                .to_string(),
+            "INSERT INTO category (id, name, slug) VALUES (1, 'Development', 'development');"
+                .to_string(),
+            format!(
+                "INSERT INTO article_tag (article_id, tag_id) VALUES {}, (1, 2);",
+                (1..=11)
+                    .chain([13])
+                    .map(|id| format!("({id}, 1)"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            "INSERT INTO article_category (article_id, category_id) VALUES (1, 1), (2, 1);"
+                .to_string(),
+            "INSERT INTO fixed_content (id, title, slug, excerpt, content, created_at, updated_at) VALUES (1, 'About', 'about', NULL, 'About this blog.', '2025-01-01T00:00:00Z', '2025-01-01T00:00:00Z');"
+                .to_string(),
+        ];
+        for sql in statements {
+            db.execute(Statement::from_string(DbBackend::Sqlite, sql))
+                .await
+                .expect("failed to insert fixture");
+        }
+    }
+
+    #[test]
+    fn canonical_link_writes_static_paths_as_live_urls() {
+        assert_eq!(canonical_link("/page/2/"), "/?page=2");
+        assert_eq!(canonical_link("/?page=2&per=10"), "/?page=2");
+        assert_eq!(canonical_link("/posts/a/"), "/posts/a");
+        assert_eq!(
+            canonical_link("/tag/rust/updated/page/2/"),
+            "/tag/rust?page=2&sort_key=updated_at"
+        );
+        assert_eq!(
+            canonical_link("/tag/rust?page=2&per=10&sort_key=updated_at"),
+            "/tag/rust?page=2&sort_key=updated_at"
+        );
+        assert_eq!(
+            canonical_link("/category/dev?sort_key=created_at"),
+            "/category/dev"
+        );
+        assert_eq!(canonical_link("/archive/2025/12/"), "/archive/2025/12");
+        assert_eq!(canonical_link("/css/site.css"), "/css/site.css");
+        assert_eq!(
+            canonical_link("https://example.com/a/"),
+            "https://example.com/a/"
+        );
+    }
+
+    #[test]
+    fn summarize_separates_metadata_content_and_links() {
+        let html = "<html><head><title> Blog </title><link rel=\"stylesheet\" href=\"/css/site.css\"></head><body><nav><a href=\"/tags/\">Tags</a></nav><section class=\"section\"><p>Hello\n  <a href=\"/posts/a/\">world</a></p></section><footer>(c)</footer></body></html>";
+        let summary = summarize(html);
+        assert_eq!(
+            summary.metadata,
+            vec!["link stylesheet=/css/site.css", "title Blog"]
+        );
+        assert_eq!(summary.content, "Hello world");
+        assert_eq!(summary.links, vec!["/css/site.css", "/posts/a", "/tags"]);
+    }
+
+    #[rocket::async_test]
+    async fn live_and_exported_pages_match() {
+        let root = create_temp_dir();
+        let db_url = format!("sqlite://{}?mode=rwc", root.join("blog.db").display());
+        prepare_db(&db_url).await;
+        let config_map = HashMap::from([
+            ("site_name".to_string(), "Parity Blog".to_string()),
+            (
+                "default_icatch_path".to_string(),
+                "/image/default.png".to_string(),
+            ),
+            ("favicon_path".to_string(), "/icon/favicon.png".to_string()),
+        ]);
+
+        let diffs = check_parity(&db_url, config_map, &root)
+            .await
+            .expect("failed to compare live and exported pages");
+        let report: Vec<String> = diffs.iter().map(ToString::to_string).collect();
+        let found: BTreeSet<(String, Aspect)> = diffs
+            .into_iter()
+            .map(|diff| (diff.page, diff.aspect))
+            .collect();
+        let known: BTreeSet<(String, Aspect)> = KNOWN_DIFFERENCES
+            .iter()
+            .map(|(page, aspect)| (page.to_string(), *aspect))
+            .collect();
+        assert_eq!(
+            found,
+            known,
+            "live and exported pages differ:\n{}",
+            report.join("\n")
+        );
+        let _ = fs::remove_dir_all(root);
+    }
+}
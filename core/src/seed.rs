@@ -1,70 +1,472 @@
 pub mod article;
 pub mod config;
+pub mod datetime;
+pub mod diff;
+pub mod error;
 pub mod fixed_content;
+pub mod front_matter;
+pub mod git;
+pub mod image;
+pub mod incremental;
 pub mod markdown;
+pub mod prune;
+pub mod references;
+pub mod taxonomy;
+pub mod watch;
 use crate::{
     entity::category::Entity as CategoryEntity,
     entity::tag::Entity as TagEntity,
+    repository::data_version::bump_data_version,
     seed::{
+        diff::SeedDiff,
+        error::{SeedError, SeedErrorReport},
         fixed_content::seed_fixed_content,
+        git::GitHistory,
+        image::{CreatedVariants, is_raster_image, run_image_seed},
+        incremental::{SeedStatus, SeedSummary, content_hash},
         markdown::{
             markdown_files, parse_markdown_to_fixed_content_matter, parse_markdown_to_front_matter,
         },
+        prune::{PruneMode, delete_orphans, find_orphans},
+        references::ReferenceChecker,
+        taxonomy::DeclaredTaxonomy,
+    },
+    utils::{
+        bundle::{bundle_slug, bundle_url},
+        config::load_config_from_file,
     },
 };
+use anyhow::bail;
 use article::{delete_article_by_slug, seed_article, seed_category, seed_tag};
-use config::{env::load_env, seed::seed_from_toml};
-use sea_orm::DatabaseConnection;
+use config::PathConfig;
+use config::{
+    SeedOptions,
+    env::{load_env, load_image_config, load_seed_options},
+    seed::seed_from_toml,
+};
+use sea_orm::{ConnectionTrait, DatabaseConnection, TransactionTrait};
+use std::{
+    collections::{BTreeSet, HashSet},
+    path::{Path, PathBuf},
+};
 
-pub async fn run_all(db: DatabaseConnection) -> anyhow::Result<()> {
+/// Sources a run reads: everything, or only the files the watcher saw change.
+#[derive(Debug, Clone, Copy)]
+pub enum SeedScope<'a> {
+    All,
+    /// Articles and fixed contents among these paths, and the images when one
+    /// of them is an image. The config taxonomies are left alone.
+    Changed(&'a BTreeSet<PathBuf>),
+}
+
+impl SeedScope<'_> {
+    fn reads(&self, path: &Path) -> bool {
+        match self {
+            SeedScope::All => true,
+            SeedScope::Changed(paths) => {
+                paths.contains(path)
+                    || std::path::absolute(path).is_ok_and(|path| paths.contains(&path))
+            }
+        }
+    }
+
+    fn reads_images(&self) -> bool {
+        match self {
+            SeedScope::All => true,
+            SeedScope::Changed(paths) => paths.iter().any(|path| is_raster_image(path)),
+        }
+    }
+}
+
+pub async fn run_all(db: &DatabaseConnection, dry_run: bool) -> anyhow::Result<()> {
+    run(db, dry_run, SeedScope::All).await
+}
+
+pub async fn run(
+    db: &DatabaseConnection,
+    dry_run: bool,
+    scope: SeedScope<'_>,
+) -> anyhow::Result<()> {
     let config = load_env();
+    let options = load_seed_options();
     println!("{:?}", config);
-    run_fixed_content_seed(&db, &config.fixed_content_path).await?;
+    if dry_run {
+        let mut diff = SeedDiff::default();
+        let mut created = CreatedVariants::default();
+        let errors = seed_all(db, &config, &options, scope, Some(&mut diff), &mut created).await?;
+        if diff.is_empty() {
+            println!("🔍 dry-run: 変更はありません");
+        } else {
+            println!("🔍 dry-run: 以下の変更が適用されます (DB には書き込んでいません)\n{diff}");
+        }
+        return report_errors(&errors, "dry-run");
+    }
+    // Without a transaction, each image row is written along with its variants.
+    let mut created = CreatedVariants::default();
+    if options.best_effort {
+        let seeded = seed_all(db, &config, &options, scope, None, &mut created).await;
+        if seeded.is_err() {
+            // The rows written before the failure stay, so servers must drop
+            // the pages rendered without them.
+            if let Err(e) = bump_data_version(db).await {
+                eprintln!("⚠️ data_version を更新できませんでした: {e}");
+            }
+        }
+        return report_errors(&seeded?, "the other files were written");
+    }
+
+    let seeded = seed_in_transaction(db, &config, &options, scope, &mut created).await;
+    if !seeded.as_ref().is_ok_and(SeedErrorReport::is_empty) {
+        // The rows that listed them were rolled back.
+        created.remove();
+    }
+    let errors = seeded?;
+    if !errors.is_empty() {
+        return report_errors(&errors, "nothing was written");
+    }
+    println!("✅ シード結果をコミットしました");
+    Ok(())
+}
+
+/// Commits only when every file was seeded.
+async fn seed_in_transaction(
+    db: &DatabaseConnection,
+    config: &PathConfig,
+    options: &SeedOptions,
+    scope: SeedScope<'_>,
+    created: &mut CreatedVariants,
+) -> anyhow::Result<SeedErrorReport> {
+    let txn = db.begin().await?;
+    let errors = seed_all(&txn, config, options, scope, None, created).await?;
+    if errors.is_empty() {
+        txn.commit().await?;
+    } else {
+        txn.rollback().await?;
+    }
+    Ok(errors)
+}
+
+fn report_errors(errors: &SeedErrorReport, outcome: &str) -> anyhow::Result<()> {
+    for warning in &errors.warnings {
+        eprintln!("⚠️ {warning}");
+    }
+    if errors.is_empty() {
+        return Ok(());
+    }
+    eprintln!("❌ シードに失敗したファイルがあります\n{errors}");
+    eprintln!("{}", errors.to_json());
+    bail!("{} file(s) failed to seed, {outcome}", errors.file_count());
+}
+
+/// With `diff`, nothing is written and the changes are collected into it instead.
+async fn seed_all<C>(
+    db: &C,
+    config: &PathConfig,
+    options: &SeedOptions,
+    scope: SeedScope<'_>,
+    mut diff: Option<&mut SeedDiff>,
+    created: &mut CreatedVariants,
+) -> anyhow::Result<SeedErrorReport>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let taxonomy = declared_taxonomy(options, &config.config_toml_path)?;
+    let history = git_history(options, &config.article_path);
+    let fixed_content_history = git_history(options, &config.fixed_content_path);
+    let mut errors = SeedErrorReport::default();
+    let images = load_image_config();
+    let references = ReferenceChecker {
+        images: &images,
+        article_dir: Path::new(&config.article_path),
+    };
+    if matches!(scope, SeedScope::All) {
+        check_config_references(&references, &config.config_toml_path, &mut errors);
+    }
+    let (fixed_content_slugs, fixed_content_summary) = run_fixed_content_seed(
+        db,
+        &config.fixed_content_path,
+        scope,
+        fixed_content_history.as_ref(),
+        &references,
+        &mut errors,
+        diff.as_deref_mut(),
+    )
+    .await?;
     println!("✅ 固定ページ Markdown → DB のシード完了");
-    run_article_seed(&db, &config.article_path).await?;
+    let article_summary = run_article_seed(
+        db,
+        markdown_files(&config.article_path).filter(|path| scope.reads(path)),
+        taxonomy.as_ref(),
+        history.as_ref(),
+        &references,
+        &mut errors,
+        diff.as_deref_mut(),
+    )
+    .await?;
     println!("✅ Article Markdown → DB のシード完了");
-    seed_from_toml::<TagEntity>(&db, &config.config_toml_path, "tags").await?;
-    println!("✅ Tag Toml → DB のシード完了");
-    seed_from_toml::<CategoryEntity>(&db, &config.config_toml_path, "categories").await?;
-    println!("✅ Category Toml → DB のシード完了");
+    let changed = article_summary.changed() || fixed_content_summary.changed();
+    // Images, variants and config taxonomies are only written by a real run.
+    if let Some(diff) = diff {
+        run_prune(
+            db,
+            options.prune,
+            config,
+            fixed_content_slugs.as_ref(),
+            changed,
+            Some(diff),
+        )
+        .await?;
+        return Ok(errors);
+    }
+
+    if scope.reads_images() {
+        run_image_seed(db, &images, &config.article_path, &mut errors, created).await?;
+        println!("✅ 画像 → DB のシード完了");
+    }
+    if matches!(scope, SeedScope::All) {
+        seed_from_toml::<TagEntity, _>(db, &config.config_toml_path, "tags").await?;
+        println!("✅ Tag Toml → DB のシード完了");
+        seed_from_toml::<CategoryEntity, _>(db, &config.config_toml_path, "categories").await?;
+        println!("✅ Category Toml → DB のシード完了");
+    }
+    run_prune(
+        db,
+        options.prune,
+        config,
+        fixed_content_slugs.as_ref(),
+        changed,
+        None,
+    )
+    .await?;
+    // Rolled back along with everything else when the seed fails.
+    bump_data_version(db).await?;
+
+    Ok(errors)
+}
 
+/// `changed` tells whether this run wrote any article or fixed content, which is
+/// when `PruneMode::Changed` reports. With `diff`, deletions are recorded instead.
+async fn run_prune<C: ConnectionTrait>(
+    db: &C,
+    mode: PruneMode,
+    config: &PathConfig,
+    fixed_content_slugs: Option<&HashSet<String>>,
+    changed: bool,
+    diff: Option<&mut SeedDiff>,
+) -> anyhow::Result<()> {
+    if mode == PruneMode::Off || (mode == PruneMode::Changed && !changed) {
+        return Ok(());
+    }
+    let declared = DeclaredTaxonomy::from_toml_file(&config.config_toml_path)?;
+    let pending = diff
+        .as_deref()
+        .map(SeedDiff::pending_links)
+        .unwrap_or_default();
+    let report = find_orphans(db, &declared, fixed_content_slugs, &pending).await?;
+    if report.is_empty() {
+        return Ok(());
+    }
+    match (mode, diff) {
+        (PruneMode::Delete, Some(diff)) => diff.record_prune(&report),
+        (PruneMode::Delete, None) => {
+            delete_orphans(db, &report).await?;
+            println!("🧹 孤立したタグ / カテゴリ / 固定ページを削除しました\n{report}");
+        }
+        _ => println!("⚠️ 孤立したタグ / カテゴリ / 固定ページがあります\n{report}"),
+    }
     Ok(())
 }
 
-async fn run_article_seed(db: &DatabaseConnection, dir: &str) -> Result<(), anyhow::Error> {
-    for path in markdown_files(dir) {
+fn git_history(options: &SeedOptions, dir: &str) -> Option<GitHistory> {
+    if !options.git_dates {
+        return None;
+    }
+    match GitHistory::load(dir) {
+        Ok(history) => Some(history),
+        Err(e) => {
+            eprintln!("⚠️ git の履歴を読めないため日付の補完をスキップします: {e:#}");
+            None
+        }
+    }
+}
+
+/// Site-wide images from `[common]`, reported against the config file.
+fn check_config_references(
+    references: &ReferenceChecker<'_>,
+    toml_path: &str,
+    errors: &mut SeedErrorReport,
+) {
+    let common = load_config_from_file(toml_path);
+    for field in ["default_icatch_path", "favicon_path"] {
+        if let Some(url) = common.get(field).filter(|url| !url.is_empty()) {
+            references.check_path(Path::new(toml_path), Some(field), url, errors);
+        }
+    }
+}
+
+fn declared_taxonomy(
+    options: &SeedOptions,
+    toml_path: &str,
+) -> anyhow::Result<Option<DeclaredTaxonomy>> {
+    if !options.strict_taxonomy {
+        return Ok(None);
+    }
+    Ok(Some(DeclaredTaxonomy::from_toml_file(toml_path)?))
+}
+
+async fn run_article_seed<C>(
+    db: &C,
+    paths: impl Iterator<Item = PathBuf>,
+    taxonomy: Option<&DeclaredTaxonomy>,
+    history: Option<&GitHistory>,
+    references: &ReferenceChecker<'_>,
+    errors: &mut SeedErrorReport,
+    mut diff: Option<&mut SeedDiff>,
+) -> Result<SeedSummary, anyhow::Error>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut summary = SeedSummary::default();
+    for path in paths {
         println!("{:?}", path);
         let (front_matter, body) = match parse_markdown_to_front_matter(&path) {
             Ok(x) => x,
             Err(e) => {
-                eprintln!("FrontMatter parse error {:?}", e);
+                errors.push(SeedError::from_markdown(path, e));
                 continue;
             }
         };
 
+        if let Some(bundle) = bundle_slug(&path).filter(|bundle| *bundle != front_matter.slug) {
+            let message = format!("must match the bundle directory {bundle:?}");
+            errors.push(SeedError::new(path, Some("slug".to_string()), message));
+            continue;
+        }
+
         if front_matter.deleted {
-            delete_article_by_slug(db, &front_matter.slug).await?;
+            let deleted = match diff.as_deref_mut() {
+                Some(diff) => {
+                    let stored = article::stored_content_hash(db, &front_matter.slug).await?;
+                    if stored.is_some() {
+                        diff.deleted_articles.push(front_matter.slug.clone());
+                    }
+                    stored.is_some()
+                }
+                None => delete_article_by_slug(db, &front_matter.slug).await? > 0,
+            };
+            if deleted {
+                summary.deleted += 1;
+            }
+            continue;
+        }
+
+        if let Some(taxonomy) = taxonomy {
+            let violations = taxonomy.undeclared(&front_matter);
+            if !violations.is_empty() {
+                for violation in &violations {
+                    errors.push(SeedError::from_violation(path.clone(), violation));
+                }
+                continue;
+            }
+        }
+
+        let icatch_ok = front_matter
+            .icatch_path
+            .as_deref()
+            .is_none_or(|icatch_path| {
+                references.check_path(&path, Some("icatch_path"), icatch_path, errors)
+            });
+        let base_url = bundle_url(&front_matter.slug);
+        let images_ok = references.check_markdown(&path, &body, Some(&base_url), errors);
+        if !(icatch_ok && images_ok) {
             continue;
         }
 
-        let article_id = seed_article(db, &front_matter, &body).await?;
-        seed_tag(db, &front_matter, article_id).await?;
-        seed_category(db, &front_matter, article_id).await?;
+        let dates = history
+            .map(|history| history.dates_for(&path))
+            .unwrap_or_default();
+        let hash = content_hash(&(&front_matter, &dates), &body);
+        let stored = article::stored_content_hash(db, &front_matter.slug).await?;
+        let status = SeedStatus::classify(stored, &hash);
+        if status == SeedStatus::Unchanged {
+            summary.record(status);
+            continue;
+        }
+
+        if let Some(diff) = diff.as_deref_mut() {
+            match diff.record_article(db, &front_matter, &body, &dates).await {
+                Ok(()) => summary.record(status),
+                Err(e) => errors.extend(SeedError::from_seed_error(path, &e)),
+            }
+            continue;
+        }
+        let txn = db.begin().await?;
+        let seeded: anyhow::Result<()> = async {
+            let article_id = seed_article(&txn, &front_matter, &body, &hash, &dates).await?;
+            seed_tag(&txn, &front_matter, article_id).await?;
+            seed_category(&txn, &front_matter, article_id).await?;
+            Ok(())
+        }
+        .await;
+        match seeded {
+            Ok(()) => {
+                txn.commit().await?;
+                summary.record(status);
+            }
+            Err(e) => {
+                txn.rollback().await?;
+                errors.extend(SeedError::from_seed_error(path, &e));
+            }
+        }
     }
-    Ok(())
+    println!("📊 Article: {summary}");
+    Ok(summary)
 }
 
-async fn run_fixed_content_seed(db: &DatabaseConnection, dir: &str) -> Result<(), anyhow::Error> {
-    for path in markdown_files(dir) {
+/// Returns the slugs seen in `dir`, or `None` when some file could not be parsed
+/// or `scope` skipped some, along with what was written.
+async fn run_fixed_content_seed<C: ConnectionTrait>(
+    db: &C,
+    dir: &str,
+    scope: SeedScope<'_>,
+    history: Option<&GitHistory>,
+    references: &ReferenceChecker<'_>,
+    errors: &mut SeedErrorReport,
+    mut diff: Option<&mut SeedDiff>,
+) -> Result<(Option<HashSet<String>>, SeedSummary), anyhow::Error> {
+    let mut slugs = HashSet::new();
+    let mut complete = matches!(scope, SeedScope::All);
+    let mut summary = SeedSummary::default();
+    for path in markdown_files(dir).filter(|path| scope.reads(path)) {
         println!("{:?}", path);
         let (front_matter, body) = match parse_markdown_to_fixed_content_matter(&path) {
             Ok(x) => x,
             Err(e) => {
-                eprintln!("FrontMatter parse error {:?}", e);
+                errors.push(SeedError::from_markdown(path, e));
+                complete = false;
                 continue;
             }
         };
-        seed_fixed_content(db, &front_matter, &body).await?;
+        slugs.insert(front_matter.slug.clone());
+        if !references.check_markdown(&path, &body, None, errors) {
+            continue;
+        }
+        let dates = history
+            .map(|history| history.dates_for(&path))
+            .unwrap_or_default();
+        let hash = content_hash(&(&front_matter, &dates), &body);
+        let stored = fixed_content::stored_content_hash(db, &front_matter.slug).await?;
+        let status = SeedStatus::classify(stored, &hash);
+        if let Some(diff) = diff.as_deref_mut() {
+            diff.record_fixed_content(&front_matter.slug, status);
+        } else if status != SeedStatus::Unchanged
+            && let Err(e) = seed_fixed_content(db, &front_matter, &body, &hash, &dates).await
+        {
+            errors.extend(SeedError::from_seed_error(path, &e));
+            continue;
+        }
+        summary.record(status);
     }
-    Ok(())
+    println!("📊 固定ページ: {summary}");
+    Ok((complete.then_some(slugs), summary))
 }
@@ -1,42 +1,64 @@
 pub mod seed;
 use crate::entity::{article, article::ActiveModel, article_tag};
 use crate::entity::{article_category, category, tag};
+use crate::seed::{datetime::resolve_updated_at, git::SourceDates};
 use crate::utils;
 use sea_orm::ActiveValue::Set;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QuerySelect,
 };
 use seed::{prepare, upsert, validate};
+use std::{collections::HashSet, hash::Hash};
 use utils::front_matter::FrontMatter;
 
-pub async fn seed_article(
-    db: &DatabaseConnection,
+pub async fn seed_article<C: ConnectionTrait>(
+    db: &C,
     front_matter: &FrontMatter,
     body: &str,
+    content_hash: &str,
+    dates: &SourceDates,
 ) -> Result<i32, anyhow::Error> {
-    let active_model: ActiveModel = prepare(db, front_matter, body).await?;
+    let active_model: ActiveModel = prepare(db, front_matter, body, dates).await?;
     validate(front_matter, body)?;
-    let article_id = upsert(db, active_model).await?;
+    let updated_at = resolve_updated_at(front_matter.updated_at.as_deref(), dates.updated_at)?;
+    let article_id = upsert(db, active_model, content_hash, updated_at).await?;
     Ok(article_id)
 }
 
-pub async fn delete_article_by_slug(db: &DatabaseConnection, slug: &str) -> Result<(), DbErr> {
+/// Returns `None` when no article has `slug`, otherwise its stored content hash.
+pub async fn stored_content_hash<C: ConnectionTrait>(
+    db: &C,
+    slug: &str,
+) -> Result<Option<Option<String>>, DbErr> {
+    article::Entity::find()
+        .select_only()
+        .column(article::Column::ContentHash)
+        .filter(article::Column::Slug.eq(slug))
+        .into_tuple()
+        .one(db)
+        .await
+}
+
+/// Returns the number of deleted rows.
+pub async fn delete_article_by_slug<C: ConnectionTrait>(db: &C, slug: &str) -> Result<u64, DbErr> {
     if slug.trim().is_empty() {
         return Err(DbErr::Custom("slug is empty".into()));
     }
 
-    article::Entity::delete_many()
+    let result = article::Entity::delete_many()
         .filter(article::Column::Slug.eq(slug))
         .exec(db)
         .await?;
-    Ok(())
+    Ok(result.rows_affected)
 }
 
-pub async fn seed_tag(
-    db: &DatabaseConnection,
+/// Makes the article's `article_tag` rows match `front_matter.tags` exactly.
+pub async fn seed_tag<C: ConnectionTrait>(
+    db: &C,
     front_matter: &FrontMatter,
     article_id: i32,
 ) -> Result<(), DbErr> {
+    let mut tag_ids = Vec::with_capacity(front_matter.tags.len());
     for tag_slug in &front_matter.tags {
         let existing = tag::Entity::find()
             .filter(tag::Column::Slug.eq(tag_slug.as_str()))
@@ -54,31 +76,45 @@ pub async fn seed_tag(
             .await?
             .id
         };
+        tag_ids.push(tag_id);
+    }
 
-        let exists_link = article_tag::Entity::find()
-            .filter(article_tag::Column::ArticleId.eq(article_id))
-            .filter(article_tag::Column::TagId.eq(tag_id))
-            .one(db)
-            .await?
-            .is_some();
+    let linked: Vec<i32> = article_tag::Entity::find()
+        .select_only()
+        .column(article_tag::Column::TagId)
+        .filter(article_tag::Column::ArticleId.eq(article_id))
+        .into_tuple()
+        .all(db)
+        .await?;
+    let (added, removed) = diff_links(&linked, &tag_ids);
 
-        if !exists_link {
+    if !removed.is_empty() {
+        article_tag::Entity::delete_many()
+            .filter(article_tag::Column::ArticleId.eq(article_id))
+            .filter(article_tag::Column::TagId.is_in(removed))
+            .exec(db)
+            .await?;
+    }
+    if !added.is_empty() {
+        article_tag::Entity::insert_many(added.into_iter().map(|tag_id| {
             article_tag::ActiveModel {
                 article_id: Set(article_id),
                 tag_id: Set(tag_id),
             }
-            .insert(db)
-            .await?;
-        }
+        }))
+        .exec_without_returning(db)
+        .await?;
     }
     Ok(())
 }
 
-pub async fn seed_category(
-    db: &DatabaseConnection,
+/// Makes the article's `article_category` rows match `front_matter.categories` exactly.
+pub async fn seed_category<C: ConnectionTrait>(
+    db: &C,
     front_matter: &FrontMatter,
     article_id: i32,
 ) -> Result<(), DbErr> {
+    let mut category_ids = Vec::with_capacity(front_matter.categories.len());
     for category_slug in &front_matter.categories {
         let existing = category::Entity::find()
             .filter(category::Column::Slug.eq(category_slug.as_str()))
@@ -96,30 +132,61 @@ pub async fn seed_category(
             .await?
             .id
         };
+        category_ids.push(category_id);
+    }
 
-        let exists_link = article_category::Entity::find()
-            .filter(article_category::Column::ArticleId.eq(article_id))
-            .filter(article_category::Column::CategoryId.eq(category_id))
-            .one(db)
-            .await?
-            .is_some();
+    let linked: Vec<i32> = article_category::Entity::find()
+        .select_only()
+        .column(article_category::Column::CategoryId)
+        .filter(article_category::Column::ArticleId.eq(article_id))
+        .into_tuple()
+        .all(db)
+        .await?;
+    let (added, removed) = diff_links(&linked, &category_ids);
 
-        if !exists_link {
+    if !removed.is_empty() {
+        article_category::Entity::delete_many()
+            .filter(article_category::Column::ArticleId.eq(article_id))
+            .filter(article_category::Column::CategoryId.is_in(removed))
+            .exec(db)
+            .await?;
+    }
+    if !added.is_empty() {
+        article_category::Entity::insert_many(added.into_iter().map(|category_id| {
             article_category::ActiveModel {
                 article_id: Set(article_id),
                 category_id: Set(category_id),
             }
-            .insert(db)
-            .await?;
-        }
+        }))
+        .exec_without_returning(db)
+        .await?;
     }
     Ok(())
 }
 
+/// Returns `(added, removed)` values needed to turn `linked` into `desired`.
+pub(crate) fn diff_links<T: Clone + Eq + Hash>(linked: &[T], desired: &[T]) -> (Vec<T>, Vec<T>) {
+    let linked_set: HashSet<&T> = linked.iter().collect();
+    let desired_set: HashSet<&T> = desired.iter().collect();
+    let mut seen = HashSet::new();
+    let added = desired
+        .iter()
+        .filter(|value| !linked_set.contains(value) && seen.insert(*value))
+        .cloned()
+        .collect();
+    let removed = linked
+        .iter()
+        .filter(|value| !desired_set.contains(value))
+        .cloned()
+        .collect();
+    (added, removed)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{delete_article_by_slug, seed_article};
+    use super::{delete_article_by_slug, diff_links, seed_article};
     use crate::entity::article;
+    use crate::seed::git::SourceDates;
     use crate::utils::front_matter::FrontMatter;
     use chrono::{TimeZone, Utc};
     use rocket::tokio;
@@ -149,6 +216,7 @@ mod tests {
             created_at: ts.unwrap(),
             updated_at: ts.unwrap(),
             icatch_path: None,
+            content_hash: None,
         }
     }
 
@@ -165,7 +233,7 @@ mod tests {
             .append_query_results([Vec::<article::Model>::new(), vec![returned.clone()]])
             .into_connection();
 
-        let article_id = seed_article(&db, &front_matter, "body")
+        let article_id = seed_article(&db, &front_matter, "body", "hash", &SourceDates::default())
             .await
             .expect("seed should insert");
         assert_eq!(article_id, 1);
@@ -185,9 +253,15 @@ mod tests {
             .append_query_results([vec![existing], vec![returned]])
             .into_connection();
 
-        let article_id = seed_article(&db, &front_matter, "new body")
-            .await
-            .expect("seed should insert");
+        let article_id = seed_article(
+            &db,
+            &front_matter,
+            "new body",
+            "hash",
+            &SourceDates::default(),
+        )
+        .await
+        .expect("seed should insert");
 
         assert_eq!(article_id, 7);
         Ok(())
@@ -201,7 +275,8 @@ mod tests {
             .append_query_results([Vec::<article::Model>::new()])
             .into_connection();
 
-        let result = seed_article(&db, &front_matter, "body").await;
+        let result =
+            seed_article(&db, &front_matter, "body", "hash", &SourceDates::default()).await;
         assert!(result.is_err());
     }
 
@@ -224,12 +299,26 @@ mod tests {
             .append_query_results([Vec::<article::Model>::new(), vec![returned.clone()]])
             .into_connection();
 
-        let article_id = seed_article(&db, &front_matter, &body)
+        let article_id = seed_article(&db, &front_matter, &body, "hash", &SourceDates::default())
             .await
             .expect("seed should insert");
         assert_eq!(article_id, 1);
     }
 
+    #[test]
+    fn diff_links_returns_added_and_removed_ids() {
+        let (added, removed) = diff_links(&[1, 2, 3], &[3, 4, 4, 1]);
+        assert_eq!(added, vec![4]);
+        assert_eq!(removed, vec![2]);
+    }
+
+    #[test]
+    fn diff_links_is_empty_when_links_match() {
+        let (added, removed) = diff_links(&[2, 1], &[1, 2]);
+        assert!(added.is_empty());
+        assert!(removed.is_empty());
+    }
+
     #[tokio::test]
     async fn test_delete_article_by_slug_executes_delete() {
         let db = MockDatabase::new(DbBackend::Sqlite)
@@ -240,7 +329,7 @@ mod tests {
             .into_connection();
 
         let result = delete_article_by_slug(&db, "to-delete").await;
-        assert!(result.is_ok());
+        assert_eq!(result.expect("delete should succeed"), 1);
     }
 
     #[tokio::test]
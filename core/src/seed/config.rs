@@ -1,3 +1,4 @@
+use crate::seed::prune::PruneMode;
 use serde::{Deserialize, Serialize};
 pub mod env;
 pub mod seed;
@@ -9,6 +10,16 @@ pub struct PathConfig {
     pub config_toml_path: String,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct SeedOptions {
+    pub strict_taxonomy: bool,
+    pub prune: PruneMode,
+    /// Commit each file as it is seeded instead of wrapping the whole run in one transaction.
+    pub best_effort: bool,
+    /// Fall back to the first / last git commit of each article for `created_at` / `updated_at`.
+    pub git_dates: bool,
+}
+
 pub trait PathConfigTrait {
     fn new(
         fixed_content_path: Option<String>,
@@ -1,4 +1,8 @@
-use crate::seed::config::{PathConfig, PathConfigTrait};
+use crate::seed::{
+    config::{PathConfig, PathConfigTrait, SeedOptions},
+    image::ImageConfig,
+    prune::PruneMode,
+};
 use dotenvy::dotenv;
 use std::env;
 pub fn load_env() -> PathConfig {
@@ -9,3 +13,62 @@ pub fn load_env() -> PathConfig {
         env::var("CONFIG_TOML_PATH").ok().or_else(|| None),
     )
 }
+
+pub fn load_image_config() -> ImageConfig {
+    let _ = dotenv();
+    ImageConfig::new(
+        env::var("IMAGE_PATH").ok(),
+        env::var("ICON_PATH").ok(),
+        env::var("IMAGE_VARIANT_PATH").ok(),
+    )
+}
+
+pub fn load_seed_options() -> SeedOptions {
+    let _ = dotenv();
+    SeedOptions {
+        strict_taxonomy: env_flag("SEED_STRICT_TAXONOMY"),
+        prune: env_prune_mode("SEED_PRUNE"),
+        best_effort: env_flag("SEED_BEST_EFFORT"),
+        git_dates: env_flag("SEED_GIT_DATES"),
+    }
+}
+
+fn env_prune_mode(key: &str) -> PruneMode {
+    match env::var(key) {
+        Ok(value) => PruneMode::parse(&value).unwrap_or_else(|| {
+            eprintln!("unknown {key}={value:?}, expected off / report / delete");
+            PruneMode::default()
+        }),
+        Err(_) => PruneMode::default(),
+    }
+}
+
+fn env_flag(key: &str) -> bool {
+    env::var(key).is_ok_and(|value| parse_flag(&value))
+}
+
+fn parse_flag(value: &str) -> bool {
+    matches!(
+        value.trim().to_ascii_lowercase().as_str(),
+        "1" | "true" | "yes" | "on"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_flag;
+
+    #[test]
+    fn parse_flag_accepts_truthy_values() {
+        for value in ["1", "true", "TRUE", " yes ", "on"] {
+            assert!(parse_flag(value), "{value} should be truthy");
+        }
+    }
+
+    #[test]
+    fn parse_flag_rejects_other_values() {
+        for value in ["", "0", "false", "off", "strict"] {
+            assert!(!parse_flag(value), "{value} should be falsy");
+        }
+    }
+}
@@ -7,16 +7,13 @@ use crate::{
 };
 use anyhow::Context;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel, QueryFilter,
     Value,
 };
 
-pub async fn seed_from_toml<T>(
-    db: &DatabaseConnection,
-    toml_path: &str,
-    entity_name: &str,
-) -> anyhow::Result<()>
+pub async fn seed_from_toml<T, C>(db: &C, toml_path: &str, entity_name: &str) -> anyhow::Result<()>
 where
+    C: ConnectionTrait,
     T: EntityTrait + NameSlugEntity,
     T::Column: ColumnTrait + Copy,
     T::Model: NameSlugModel,
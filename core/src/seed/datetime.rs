@@ -0,0 +1,148 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Asia::Tokyo;
+use sea_orm::DbErr;
+
+/// Front matter wins, then the source dates (git history), then the stored value, then `now`.
+/// `None` means the stored value is kept.
+pub fn resolve_created_at(
+    existing_created_at: Option<DateTime<Utc>>,
+    raw: Option<&str>,
+    source_created_at: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>, DbErr> {
+    match raw {
+        Some(raw) => Ok(Some(parse_datetime("created_at", raw)?)),
+        None if source_created_at.is_some() => Ok(source_created_at),
+        None => {
+            if existing_created_at.is_some() {
+                Ok(None)
+            } else {
+                Ok(Some(now))
+            }
+        }
+    }
+}
+
+/// `None` means `updated_at` is bumped only when the row actually changed.
+pub fn resolve_updated_at(
+    raw: Option<&str>,
+    source_updated_at: Option<DateTime<Utc>>,
+) -> Result<Option<DateTime<Utc>>, DbErr> {
+    match raw {
+        Some(raw) => Ok(Some(parse_datetime("updated_at", raw)?)),
+        None => Ok(source_updated_at),
+    }
+}
+
+/// Accepts RFC3339, or a JST local `YYYY-MM-DD[ T]HH:MM:SS` / `YYYY-MM-DD`.
+pub fn parse_datetime(field: &str, raw: &str) -> Result<DateTime<Utc>, DbErr> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(raw) {
+        return Ok(dt.with_timezone(&Utc));
+    }
+
+    if let Ok(naive_dt) = NaiveDateTime::parse_from_str(raw, "%Y-%m-%d %H:%M:%S") {
+        let dt = Tokyo
+            .from_local_datetime(&naive_dt)
+            .single()
+            .ok_or_else(|| DbErr::Custom(format!("invalid JST local datetime: {raw}")))?;
+        return Ok(dt.with_timezone(&Utc));
+    }
+
+    if let Ok(naive_dt) = NaiveDateTime::parse_from_str(raw, "%Y-%m-%dT%H:%M:%S") {
+        let dt = Tokyo
+            .from_local_datetime(&naive_dt)
+            .single()
+            .ok_or_else(|| DbErr::Custom(format!("invalid JST local datetime: {raw}")))?;
+        return Ok(dt.with_timezone(&Utc));
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(raw, "%Y-%m-%d") {
+        if let Some(naive_dt) = date.and_hms_opt(0, 0, 0) {
+            let dt = Tokyo
+                .from_local_datetime(&naive_dt)
+                .single()
+                .ok_or_else(|| DbErr::Custom(format!("invalid JST local datetime: {raw}")))?;
+            return Ok(dt.with_timezone(&Utc));
+        }
+    }
+
+    Err(DbErr::Custom(format!(
+        "invalid {field} format: {raw} (expected RFC3339 or JST local datetime YYYY-MM-DD[ T]HH:MM:SS or YYYY-MM-DD)"
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_datetime, resolve_created_at, resolve_updated_at};
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn resolve_created_at_uses_front_matter_value_when_present() {
+        let now = Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        let result = resolve_created_at(None, Some("2026-01-01T12:34:56Z"), None, now)
+            .expect("created_at should parse");
+        assert_eq!(
+            result,
+            Some(Utc.with_ymd_and_hms(2026, 1, 1, 12, 34, 56).unwrap())
+        );
+    }
+
+    #[test]
+    fn resolve_created_at_uses_now_for_new_article_when_missing() {
+        let now = Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        let result = resolve_created_at(None, None, None, now).expect("should use now");
+        assert_eq!(result, Some(now));
+    }
+
+    #[test]
+    fn resolve_created_at_keeps_existing_for_existing_article_when_missing() {
+        let now = Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        let existing = Utc.with_ymd_and_hms(2025, 2, 1, 0, 0, 0).unwrap();
+        let result =
+            resolve_created_at(Some(existing), None, None, now).expect("should keep existing");
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn resolve_created_at_prefers_source_date_over_existing_when_missing() {
+        let now = Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        let existing = Utc.with_ymd_and_hms(2025, 2, 1, 0, 0, 0).unwrap();
+        let committed = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+        let result = resolve_created_at(Some(existing), None, Some(committed), now)
+            .expect("should use source date");
+        assert_eq!(result, Some(committed));
+    }
+
+    #[test]
+    fn resolve_updated_at_prefers_front_matter_over_source_date() {
+        let committed = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+        let explicit = Utc.with_ymd_and_hms(2026, 2, 28, 15, 0, 0).unwrap();
+        assert_eq!(
+            resolve_updated_at(Some("2026-03-01"), Some(committed)).unwrap(),
+            Some(explicit)
+        );
+        assert_eq!(
+            resolve_updated_at(None, Some(committed)).unwrap(),
+            Some(committed)
+        );
+        assert_eq!(resolve_updated_at(None, None).unwrap(), None);
+    }
+
+    #[test]
+    fn resolve_updated_at_reports_field_on_invalid_value() {
+        let err = resolve_updated_at(Some("yesterday"), None).unwrap_err();
+        assert!(err.to_string().contains("invalid updated_at format"));
+    }
+
+    #[test]
+    fn parse_datetime_treats_naive_datetime_as_jst() {
+        let parsed = parse_datetime("created_at", "2026-02-18 09:30:00").expect("must parse");
+        assert_eq!(parsed, Utc.with_ymd_and_hms(2026, 2, 18, 0, 30, 0).unwrap());
+    }
+
+    #[test]
+    fn parse_datetime_treats_date_only_as_jst_midnight() {
+        let parsed = parse_datetime("created_at", "2026-02-18").expect("must parse");
+        assert_eq!(parsed, Utc.with_ymd_and_hms(2026, 2, 17, 15, 0, 0).unwrap());
+    }
+}
@@ -0,0 +1,384 @@
+use crate::entity::{article, article_category, article_tag, category, tag};
+use crate::seed::{
+    article::{
+        diff_links,
+        seed::{prepare, validate},
+    },
+    git::SourceDates,
+    incremental::SeedStatus,
+    prune::{OrphanReport, OrphanTaxonomy, PendingLinks},
+    taxonomy::TaxonomyKind,
+};
+use crate::utils::front_matter::FrontMatter;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, IdenStatic,
+    Iterable, QueryFilter, QuerySelect, RelationTrait, sea_query::JoinType,
+};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArticleChange {
+    pub slug: String,
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkChange {
+    pub slug: String,
+    pub kind: TaxonomyKind,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// What a seed run would write, collected by `seed --dry-run`.
+#[derive(Debug, Default)]
+pub struct SeedDiff {
+    pub new_articles: Vec<String>,
+    pub changed_articles: Vec<ArticleChange>,
+    pub deleted_articles: Vec<String>,
+    pub links: Vec<LinkChange>,
+    pub new_fixed_contents: Vec<String>,
+    pub changed_fixed_contents: Vec<String>,
+    /// Tags, categories and fixed contents `SEED_PRUNE=delete` would remove.
+    pub pruned: Vec<(&'static str, String)>,
+}
+
+impl SeedDiff {
+    pub fn is_empty(&self) -> bool {
+        self.new_articles.is_empty()
+            && self.changed_articles.is_empty()
+            && self.deleted_articles.is_empty()
+            && self.links.is_empty()
+            && self.new_fixed_contents.is_empty()
+            && self.changed_fixed_contents.is_empty()
+            && self.pruned.is_empty()
+    }
+
+    pub fn record_fixed_content(&mut self, slug: &str, status: SeedStatus) {
+        match status {
+            SeedStatus::Added => self.new_fixed_contents.push(slug.to_string()),
+            SeedStatus::Updated => self.changed_fixed_contents.push(slug.to_string()),
+            SeedStatus::Unchanged => {}
+        }
+    }
+
+    /// The links this run would add and remove, for finding the orphans it leaves.
+    pub fn pending_links(&self) -> PendingLinks {
+        let mut pending = PendingLinks {
+            deleted_articles: self.deleted_articles.iter().cloned().collect(),
+            ..Default::default()
+        };
+        for link in &self.links {
+            pending
+                .added
+                .extend(link.added.iter().map(|slug| (link.kind, slug.clone())));
+            pending.removed.extend(
+                link.removed
+                    .iter()
+                    .map(|slug| (link.kind, link.slug.clone(), slug.clone())),
+            );
+        }
+        pending
+    }
+
+    /// Records what `delete_orphans` would remove from a report found with
+    /// [`SeedDiff::pending_links`].
+    pub fn record_prune(&mut self, report: &OrphanReport) {
+        let deletable = |orphans: &[OrphanTaxonomy]| {
+            orphans
+                .iter()
+                .filter(|orphan| !orphan.scheduled)
+                .map(|orphan| orphan.slug.clone())
+                .collect::<Vec<_>>()
+        };
+        let tags = deletable(&report.tags);
+        let categories = deletable(&report.categories);
+        let pruned = tags
+            .into_iter()
+            .map(|slug| ("tag", slug))
+            .chain(categories.into_iter().map(|slug| ("category", slug)))
+            .chain(
+                report
+                    .fixed_contents
+                    .iter()
+                    .map(|orphan| ("fixed content", orphan.slug.clone())),
+            );
+        self.pruned.extend(pruned);
+    }
+
+    /// Compares `front_matter` and `body` with the stored article without writing anything.
+    /// Fails where `seed_article` would.
+    pub async fn record_article<C: ConnectionTrait>(
+        &mut self,
+        db: &C,
+        front_matter: &FrontMatter,
+        body: &str,
+        dates: &SourceDates,
+    ) -> anyhow::Result<()> {
+        let active_model = prepare(db, front_matter, body, dates).await?;
+        validate(front_matter, body)?;
+        let slug = front_matter.slug.clone();
+        let (linked_tags, linked_categories) = match active_model.id {
+            ActiveValue::Set(id) | ActiveValue::Unchanged(id) => {
+                let fields = changed_fields(&active_model);
+                if !fields.is_empty() {
+                    self.changed_articles.push(ArticleChange {
+                        slug: slug.clone(),
+                        fields,
+                    });
+                }
+                (
+                    linked_tag_slugs(db, id).await?,
+                    linked_category_slugs(db, id).await?,
+                )
+            }
+            ActiveValue::NotSet => {
+                self.new_articles.push(slug.clone());
+                (Vec::new(), Vec::new())
+            }
+        };
+        self.push_links(&slug, TaxonomyKind::Tag, &linked_tags, &front_matter.tags);
+        self.push_links(
+            &slug,
+            TaxonomyKind::Category,
+            &linked_categories,
+            &front_matter.categories,
+        );
+        Ok(())
+    }
+
+    fn push_links(
+        &mut self,
+        slug: &str,
+        kind: TaxonomyKind,
+        linked: &[String],
+        desired: &[String],
+    ) {
+        let (added, removed) = diff_links(linked, desired);
+        if added.is_empty() && removed.is_empty() {
+            return;
+        }
+        self.links.push(LinkChange {
+            slug: slug.to_string(),
+            kind,
+            added,
+            removed,
+        });
+    }
+}
+
+impl fmt::Display for SeedDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for slug in &self.new_articles {
+            writeln!(f, "  + article: {slug}")?;
+        }
+        for change in &self.changed_articles {
+            writeln!(
+                f,
+                "  ~ article: {} ({})",
+                change.slug,
+                change.fields.join(", ")
+            )?;
+        }
+        for slug in &self.deleted_articles {
+            writeln!(f, "  - article: {slug}")?;
+        }
+        for link in &self.links {
+            for value in &link.added {
+                writeln!(f, "  + {}: {} -> {value}", link.kind, link.slug)?;
+            }
+            for value in &link.removed {
+                writeln!(f, "  - {}: {} -> {value}", link.kind, link.slug)?;
+            }
+        }
+        for slug in &self.new_fixed_contents {
+            writeln!(f, "  + fixed content: {slug}")?;
+        }
+        for slug in &self.changed_fixed_contents {
+            writeln!(f, "  ~ fixed content: {slug}")?;
+        }
+        for (label, slug) in &self.pruned {
+            writeln!(f, "  - {label}: {slug} (pruned)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Columns `prepare` would overwrite. Bookkeeping columns set by `upsert` are left out.
+fn changed_fields(active_model: &article::ActiveModel) -> Vec<String> {
+    article::Column::iter()
+        .filter(|column| {
+            !matches!(
+                column,
+                article::Column::Id | article::Column::UpdatedAt | article::Column::ContentHash
+            )
+        })
+        .filter(|column| matches!(active_model.get(*column), ActiveValue::Set(_)))
+        .map(|column| column.as_str().to_string())
+        .collect()
+}
+
+async fn linked_tag_slugs<C: ConnectionTrait>(
+    db: &C,
+    article_id: i32,
+) -> Result<Vec<String>, DbErr> {
+    tag::Entity::find()
+        .select_only()
+        .column(tag::Column::Slug)
+        .join(JoinType::InnerJoin, tag::Relation::ArticleTag.def())
+        .filter(article_tag::Column::ArticleId.eq(article_id))
+        .into_tuple()
+        .all(db)
+        .await
+}
+
+async fn linked_category_slugs<C: ConnectionTrait>(
+    db: &C,
+    article_id: i32,
+) -> Result<Vec<String>, DbErr> {
+    category::Entity::find()
+        .select_only()
+        .column(category::Column::Slug)
+        .join(
+            JoinType::InnerJoin,
+            category::Relation::ArticleCategory.def(),
+        )
+        .filter(article_category::Column::ArticleId.eq(article_id))
+        .into_tuple()
+        .all(db)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArticleChange, LinkChange, SeedDiff, changed_fields};
+    use crate::entity::article;
+    use crate::seed::git::SourceDates;
+    use crate::seed::incremental::SeedStatus;
+    use crate::seed::prune::{OrphanFixedContent, OrphanReport, OrphanTaxonomy};
+    use crate::seed::taxonomy::TaxonomyKind;
+    use crate::utils::front_matter::FrontMatter;
+    use chrono::{TimeZone, Utc};
+    use rocket::tokio;
+    use sea_orm::{ActiveValue::Set, DbBackend, IntoActiveModel, MockDatabase};
+    use std::collections::HashSet;
+
+    #[test]
+    fn changed_fields_lists_set_columns_only() {
+        let ts = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        let mut active_model = article::Model {
+            id: 1,
+            title: "Old".to_string(),
+            slug: "slug".to_string(),
+            excerpt: None,
+            content: "body".to_string(),
+            created_at: ts,
+            updated_at: ts,
+            icatch_path: None,
+            content_hash: None,
+        }
+        .into_active_model();
+        active_model.title = Set("New".to_string());
+        active_model.updated_at = Set(ts);
+        active_model.content_hash = Set(Some("hash".to_string()));
+
+        assert_eq!(changed_fields(&active_model), vec!["title"]);
+    }
+
+    #[tokio::test]
+    async fn record_article_rejects_what_seed_article_would() {
+        let db = MockDatabase::new(DbBackend::Sqlite)
+            .append_query_results([Vec::<article::Model>::new()])
+            .into_connection();
+        let front_matter = FrontMatter::new(
+            "t".repeat(51),
+            "long-title".to_string(),
+            false,
+            None,
+            None,
+            None,
+            vec![],
+            vec![],
+        );
+        let mut diff = SeedDiff::default();
+
+        let result = diff
+            .record_article(&db, &front_matter, "body", &SourceDates::default())
+            .await;
+        assert!(result.is_err());
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn display_lists_articles_and_links() {
+        let diff = SeedDiff {
+            new_articles: vec!["new-post".to_string()],
+            changed_articles: vec![ArticleChange {
+                slug: "old-post".to_string(),
+                fields: vec!["title".to_string(), "content".to_string()],
+            }],
+            deleted_articles: vec!["gone".to_string()],
+            links: vec![LinkChange {
+                slug: "old-post".to_string(),
+                kind: TaxonomyKind::Tag,
+                added: vec!["rust".to_string()],
+                removed: vec!["go".to_string()],
+            }],
+            ..Default::default()
+        };
+        assert!(!diff.is_empty());
+        assert_eq!(
+            diff.to_string(),
+            "  + article: new-post\n  ~ article: old-post (title, content)\n  - article: gone\n  + tag: old-post -> rust\n  - tag: old-post -> go\n"
+        );
+    }
+
+    #[test]
+    fn records_fixed_contents_and_pruned_rows() {
+        let orphan = |id, slug: &str, scheduled| OrphanTaxonomy {
+            id,
+            slug: slug.to_string(),
+            scheduled,
+        };
+        let mut diff = SeedDiff {
+            links: vec![LinkChange {
+                slug: "post".to_string(),
+                kind: TaxonomyKind::Tag,
+                added: vec!["relinked".to_string()],
+                removed: vec!["old".to_string()],
+            }],
+            deleted_articles: vec!["gone".to_string()],
+            ..Default::default()
+        };
+        let pending = diff.pending_links();
+        assert_eq!(
+            pending.added,
+            HashSet::from([(TaxonomyKind::Tag, "relinked".to_string())])
+        );
+        assert_eq!(
+            pending.removed,
+            HashSet::from([(TaxonomyKind::Tag, "post".to_string(), "old".to_string())])
+        );
+        assert_eq!(
+            pending.deleted_articles,
+            HashSet::from(["gone".to_string()])
+        );
+
+        diff.record_fixed_content("about", SeedStatus::Added);
+        diff.record_fixed_content("privacy", SeedStatus::Updated);
+        diff.record_fixed_content("contact", SeedStatus::Unchanged);
+        diff.record_prune(&OrphanReport {
+            tags: vec![orphan(1, "old", false), orphan(2, "scheduled", true)],
+            categories: vec![orphan(4, "misc", false)],
+            fixed_contents: vec![OrphanFixedContent {
+                id: 5,
+                slug: "legacy".to_string(),
+            }],
+        });
+
+        assert_eq!(
+            diff.to_string(),
+            "  - article: gone\n  + tag: post -> relinked\n  - tag: post -> old\n  + fixed content: about\n  ~ fixed content: privacy\n  - tag: old (pruned)\n  - category: misc (pruned)\n  - fixed content: legacy (pruned)\n"
+        );
+    }
+}
@@ -0,0 +1,209 @@
+use crate::seed::{
+    markdown::MarkdownError,
+    taxonomy::{TaxonomyKind, TaxonomyViolation},
+};
+use serde::Serialize;
+use std::{fmt, path::PathBuf};
+
+/// One problem found in a source file. `line` / `column` are only known for parse errors.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct SeedError {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub field: Option<String>,
+    pub message: String,
+}
+
+impl SeedError {
+    pub fn new(path: PathBuf, field: Option<String>, message: impl Into<String>) -> Self {
+        SeedError {
+            path,
+            line: None,
+            column: None,
+            field,
+            message: message.into(),
+        }
+    }
+
+    pub fn from_markdown(path: PathBuf, error: MarkdownError) -> Self {
+        match error {
+            MarkdownError::Invalid {
+                line,
+                column,
+                field,
+                message,
+            } => SeedError {
+                path,
+                line,
+                column,
+                field,
+                message,
+            },
+            other => SeedError::new(path, None, other.to_string()),
+        }
+    }
+
+    /// Splits a garde `Report` into one error per field; other errors become a single entry.
+    pub fn from_seed_error(path: PathBuf, error: &anyhow::Error) -> Vec<Self> {
+        match error.downcast_ref::<garde::Report>() {
+            Some(report) => report
+                .iter()
+                .map(|(field, e)| {
+                    let field = field.to_string();
+                    SeedError::new(
+                        path.clone(),
+                        (!field.is_empty()).then_some(field),
+                        e.message(),
+                    )
+                })
+                .collect(),
+            None => vec![SeedError::new(path, None, format!("{error:#}"))],
+        }
+    }
+
+    pub fn from_violation(path: PathBuf, violation: &TaxonomyViolation) -> Self {
+        let field = match violation.kind {
+            TaxonomyKind::Tag => "tags",
+            TaxonomyKind::Category => "categories",
+        };
+        SeedError::new(
+            path,
+            Some(field.to_string()),
+            format!("undeclared {}: {:?}", violation.kind, violation.value),
+        )
+    }
+}
+
+impl fmt::Display for SeedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{line}")?;
+            if let Some(column) = self.column {
+                write!(f, ":{column}")?;
+            }
+        }
+        match &self.field {
+            Some(field) => write!(f, ": {field}: {}", self.message),
+            None => write!(f, ": {}", self.message),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SeedErrorReport {
+    pub errors: Vec<SeedError>,
+    /// Problems worth fixing that do not fail the seed, such as images without alt text.
+    pub warnings: Vec<SeedError>,
+}
+
+impl SeedErrorReport {
+    pub fn push(&mut self, error: SeedError) {
+        self.errors.push(error);
+    }
+
+    pub fn push_warning(&mut self, warning: SeedError) {
+        self.warnings.push(warning);
+    }
+
+    pub fn extend(&mut self, errors: impl IntoIterator<Item = SeedError>) {
+        self.errors.extend(errors);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Number of distinct files with at least one error.
+    pub fn file_count(&self) -> usize {
+        let mut paths: Vec<&PathBuf> = self.errors.iter().map(|e| &e.path).collect();
+        paths.sort();
+        paths.dedup();
+        paths.len()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+impl fmt::Display for SeedErrorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for error in &self.errors {
+            writeln!(f, "  - {error}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SeedError, SeedErrorReport};
+    use crate::seed::{
+        markdown::MarkdownError,
+        taxonomy::{TaxonomyKind, TaxonomyViolation},
+    };
+    use std::path::PathBuf;
+
+    #[test]
+    fn from_markdown_keeps_yaml_location() {
+        let error = SeedError::from_markdown(
+            PathBuf::from("content/articles/a.md"),
+            MarkdownError::Invalid {
+                line: Some(2),
+                column: Some(8),
+                field: Some("title".to_string()),
+                message: "invalid type: sequence, expected a string".to_string(),
+            },
+        );
+        assert_eq!(
+            error.to_string(),
+            "content/articles/a.md:2:8: title: invalid type: sequence, expected a string"
+        );
+    }
+
+    #[test]
+    fn from_seed_error_splits_garde_report_by_field() {
+        let mut report = garde::Report::new();
+        report.append(
+            garde::Path::new("title"),
+            garde::Error::new("length is lower than 1"),
+        );
+        let errors = SeedError::from_seed_error(PathBuf::from("a.md"), &report.into());
+        assert_eq!(
+            errors,
+            vec![SeedError::new(
+                PathBuf::from("a.md"),
+                Some("title".to_string()),
+                "length is lower than 1"
+            )]
+        );
+    }
+
+    #[test]
+    fn report_counts_files_and_serializes_to_json() {
+        let mut report = SeedErrorReport::default();
+        report.push(SeedError::new(
+            PathBuf::from("a.md"),
+            None,
+            "FrontMatter not found",
+        ));
+        report.push(SeedError::from_violation(
+            PathBuf::from("b.md"),
+            &TaxonomyViolation {
+                kind: TaxonomyKind::Tag,
+                value: "blog".to_string(),
+            },
+        ));
+        report.push(SeedError::new(PathBuf::from("b.md"), None, "other"));
+        assert_eq!(report.file_count(), 2);
+        assert_eq!(
+            report.to_string(),
+            "  - a.md: FrontMatter not found\n  - b.md: tags: undeclared tag: \"blog\"\n  - b.md: other\n"
+        );
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["errors"][1]["field"], "tags");
+        assert_eq!(json["errors"][0]["line"], serde_json::Value::Null);
+    }
+}
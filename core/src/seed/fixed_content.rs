@@ -1,5 +1,9 @@
 use crate::entity;
 use crate::entity_extension::fixed_content::FixedContentValidator;
+use crate::seed::{
+    datetime::{resolve_created_at, resolve_updated_at},
+    git::SourceDates,
+};
 use crate::utils;
 use chrono::Timelike;
 use chrono::Utc;
@@ -8,25 +12,37 @@ use entity::fixed_content::{
 };
 use garde::Validate;
 use sea_orm::{
-    ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseConnection, DbErr, EntityTrait,
-    IntoActiveModel, QueryFilter, Set,
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, DbErr, EntityTrait,
+    IntoActiveModel, QueryFilter, QuerySelect,
 };
 use std::default::Default;
 use utils::fixed_content_matter::FixedContentMatter;
 
-pub async fn seed_fixed_content(
-    db: &DatabaseConnection,
+pub async fn seed_fixed_content<C: ConnectionTrait>(
+    db: &C,
     fixed_content_matter: &FixedContentMatter,
     body: &str,
+    content_hash: &str,
+    dates: &SourceDates,
 ) -> Result<i32, anyhow::Error> {
     let model: Option<FixedContentModel> = FixedContentEntity::find()
         .filter(FixedContentColumn::Slug.eq(fixed_content_matter.slug.clone()))
         .one(db)
         .await?;
+    let now = Utc::now().with_nanosecond(0).unwrap_or_else(Utc::now);
+    let existing_created_at = model.as_ref().map(|model| model.created_at);
     let mut active_model: entity::fixed_content::ActiveModel = match model {
         Some(model) => model.into_active_model(),
         None => Default::default(),
     };
+    if let Some(created_at) = resolve_created_at(
+        existing_created_at,
+        fixed_content_matter.created_at.as_deref(),
+        dates.created_at,
+        now,
+    )? {
+        active_model.created_at.set_if_not_equals(created_at);
+    }
     active_model
         .title
         .set_if_not_equals(fixed_content_matter.title.clone());
@@ -38,7 +54,6 @@ pub async fn seed_fixed_content(
         .set_if_not_equals(fixed_content_matter.excerpt.clone());
     active_model.content.set_if_not_equals(body.to_string());
 
-    let now = Utc::now().with_nanosecond(0).unwrap_or_else(Utc::now);
     let validator = FixedContentValidator {
         title: fixed_content_matter.title.clone(),
         slug: fixed_content_matter.slug.clone(),
@@ -47,28 +62,88 @@ pub async fn seed_fixed_content(
         created_at: now,
         updated_at: now,
     };
-    match validator.validate() {
-        Ok(_) => {}
-        Err(e) => {
-            println!("{:?}", e);
-            return Err(e.into());
-        }
-    }
+    validator.validate()?;
 
+    let updated_at =
+        match resolve_updated_at(fixed_content_matter.updated_at.as_deref(), dates.updated_at)? {
+            Some(updated_at) => Some(updated_at),
+            None if active_model.is_changed() => Some(now),
+            None => None,
+        };
     if active_model.is_changed() {
-        if let Some(utc) = Utc::now().with_nanosecond(0) {
-            active_model.updated_at = Set(utc);
-        }
         println!("{} updated", fixed_content_matter.title);
     }
+    if let Some(updated_at) = updated_at {
+        active_model.updated_at.set_if_not_equals(updated_at);
+    }
+    active_model
+        .content_hash
+        .set_if_not_equals(Some(content_hash.to_string()));
 
     let saved = active_model.save(db).await?;
     let fixed_content_id: i32 = match saved.id {
-        ActiveValue::Set(id) | ActiveValue::Unchanged(id) => {
-            println!("{} updated", fixed_content_matter.title);
-            id
-        }
+        ActiveValue::Set(id) | ActiveValue::Unchanged(id) => id,
         ActiveValue::NotSet => return Err(DbErr::Custom("fixed content id not set".into()).into()),
     };
     Ok(fixed_content_id)
 }
+
+/// Returns `None` when no fixed content has `slug`, otherwise its stored content hash.
+pub async fn stored_content_hash<C: ConnectionTrait>(
+    db: &C,
+    slug: &str,
+) -> Result<Option<Option<String>>, DbErr> {
+    FixedContentEntity::find()
+        .select_only()
+        .column(FixedContentColumn::ContentHash)
+        .filter(FixedContentColumn::Slug.eq(slug))
+        .into_tuple()
+        .one(db)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::seed_fixed_content;
+    use crate::entity::fixed_content;
+    use crate::seed::git::SourceDates;
+    use crate::utils::fixed_content_matter::FixedContentMatter;
+    use chrono::{TimeZone, Utc};
+    use rocket::tokio;
+    use sea_orm::{ConnectionTrait, Database, DbBackend, EntityTrait, Statement};
+
+    #[tokio::test]
+    async fn seed_fixed_content_takes_dates_from_git_history() {
+        let db = Database::connect("sqlite::memory:")
+            .await
+            .expect("failed to connect sqlite memory");
+        db.execute(Statement::from_string(
+            DbBackend::Sqlite,
+            "CREATE TABLE fixed_content (id INTEGER PRIMARY KEY, title TEXT NOT NULL, slug TEXT NOT NULL UNIQUE, excerpt TEXT NULL, content TEXT NOT NULL, created_at TEXT NOT NULL, updated_at TEXT NOT NULL, content_hash TEXT NULL);",
+        ))
+        .await
+        .expect("failed to create fixed_content table");
+        let matter = FixedContentMatter {
+            title: "About".to_string(),
+            slug: "about".to_string(),
+            excerpt: None,
+            created_at: None,
+            updated_at: None,
+        };
+        let dates = SourceDates {
+            created_at: Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).single(),
+            updated_at: Utc.with_ymd_and_hms(2025, 6, 1, 0, 0, 0).single(),
+        };
+
+        let id = seed_fixed_content(&db, &matter, "body", "hash", &dates)
+            .await
+            .expect("seed should insert");
+        let stored = fixed_content::Entity::find_by_id(id)
+            .one(&db)
+            .await
+            .expect("failed to read")
+            .expect("fixed content should exist");
+        assert_eq!(Some(stored.created_at), dates.created_at);
+        assert_eq!(Some(stored.updated_at), dates.updated_at);
+    }
+}
@@ -0,0 +1,267 @@
+use crate::seed::markdown::MarkdownError;
+use serde::de::DeserializeOwned;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrontMatterFormat {
+    /// `---` fenced YAML.
+    Yaml,
+    /// `+++` fenced TOML, as written by Hugo.
+    Toml,
+    /// A JSON object at the very start of the file.
+    Json,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct FrontMatterBlock<'a> {
+    pub format: FrontMatterFormat,
+    pub matter: &'a str,
+    pub body: &'a str,
+    /// 1-based line of the file on which `matter` starts.
+    pub first_line: usize,
+}
+
+/// Splits the front matter anchored at the start of `text` from the body.
+/// Only whole-line fences count, so `---` rules in the body or values are left alone.
+pub fn split_front_matter(text: &str) -> Result<FrontMatterBlock<'_>, MarkdownError> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    if text.starts_with('{') {
+        return split_json(text);
+    }
+    let first = text.lines().next().unwrap_or_default().trim_end();
+    let (format, fence) = match first {
+        "---" => (FrontMatterFormat::Yaml, "---"),
+        "+++" => (FrontMatterFormat::Toml, "+++"),
+        _ => return Err(MarkdownError::MissingFrontMatter),
+    };
+
+    let matter_start = text.find('\n').map_or(text.len(), |i| i + 1);
+    let mut offset = matter_start;
+    for line in text[matter_start..].split_inclusive('\n') {
+        if line.trim_end() == fence {
+            return Ok(FrontMatterBlock {
+                format,
+                matter: &text[matter_start..offset],
+                body: &text[offset + line.len()..],
+                first_line: 2,
+            });
+        }
+        offset += line.len();
+    }
+    Err(MarkdownError::UnclosedFrontMatter(fence))
+}
+
+fn split_json(text: &str) -> Result<FrontMatterBlock<'_>, MarkdownError> {
+    let mut stream = serde_json::Deserializer::from_str(text).into_iter::<serde_json::Value>();
+    match stream.next() {
+        Some(Ok(_)) => {
+            let end = stream.byte_offset();
+            Ok(FrontMatterBlock {
+                format: FrontMatterFormat::Json,
+                matter: &text[..end],
+                body: &text[end..],
+                first_line: 1,
+            })
+        }
+        Some(Err(e)) => Err(json_error(e, 0)),
+        None => Err(MarkdownError::MissingFrontMatter),
+    }
+}
+
+pub fn deserialize_front_matter<T: DeserializeOwned>(
+    block: &FrontMatterBlock<'_>,
+) -> Result<T, MarkdownError> {
+    let line_offset = block.first_line - 1;
+    match block.format {
+        FrontMatterFormat::Yaml => {
+            serde_yaml::from_str(block.matter).map_err(|e| yaml_error(e, line_offset))
+        }
+        FrontMatterFormat::Json => {
+            serde_json::from_str(block.matter).map_err(|e| json_error(e, line_offset))
+        }
+        FrontMatterFormat::Toml => {
+            let table: toml::Table = toml::from_str(block.matter)
+                .map_err(|e| toml_error(&e, block.matter, line_offset))?;
+            // TOML has a native datetime type; the matter structs keep dates as strings.
+            serde_json::from_value(toml_to_json(toml::Value::Table(table)))
+                .map_err(|e| invalid(None, None, e.to_string()))
+        }
+    }
+}
+
+fn toml_to_json(value: toml::Value) -> serde_json::Value {
+    match value {
+        toml::Value::String(s) => serde_json::Value::String(s),
+        toml::Value::Integer(i) => i.into(),
+        toml::Value::Float(f) => f.into(),
+        toml::Value::Boolean(b) => b.into(),
+        toml::Value::Datetime(dt) => serde_json::Value::String(dt.to_string()),
+        toml::Value::Array(items) => items.into_iter().map(toml_to_json).collect(),
+        toml::Value::Table(table) => table
+            .into_iter()
+            .map(|(key, value)| (key, toml_to_json(value)))
+            .collect(),
+    }
+}
+
+fn yaml_error(e: serde_yaml::Error, line_offset: usize) -> MarkdownError {
+    let location = e.location();
+    let message = strip_location(
+        e.to_string(),
+        location.as_ref().map(|l| (l.line(), l.column())),
+    );
+    invalid(
+        location.as_ref().map(|l| l.line() + line_offset),
+        location.as_ref().map(|l| l.column()),
+        message,
+    )
+}
+
+fn json_error(e: serde_json::Error, line_offset: usize) -> MarkdownError {
+    let message = strip_location(e.to_string(), Some((e.line(), e.column())));
+    let known = e.line() > 0;
+    invalid(
+        known.then_some(e.line() + line_offset),
+        known.then_some(e.column()),
+        message,
+    )
+}
+
+fn toml_error(e: &toml::de::Error, matter: &str, line_offset: usize) -> MarkdownError {
+    let position = e.span().map(|span| {
+        let before = &matter[..span.start.min(matter.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+        (line + line_offset, column)
+    });
+    invalid(
+        position.map(|(line, _)| line),
+        position.map(|(_, column)| column),
+        e.message().to_string(),
+    )
+}
+
+fn strip_location(message: String, location: Option<(usize, usize)>) -> String {
+    let Some((line, column)) = location else {
+        return message;
+    };
+    match message.strip_suffix(&format!(" at line {line} column {column}")) {
+        Some(stripped) => stripped.to_string(),
+        None => message,
+    }
+}
+
+fn invalid(line: Option<usize>, column: Option<usize>, message: String) -> MarkdownError {
+    let field = error_field(&message);
+    let message = match field
+        .as_deref()
+        .and_then(|field| message.strip_prefix(&format!("{field}: ")))
+    {
+        Some(rest) => rest.to_string(),
+        None => message,
+    };
+    MarkdownError::Invalid {
+        line,
+        column,
+        field,
+        message,
+    }
+}
+
+/// serde reports `missing field `x``; serde_yaml also prefixes messages with the field path.
+fn error_field(message: &str) -> Option<String> {
+    if let Some(rest) = message.strip_prefix("missing field `") {
+        return rest.split('`').next().map(str::to_string);
+    }
+    let (path, _) = message.split_once(": ")?;
+    let is_path = !path.is_empty()
+        && path
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '[' | ']'));
+    is_path.then(|| path.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FrontMatterFormat, deserialize_front_matter, error_field, split_front_matter};
+    use crate::seed::markdown::MarkdownError;
+    use crate::utils::front_matter::FrontMatter;
+
+    #[test]
+    fn split_front_matter_ignores_rules_in_body_and_values() {
+        let text = "---\ntitle: \"a---b\"\n---\nintro\n\n---\n\nmore\n";
+        let block = split_front_matter(text).expect("yaml block");
+        assert_eq!(block.format, FrontMatterFormat::Yaml);
+        assert_eq!(block.matter, "title: \"a---b\"\n");
+        assert_eq!(block.body, "intro\n\n---\n\nmore\n");
+    }
+
+    #[test]
+    fn split_front_matter_detects_toml_and_json() {
+        let toml = split_front_matter("+++\ntitle = \"t\"\n+++\nbody").expect("toml block");
+        assert_eq!(toml.format, FrontMatterFormat::Toml);
+        assert_eq!(toml.matter, "title = \"t\"\n");
+        assert_eq!(toml.body, "body");
+
+        let json = split_front_matter("{\"title\": \"t\"}\nbody").expect("json block");
+        assert_eq!(json.format, FrontMatterFormat::Json);
+        assert_eq!(json.matter, "{\"title\": \"t\"}");
+        assert_eq!(json.body, "\nbody");
+    }
+
+    #[test]
+    fn split_front_matter_requires_fence_at_start_and_end() {
+        assert!(matches!(
+            split_front_matter("intro\n---\ntitle: t\n---\n"),
+            Err(MarkdownError::MissingFrontMatter)
+        ));
+        assert!(matches!(
+            split_front_matter("+++\ntitle = \"t\"\n"),
+            Err(MarkdownError::UnclosedFrontMatter("+++"))
+        ));
+    }
+
+    #[test]
+    fn deserialize_front_matter_reads_hugo_toml_dates_as_strings() {
+        let text = "+++\ntitle = \"Hugo\"\nslug = \"hugo\"\ndate = 2021-08-01T10:00:00+09:00\ntags = [\"rust\"]\ncategories = []\n+++\nbody\n";
+        let block = split_front_matter(text).expect("toml block");
+        let fm: FrontMatter = deserialize_front_matter(&block).expect("toml front matter");
+        assert_eq!(fm.title, "Hugo");
+        assert_eq!(fm.created_at, Some("2021-08-01T10:00:00+09:00".to_string()));
+        assert_eq!(fm.tags, vec!["rust".to_string()]);
+    }
+
+    #[test]
+    fn deserialize_front_matter_reports_file_line_for_toml_errors() {
+        let text = "+++\ntitle = \"t\"\nslug = \n+++\n";
+        let block = split_front_matter(text).expect("toml block");
+        match deserialize_front_matter::<FrontMatter>(&block) {
+            Err(MarkdownError::Invalid { line, .. }) => assert_eq!(line, Some(3)),
+            other => panic!("expected a TOML error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn deserialize_front_matter_names_missing_json_field() {
+        let block = split_front_matter("{\"title\": \"t\", \"slug\": \"s\", \"tags\": []}\nbody")
+            .expect("json block");
+        match deserialize_front_matter::<FrontMatter>(&block) {
+            Err(MarkdownError::Invalid { field, .. }) => {
+                assert_eq!(field, Some("categories".to_string()))
+            }
+            other => panic!("expected a JSON error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn error_field_reads_missing_and_prefixed_fields() {
+        assert_eq!(
+            error_field("missing field `tags`"),
+            Some("tags".to_string())
+        );
+        assert_eq!(
+            error_field("title: invalid type: sequence, expected a string"),
+            Some("title".to_string())
+        );
+        assert_eq!(error_field("did not find expected node content"), None);
+    }
+}
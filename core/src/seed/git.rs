@@ -0,0 +1,163 @@
+use anyhow::{Context, bail};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Timestamps taken from outside the front matter, used when it has no explicit value.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SourceDates {
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// First and last commit (author date) of every file under a directory.
+#[derive(Debug, Default)]
+pub struct GitHistory {
+    dates: HashMap<PathBuf, SourceDates>,
+}
+
+impl GitHistory {
+    pub fn load(dir: &str) -> anyhow::Result<Self> {
+        let toplevel = git(dir, &["rev-parse", "--show-toplevel"])?;
+        let toplevel = Path::new(toplevel.trim())
+            .canonicalize()
+            .with_context(|| format!("failed to resolve git toplevel {}", toplevel.trim()))?;
+        let log = git(
+            dir,
+            &[
+                "log",
+                "--format=%x00%at",
+                "--name-only",
+                "--no-renames",
+                "--",
+                ".",
+            ],
+        )?;
+        Ok(GitHistory {
+            dates: parse_log(&toplevel, &log),
+        })
+    }
+
+    /// Files that are not committed yet get no dates.
+    pub fn dates_for(&self, path: &Path) -> SourceDates {
+        path.canonicalize()
+            .ok()
+            .and_then(|path| self.dates.get(&path).copied())
+            .unwrap_or_default()
+    }
+}
+
+fn git(dir: &str, args: &[&str]) -> anyhow::Result<String> {
+    // Without this, non-ASCII file names are printed quoted and octal-escaped.
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["-c", "core.quotePath=false"])
+        .args(args)
+        .output()
+        .context("failed to run git")?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Parses `git log --format=%x00%at --name-only` output, which lists the newest commit first.
+fn parse_log(toplevel: &Path, log: &str) -> HashMap<PathBuf, SourceDates> {
+    let mut dates = HashMap::<PathBuf, SourceDates>::new();
+    let mut current = None;
+    for line in log.lines() {
+        if let Some(timestamp) = line.strip_prefix('\0') {
+            current = timestamp
+                .trim()
+                .parse::<i64>()
+                .ok()
+                .and_then(|secs| Utc.timestamp_opt(secs, 0).single());
+            continue;
+        }
+        let (Some(at), false) = (current, line.is_empty()) else {
+            continue;
+        };
+        let entry = dates.entry(toplevel.join(line)).or_default();
+        entry.updated_at.get_or_insert(at);
+        entry.created_at = Some(at);
+    }
+    dates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GitHistory, SourceDates, git, parse_log};
+    use chrono::{TimeZone, Utc};
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+        time::{SystemTime, UNIX_EPOCH},
+    };
+
+    #[test]
+    fn parse_log_uses_oldest_and_newest_commit_per_file() {
+        let log = "\u{0}1700000300\n\ncontent/articles/a.md\n\u{0}1700000200\n\ncontent/articles/b.md\n\u{0}1700000100\n\ncontent/articles/a.md\ncontent/articles/b.md\n";
+        let dates = parse_log(Path::new("/repo"), log);
+
+        assert_eq!(
+            dates[&PathBuf::from("/repo/content/articles/a.md")],
+            SourceDates {
+                created_at: Utc.timestamp_opt(1700000100, 0).single(),
+                updated_at: Utc.timestamp_opt(1700000300, 0).single(),
+            }
+        );
+        assert_eq!(
+            dates[&PathBuf::from("/repo/content/articles/b.md")],
+            SourceDates {
+                created_at: Utc.timestamp_opt(1700000100, 0).single(),
+                updated_at: Utc.timestamp_opt(1700000200, 0).single(),
+            }
+        );
+    }
+
+    #[test]
+    fn load_finds_files_with_non_ascii_names() {
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time before UNIX_EPOCH")
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("seed_git_test_{}_{}", std::process::id(), ts));
+        fs::create_dir_all(&dir).expect("failed to create temp dir");
+        let dir_str = dir.to_str().expect("utf-8 temp dir");
+        let path = dir.join("日本語の記事.md");
+        fs::write(&path, "# 記事").expect("failed to write article");
+        git(dir_str, &["init", "-q"]).expect("failed to init repo");
+        git(dir_str, &["add", "."]).expect("failed to add");
+        git(
+            dir_str,
+            &[
+                "-c",
+                "user.name=seed",
+                "-c",
+                "user.email=seed@example.com",
+                "commit",
+                "-q",
+                "-m",
+                "add article",
+            ],
+        )
+        .expect("failed to commit");
+
+        let dates = GitHistory::load(dir_str)
+            .expect("failed to load history")
+            .dates_for(&path);
+
+        assert!(dates.created_at.is_some());
+        assert_eq!(dates.created_at, dates.updated_at);
+        fs::remove_dir_all(&dir).expect("failed to remove temp dir");
+    }
+}
@@ -0,0 +1,420 @@
+use crate::entity::image as image_entity;
+use crate::seed::{
+    error::{SeedError, SeedErrorReport},
+    incremental::{SeedStatus, SeedSummary},
+};
+use crate::utils::{
+    bundle::{BUNDLE_INDEX, bundle_assets, bundle_url},
+    image::{ImageIndex, ImageInfo, ImageVariant, VARIANT_URL_PREFIX},
+};
+use ::image::{GenericImageView, ImageFormat, imageops::FilterType};
+use anyhow::Context;
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, ConnectionTrait, EntityTrait, IntoActiveModel};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Component, Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+/// Widths of the resized copies. Only those narrower than the original are generated.
+pub const VARIANT_WIDTHS: [u32; 3] = [480, 960, 1440];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageConfig {
+    /// Served as `/image`.
+    pub image_path: String,
+    /// Served as `/icon`. Only checked for references, no variants are generated.
+    pub icon_path: String,
+    /// Generated variants, served as `/variants`.
+    pub variant_path: String,
+}
+
+impl ImageConfig {
+    pub fn new(
+        image_path: Option<String>,
+        icon_path: Option<String>,
+        variant_path: Option<String>,
+    ) -> Self {
+        ImageConfig {
+            image_path: image_path.unwrap_or("content/image".to_string()),
+            icon_path: icon_path.unwrap_or("content/icon".to_string()),
+            variant_path: variant_path.unwrap_or("content/variants".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageSource {
+    /// URL pages reference the image with.
+    pub url: String,
+    pub path: PathBuf,
+}
+
+pub fn is_raster_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            matches!(
+                ext.to_ascii_lowercase().as_str(),
+                "png" | "jpg" | "jpeg" | "gif" | "webp"
+            )
+        })
+}
+
+/// Images under `image_dir` (`/image/...`) and inside page bundles (`/posts/<slug>/...`).
+pub fn image_sources(image_dir: &Path, article_dir: &Path) -> Vec<ImageSource> {
+    let mut sources: Vec<ImageSource> = WalkDir::new(image_dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file() && is_raster_image(entry.path()))
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(image_dir).ok()?;
+            Some(ImageSource {
+                url: format!("/image/{}", url_path(relative)),
+                path: entry.path().to_path_buf(),
+            })
+        })
+        .collect();
+
+    let bundles = fs::read_dir(article_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().join(BUNDLE_INDEX).is_file());
+    for bundle in bundles {
+        let Some(slug) = bundle.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        sources.extend(
+            bundle_assets(article_dir, &slug)
+                .into_iter()
+                .filter(|relative| is_raster_image(relative))
+                .map(|relative| ImageSource {
+                    url: format!("{}{}", bundle_url(&slug), url_path(&relative)),
+                    path: bundle.path().join(relative),
+                }),
+        );
+    }
+    sources.sort_by(|a, b| a.url.cmp(&b.url));
+    sources
+}
+
+fn url_path(relative: &Path) -> String {
+    relative
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// `/image/a.png` at 480px becomes `image/a.png.480w.webp` under the variant directory.
+pub fn variant_relative_path(url: &str, width: u32) -> String {
+    format!("{}.{width}w.webp", url.trim_start_matches('/'))
+}
+
+pub fn variant_widths(width: u32) -> Vec<u32> {
+    VARIANT_WIDTHS
+        .into_iter()
+        .filter(|target| *target < width)
+        .collect()
+}
+
+/// Reads the dimensions of `bytes` and writes a WebP copy for each of `variant_widths`.
+pub fn process_image(bytes: &[u8], url: &str, variant_dir: &Path) -> anyhow::Result<ImageInfo> {
+    let decoded = ::image::load_from_memory(bytes)?;
+    let (width, height) = decoded.dimensions();
+    let mut variants = Vec::new();
+    for target in variant_widths(width) {
+        let target_height = ((u64::from(height) * u64::from(target)) / u64::from(width)).max(1);
+        let resized = decoded.resize_exact(target, target_height as u32, FilterType::Lanczos3);
+        let relative = variant_relative_path(url, target);
+        let path = variant_dir.join(&relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        resized
+            .to_rgba8()
+            .save_with_format(&path, ImageFormat::WebP)?;
+        variants.push(ImageVariant {
+            url: format!("{VARIANT_URL_PREFIX}/{relative}"),
+            width: target,
+        });
+    }
+    Ok(ImageInfo {
+        width,
+        height,
+        variants,
+    })
+}
+
+/// Variant files a seed created, so they can be removed when its rows are rolled back.
+#[derive(Debug, Default)]
+pub struct CreatedVariants(Vec<PathBuf>);
+
+impl CreatedVariants {
+    pub fn remove(self) {
+        for path in self.0 {
+            if let Err(e) = fs::remove_file(&path) {
+                eprintln!("⚠️ {} を削除できませんでした: {e}", path.display());
+            }
+        }
+    }
+}
+
+fn variants_exist(variants: &[ImageVariant], variant_dir: &Path) -> bool {
+    variants.iter().all(|variant| {
+        variant
+            .url
+            .strip_prefix(VARIANT_URL_PREFIX)
+            .is_some_and(|relative| variant_dir.join(relative.trim_start_matches('/')).is_file())
+    })
+}
+
+/// Generates variants for new or changed images and records their dimensions.
+/// Rows of images that no longer exist are removed.
+pub async fn run_image_seed<C: ConnectionTrait>(
+    db: &C,
+    config: &ImageConfig,
+    article_dir: &str,
+    errors: &mut SeedErrorReport,
+    created: &mut CreatedVariants,
+) -> anyhow::Result<()> {
+    let variant_dir = Path::new(&config.variant_path);
+    let sources = image_sources(Path::new(&config.image_path), Path::new(article_dir));
+    let stored: HashMap<String, image_entity::Model> = image_entity::Entity::find()
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|model| (model.path.clone(), model))
+        .collect();
+    let mut summary = SeedSummary::default();
+
+    for source in &sources {
+        let bytes = match fs::read(&source.path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                errors.push(SeedError::new(source.path.clone(), None, e.to_string()));
+                continue;
+            }
+        };
+        let hash = format!("{:x}", Sha256::digest(&bytes));
+        let existing = stored.get(&source.url);
+        let status =
+            SeedStatus::classify(existing.map(|model| Some(model.source_hash.clone())), &hash);
+        if status == SeedStatus::Unchanged
+            && existing.is_some_and(|model| {
+                serde_json::from_str::<Vec<ImageVariant>>(&model.variants)
+                    .is_ok_and(|variants| variants_exist(&variants, variant_dir))
+            })
+        {
+            summary.record(status);
+            continue;
+        }
+
+        let missing: Vec<PathBuf> = VARIANT_WIDTHS
+            .into_iter()
+            .map(|width| variant_dir.join(variant_relative_path(&source.url, width)))
+            .filter(|path| !path.exists())
+            .collect();
+        let processed = process_image(&bytes, &source.url, variant_dir);
+        created
+            .0
+            .extend(missing.into_iter().filter(|path| path.exists()));
+        let info = match processed {
+            Ok(info) => info,
+            Err(e) => {
+                errors.push(SeedError::new(source.path.clone(), None, format!("{e:#}")));
+                continue;
+            }
+        };
+        let mut active_model = match existing {
+            Some(model) => model.clone().into_active_model(),
+            None => Default::default(),
+        };
+        active_model.path = Set(source.url.clone());
+        active_model.width = Set(info.width as i32);
+        active_model.height = Set(info.height as i32);
+        active_model.variants = Set(serde_json::to_string(&info.variants)?);
+        active_model.source_hash = Set(hash);
+        active_model.save(db).await?;
+        // Regenerating missing variant files of an unchanged image counts as an update.
+        summary.record(match status {
+            SeedStatus::Unchanged => SeedStatus::Updated,
+            status => status,
+        });
+    }
+
+    let current: HashSet<&str> = sources.iter().map(|source| source.url.as_str()).collect();
+    for model in stored.values() {
+        if !current.contains(model.path.as_str()) {
+            image_entity::Entity::delete_by_id(model.id)
+                .exec(db)
+                .await?;
+            summary.deleted += 1;
+        }
+    }
+    println!("📊 画像: {summary}");
+    Ok(())
+}
+
+/// Writes the variants `images` lists but `variant_dir` lacks, which is the case on a
+/// fresh checkout that carries the database but not the generated files.
+/// Returns how many images were processed.
+pub fn restore_variants(
+    images: &ImageIndex,
+    sources: &[ImageSource],
+    variant_dir: &Path,
+) -> anyhow::Result<usize> {
+    let mut restored = 0;
+    for source in sources {
+        let Some(info) = images.get(&source.url) else {
+            continue;
+        };
+        if variants_exist(&info.variants, variant_dir) {
+            continue;
+        }
+        let bytes = fs::read(&source.path)
+            .with_context(|| format!("failed to read {}", source.path.display()))?;
+        process_image(&bytes, &source.url, variant_dir)
+            .with_context(|| format!("failed to resize {}", source.path.display()))?;
+        restored += 1;
+    }
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ImageConfig, ImageSource, image_sources, is_raster_image, process_image, restore_variants,
+        variant_relative_path, variant_widths,
+    };
+    use crate::utils::image::ImageIndex;
+    use ::image::{ImageFormat, RgbImage};
+    use std::fs;
+    use std::io::Cursor;
+    use std::path::{Path, PathBuf};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn create_temp_dir() -> PathBuf {
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time before UNIX_EPOCH")
+            .as_nanos();
+        let dir =
+            std::env::temp_dir().join(format!("seed_image_test_{}_{}", std::process::id(), ts));
+        fs::create_dir_all(&dir).expect("failed to create temp dir");
+        dir
+    }
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        RgbImage::new(width, height)
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .expect("failed to encode png");
+        bytes
+    }
+
+    #[test]
+    fn image_config_uses_defaults_when_none_is_passed() {
+        let config = ImageConfig::new(None, None, None);
+        assert_eq!(config.image_path, "content/image");
+        assert_eq!(config.icon_path, "content/icon");
+        assert_eq!(config.variant_path, "content/variants");
+    }
+
+    #[test]
+    fn is_raster_image_matches_common_extensions() {
+        assert!(is_raster_image(Path::new("a.PNG")));
+        assert!(is_raster_image(Path::new("a.jpeg")));
+        assert!(!is_raster_image(Path::new("a.svg")));
+        assert!(!is_raster_image(Path::new("a.md")));
+    }
+
+    #[test]
+    fn variant_widths_skips_widths_not_smaller_than_original() {
+        assert_eq!(variant_widths(1000), vec![480, 960]);
+        assert_eq!(variant_widths(480), Vec::<u32>::new());
+        assert_eq!(
+            variant_relative_path("/posts/a/cover.png", 480),
+            "posts/a/cover.png.480w.webp"
+        );
+    }
+
+    #[test]
+    fn image_sources_collects_global_and_bundled_images() {
+        let dir = create_temp_dir();
+        let image_dir = dir.join("image");
+        let article_dir = dir.join("articles");
+        fs::create_dir_all(image_dir.join("nested")).expect("failed to create image dir");
+        fs::create_dir_all(article_dir.join("hello")).expect("failed to create bundle");
+        fs::write(image_dir.join("nested/a.png"), "").expect("failed to write a.png");
+        fs::write(image_dir.join("notes.txt"), "").expect("failed to write notes.txt");
+        fs::write(article_dir.join("hello/index.md"), "").expect("failed to write index.md");
+        fs::write(article_dir.join("hello/cover.jpg"), "").expect("failed to write cover.jpg");
+
+        let urls: Vec<String> = image_sources(&image_dir, &article_dir)
+            .into_iter()
+            .map(|source| source.url)
+            .collect();
+        assert_eq!(urls, vec!["/image/nested/a.png", "/posts/hello/cover.jpg"]);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn process_image_records_dimensions_and_writes_variants() {
+        let dir = create_temp_dir();
+        let info =
+            process_image(&png(1000, 500), "/image/a.png", &dir).expect("failed to process image");
+        assert_eq!((info.width, info.height), (1000, 500));
+        assert_eq!(
+            info.variants
+                .iter()
+                .map(|variant| variant.url.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "/variants/image/a.png.480w.webp",
+                "/variants/image/a.png.960w.webp"
+            ]
+        );
+        let variant = ::image::open(dir.join("image/a.png.480w.webp")).expect("variant is written");
+        assert_eq!(variant.width(), 480);
+        assert_eq!(variant.height(), 240);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn restore_variants_writes_only_missing_files() {
+        let dir = create_temp_dir();
+        let source = dir.join("a.png");
+        fs::write(&source, png(1000, 500)).expect("failed to write a.png");
+        let variant_dir = dir.join("variants");
+        let info = process_image(&png(1000, 500), "/image/a.png", &variant_dir)
+            .expect("failed to process image");
+        let images = ImageIndex::from([("/image/a.png".to_string(), info)]);
+        let sources = vec![ImageSource {
+            url: "/image/a.png".to_string(),
+            path: source,
+        }];
+        let missing = variant_dir.join("image/a.png.960w.webp");
+        fs::remove_file(&missing).expect("failed to remove variant");
+
+        assert_eq!(
+            restore_variants(&images, &sources, &variant_dir).ok(),
+            Some(1)
+        );
+        assert!(missing.is_file());
+        assert_eq!(
+            restore_variants(&images, &sources, &variant_dir).ok(),
+            Some(0)
+        );
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
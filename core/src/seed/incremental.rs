@@ -0,0 +1,115 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// Hex encoded SHA-256 of the parsed front matter and the body.
+pub fn content_hash<T: Serialize>(matter: &T, body: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(serde_json::to_vec(matter).unwrap_or_default());
+    hasher.update([0]);
+    hasher.update(body.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeedStatus {
+    Added,
+    Updated,
+    Unchanged,
+}
+
+impl SeedStatus {
+    /// `stored` is `None` when no row has the slug yet, and `Some(None)` for rows
+    /// seeded before hashes were recorded.
+    pub fn classify(stored: Option<Option<String>>, hash: &str) -> Self {
+        match stored {
+            None => SeedStatus::Added,
+            Some(Some(stored)) if stored == hash => SeedStatus::Unchanged,
+            Some(_) => SeedStatus::Updated,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SeedSummary {
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub deleted: usize,
+}
+
+impl SeedSummary {
+    pub fn record(&mut self, status: SeedStatus) {
+        match status {
+            SeedStatus::Added => self.added += 1,
+            SeedStatus::Updated => self.updated += 1,
+            SeedStatus::Unchanged => self.unchanged += 1,
+        }
+    }
+
+    /// Whether the run wrote or deleted anything.
+    pub fn changed(&self) -> bool {
+        self.added + self.updated + self.deleted > 0
+    }
+}
+
+impl fmt::Display for SeedSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "added {}, updated {}, unchanged {}, deleted {}",
+            self.added, self.updated, self.unchanged, self.deleted
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SeedStatus, SeedSummary, content_hash};
+    use crate::utils::fixed_content_matter::FixedContentMatter;
+
+    fn matter(title: &str) -> FixedContentMatter {
+        FixedContentMatter {
+            title: title.to_string(),
+            slug: "about".to_string(),
+            excerpt: None,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    #[test]
+    fn content_hash_changes_with_front_matter_and_body() {
+        let base = content_hash(&matter("About"), "body");
+        assert_eq!(base, content_hash(&matter("About"), "body"));
+        assert_ne!(base, content_hash(&matter("About me"), "body"));
+        assert_ne!(base, content_hash(&matter("About"), "body\n"));
+    }
+
+    #[test]
+    fn classify_compares_stored_hash() {
+        assert_eq!(SeedStatus::classify(None, "a"), SeedStatus::Added);
+        assert_eq!(SeedStatus::classify(Some(None), "a"), SeedStatus::Updated);
+        assert_eq!(
+            SeedStatus::classify(Some(Some("b".to_string())), "a"),
+            SeedStatus::Updated
+        );
+        assert_eq!(
+            SeedStatus::classify(Some(Some("a".to_string())), "a"),
+            SeedStatus::Unchanged
+        );
+    }
+
+    #[test]
+    fn summary_counts_each_status() {
+        let mut summary = SeedSummary::default();
+        summary.record(SeedStatus::Added);
+        summary.record(SeedStatus::Unchanged);
+        summary.record(SeedStatus::Unchanged);
+        summary.deleted += 1;
+        assert_eq!(
+            summary.to_string(),
+            "added 1, updated 0, unchanged 2, deleted 1"
+        );
+    }
+}
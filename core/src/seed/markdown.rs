@@ -1,8 +1,42 @@
+use crate::seed::front_matter::{deserialize_front_matter, split_front_matter};
 use crate::utils::{self, fixed_content_matter::FixedContentMatter};
-use std::fs;
+use serde::de::DeserializeOwned;
+use std::{fmt, fs};
 use utils::front_matter::FrontMatter;
 use walkdir::WalkDir;
 
+#[derive(Debug)]
+pub enum MarkdownError {
+    Io(std::io::Error),
+    MissingFrontMatter,
+    /// The opening fence was found but the matching closing one was not.
+    UnclosedFrontMatter(&'static str),
+    Invalid {
+        line: Option<usize>,
+        column: Option<usize>,
+        field: Option<String>,
+        message: String,
+    },
+}
+
+impl fmt::Display for MarkdownError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarkdownError::Io(e) => write!(f, "{e}"),
+            MarkdownError::MissingFrontMatter => write!(
+                f,
+                "FrontMatter not found (expected `---`, `+++` or `{{` on the first line)"
+            ),
+            MarkdownError::UnclosedFrontMatter(fence) => {
+                write!(f, "FrontMatter is not closed (missing `{fence}`)")
+            }
+            MarkdownError::Invalid { message, .. } => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for MarkdownError {}
+
 pub fn markdown_files(dir: &str) -> impl Iterator<Item = std::path::PathBuf> {
     WalkDir::new(dir)
         .into_iter()
@@ -16,34 +50,30 @@ pub fn markdown_files(dir: &str) -> impl Iterator<Item = std::path::PathBuf> {
 
 pub fn parse_markdown_to_front_matter(
     path: &std::path::Path,
-) -> Result<(FrontMatter, String), Box<dyn std::error::Error>> {
-    let text = fs::read_to_string(path)?;
-    let parts: Vec<&str> = text.splitn(3, "---").collect();
-    if parts.len() != 3 {
-        panic!("FrontMatter not found in {:?}", path);
-    }
-    let front_matter = serde_yaml::from_str(parts[1])?;
-    let body = parts[2].trim_start().to_string();
-    Ok((front_matter, body))
+) -> Result<(FrontMatter, String), MarkdownError> {
+    parse_markdown(path)
 }
 
 pub fn parse_markdown_to_fixed_content_matter(
     path: &std::path::Path,
-) -> Result<(FixedContentMatter, String), Box<dyn std::error::Error>> {
-    let text = fs::read_to_string(path)?;
-    let parts: Vec<&str> = text.splitn(3, "---").collect();
-    if parts.len() != 3 {
-        panic!("FrontMatter not found in {:?}", path);
-    }
-    let fixed_content_matter = serde_yaml::from_str(parts[1])?;
-    let body = parts[2].trim_start().to_string();
-    Ok((fixed_content_matter, body))
+) -> Result<(FixedContentMatter, String), MarkdownError> {
+    parse_markdown(path)
+}
+
+fn parse_markdown<T: DeserializeOwned>(
+    path: &std::path::Path,
+) -> Result<(T, String), MarkdownError> {
+    let text = fs::read_to_string(path).map_err(MarkdownError::Io)?;
+    let block = split_front_matter(&text)?;
+    let matter = deserialize_front_matter(&block)?;
+    Ok((matter, block.body.trim_start().to_string()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::{
-        markdown_files, parse_markdown_to_fixed_content_matter, parse_markdown_to_front_matter,
+        MarkdownError, markdown_files, parse_markdown_to_fixed_content_matter,
+        parse_markdown_to_front_matter,
     };
     use std::fs;
     use std::path::PathBuf;
@@ -197,12 +227,13 @@ Hello world
     }
 
     #[test]
-    #[should_panic(expected = "FrontMatter not found")]
-    fn parse_markdown_to_front_matter_panics_when_delimiter_missing() {
+    fn parse_markdown_to_front_matter_errors_when_delimiter_missing() {
         let dir = create_temp_dir();
         let path = dir.join("broken.md");
         fs::write(&path, "title: no delimiter").expect("failed to write markdown file");
-        let _ = parse_markdown_to_front_matter(&path);
+        let result = parse_markdown_to_front_matter(&path);
+        assert!(matches!(result, Err(MarkdownError::MissingFrontMatter)));
+        let _ = fs::remove_dir_all(dir);
     }
 
     #[test]
@@ -222,7 +253,13 @@ text
         )
         .expect("failed to write markdown file");
         let result = parse_markdown_to_front_matter(&path);
-        assert!(result.is_err());
+        match result {
+            Err(MarkdownError::Invalid { line, field, .. }) => {
+                assert_eq!(line, Some(2));
+                assert_eq!(field, Some("title".to_string()));
+            }
+            other => panic!("expected a YAML error, got {other:?}"),
+        }
         let _ = fs::remove_dir_all(dir);
     }
 }
@@ -0,0 +1,395 @@
+use crate::entity::{article, article_category, article_tag, category, fixed_content, tag};
+use crate::seed::taxonomy::{DeclaredTaxonomy, TaxonomyKind};
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QuerySelect, RelationTrait,
+    sea_query::JoinType,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PruneMode {
+    Off,
+    /// Reports only after a run that wrote or deleted something.
+    #[default]
+    Changed,
+    Report,
+    Delete,
+}
+
+impl PruneMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "off" | "false" | "0" => Some(Self::Off),
+            "changed" => Some(Self::Changed),
+            "report" => Some(Self::Report),
+            "delete" => Some(Self::Delete),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrphanTaxonomy {
+    pub id: i32,
+    pub slug: String,
+    /// Still linked to articles whose `created_at` is in the future, so it is kept on delete.
+    pub scheduled: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrphanFixedContent {
+    pub id: i32,
+    pub slug: String,
+}
+
+/// Links a dry run would change, applied on top of the stored ones.
+#[derive(Debug, Default)]
+pub struct PendingLinks {
+    /// Slugs of articles the run deletes along with their links.
+    pub deleted_articles: HashSet<String>,
+    /// `(kind, article slug, taxonomy slug)` of links the run removes.
+    pub removed: HashSet<(TaxonomyKind, String, String)>,
+    /// `(kind, taxonomy slug)` the run links to an article.
+    pub added: HashSet<(TaxonomyKind, String)>,
+}
+
+impl PendingLinks {
+    fn removes(&self, kind: TaxonomyKind, article_slug: &str, slug: &str) -> bool {
+        self.deleted_articles.contains(article_slug)
+            || self
+                .removed
+                .contains(&(kind, article_slug.to_string(), slug.to_string()))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct OrphanReport {
+    pub tags: Vec<OrphanTaxonomy>,
+    pub categories: Vec<OrphanTaxonomy>,
+    pub fixed_contents: Vec<OrphanFixedContent>,
+}
+
+impl OrphanReport {
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.categories.is_empty() && self.fixed_contents.is_empty()
+    }
+}
+
+impl fmt::Display for OrphanReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (label, orphans) in [("tag", &self.tags), ("category", &self.categories)] {
+            for orphan in orphans {
+                if orphan.scheduled {
+                    writeln!(f, "  - {label}: {} (scheduled articles only)", orphan.slug)?;
+                } else {
+                    writeln!(f, "  - {label}: {}", orphan.slug)?;
+                }
+            }
+        }
+        for orphan in &self.fixed_contents {
+            writeln!(
+                f,
+                "  - fixed content: {} (source file missing)",
+                orphan.slug
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Collects tags and categories without published articles, and fixed contents whose slug
+/// was not produced by any source file. `fixed_content_slugs` is `None` when the fixed
+/// content directory could not be read completely, in which case those are not checked.
+/// Tags and categories declared in the config are kept even when unused. The links are
+/// the stored ones with `pending` applied.
+pub async fn find_orphans<C: ConnectionTrait>(
+    db: &C,
+    declared: &DeclaredTaxonomy,
+    fixed_content_slugs: Option<&HashSet<String>>,
+    pending: &PendingLinks,
+) -> Result<OrphanReport, DbErr> {
+    let now = Utc::now();
+
+    let tag_links: Vec<(i32, String, DateTime<Utc>)> = article_tag::Entity::find()
+        .select_only()
+        .column(article_tag::Column::TagId)
+        .column(article::Column::Slug)
+        .column(article::Column::CreatedAt)
+        .join(JoinType::InnerJoin, article_tag::Relation::Article.def())
+        .into_tuple()
+        .all(db)
+        .await?;
+    let tags: Vec<(i32, String)> = tag::Entity::find()
+        .select_only()
+        .column(tag::Column::Id)
+        .column(tag::Column::Slug)
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    let category_links: Vec<(i32, String, DateTime<Utc>)> = article_category::Entity::find()
+        .select_only()
+        .column(article_category::Column::CategoryId)
+        .column(article::Column::Slug)
+        .column(article::Column::CreatedAt)
+        .join(
+            JoinType::InnerJoin,
+            article_category::Relation::Article.def(),
+        )
+        .into_tuple()
+        .all(db)
+        .await?;
+    let categories: Vec<(i32, String)> = category::Entity::find()
+        .select_only()
+        .column(category::Column::Id)
+        .column(category::Column::Slug)
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    let fixed_contents = match fixed_content_slugs {
+        Some(slugs) => fixed_content::Entity::find()
+            .all(db)
+            .await?
+            .into_iter()
+            .filter(|model| !slugs.contains(&model.slug))
+            .map(|model| OrphanFixedContent {
+                id: model.id,
+                slug: model.slug,
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+
+    let tags = Taxonomies {
+        kind: TaxonomyKind::Tag,
+        items: tags,
+        links: &tag_links,
+        declared: &declared.tags,
+    };
+    let categories = Taxonomies {
+        kind: TaxonomyKind::Category,
+        items: categories,
+        links: &category_links,
+        declared: &declared.categories,
+    };
+    Ok(OrphanReport {
+        tags: orphan_taxonomies(tags, pending, now),
+        categories: orphan_taxonomies(categories, pending, now),
+        fixed_contents,
+    })
+}
+
+/// Stored tags or categories, with the `(id, article slug, created_at)` of their links.
+struct Taxonomies<'a> {
+    kind: TaxonomyKind,
+    items: Vec<(i32, String)>,
+    links: &'a [(i32, String, DateTime<Utc>)],
+    declared: &'a HashSet<String>,
+}
+
+fn orphan_taxonomies(
+    taxonomies: Taxonomies<'_>,
+    pending: &PendingLinks,
+    now: DateTime<Utc>,
+) -> Vec<OrphanTaxonomy> {
+    let Taxonomies {
+        kind,
+        items,
+        links,
+        declared,
+    } = taxonomies;
+    let slugs: HashMap<i32, &str> = items
+        .iter()
+        .map(|(id, slug)| (*id, slug.as_str()))
+        .collect();
+    let mut published = HashMap::<i32, bool>::new();
+    for (id, article_slug, created_at) in links {
+        let slug = slugs.get(id).copied().unwrap_or_default();
+        if pending.removes(kind, article_slug, slug) {
+            continue;
+        }
+        let entry = published.entry(*id).or_insert(false);
+        *entry |= *created_at <= now;
+    }
+    items
+        .into_iter()
+        .filter(|(id, slug)| {
+            published.get(id) != Some(&true)
+                && !declared.contains(slug)
+                && !pending.added.contains(&(kind, slug.clone()))
+        })
+        .map(|(id, slug)| OrphanTaxonomy {
+            id,
+            scheduled: published.contains_key(&id),
+            slug,
+        })
+        .collect()
+}
+
+pub async fn delete_orphans<C: ConnectionTrait>(
+    db: &C,
+    report: &OrphanReport,
+) -> Result<(), DbErr> {
+    let tag_ids: Vec<i32> = deletable_ids(&report.tags);
+    if !tag_ids.is_empty() {
+        tag::Entity::delete_many()
+            .filter(tag::Column::Id.is_in(tag_ids))
+            .exec(db)
+            .await?;
+    }
+    let category_ids: Vec<i32> = deletable_ids(&report.categories);
+    if !category_ids.is_empty() {
+        category::Entity::delete_many()
+            .filter(category::Column::Id.is_in(category_ids))
+            .exec(db)
+            .await?;
+    }
+    let fixed_content_ids: Vec<i32> = report.fixed_contents.iter().map(|o| o.id).collect();
+    if !fixed_content_ids.is_empty() {
+        fixed_content::Entity::delete_many()
+            .filter(fixed_content::Column::Id.is_in(fixed_content_ids))
+            .exec(db)
+            .await?;
+    }
+    Ok(())
+}
+
+fn deletable_ids(orphans: &[OrphanTaxonomy]) -> Vec<i32> {
+    orphans
+        .iter()
+        .filter(|orphan| !orphan.scheduled)
+        .map(|orphan| orphan.id)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        OrphanReport, OrphanTaxonomy, PendingLinks, PruneMode, Taxonomies, deletable_ids,
+        orphan_taxonomies,
+    };
+    use crate::seed::taxonomy::TaxonomyKind;
+    use chrono::{TimeZone, Utc};
+    use std::collections::HashSet;
+
+    #[test]
+    fn prune_mode_parse_accepts_known_values() {
+        assert_eq!(PruneMode::parse("off"), Some(PruneMode::Off));
+        assert_eq!(PruneMode::parse("changed"), Some(PruneMode::Changed));
+        assert_eq!(PruneMode::parse(" Report "), Some(PruneMode::Report));
+        assert_eq!(PruneMode::parse("DELETE"), Some(PruneMode::Delete));
+        assert_eq!(PruneMode::parse("purge"), None);
+    }
+
+    #[test]
+    fn orphan_taxonomies_detects_unlinked_and_scheduled_only_but_keeps_declared() {
+        let now = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        let past = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        let future = Utc.with_ymd_and_hms(2027, 1, 1, 0, 0, 0).unwrap();
+        let items = vec![
+            (1, "published".to_string()),
+            (2, "scheduled".to_string()),
+            (3, "unused".to_string()),
+            (4, "mixed".to_string()),
+            (5, "declared".to_string()),
+        ];
+        let links = vec![
+            (1, "a".to_string(), past),
+            (2, "b".to_string(), future),
+            (4, "c".to_string(), future),
+            (4, "d".to_string(), past),
+        ];
+        let declared = HashSet::from(["declared".to_string()]);
+        let taxonomies = Taxonomies {
+            kind: TaxonomyKind::Tag,
+            items,
+            links: &links,
+            declared: &declared,
+        };
+
+        let orphans = orphan_taxonomies(taxonomies, &PendingLinks::default(), now);
+
+        assert_eq!(
+            orphans,
+            vec![
+                OrphanTaxonomy {
+                    id: 2,
+                    slug: "scheduled".to_string(),
+                    scheduled: true,
+                },
+                OrphanTaxonomy {
+                    id: 3,
+                    slug: "unused".to_string(),
+                    scheduled: false,
+                },
+            ]
+        );
+        assert_eq!(deletable_ids(&orphans), vec![3]);
+    }
+
+    #[test]
+    fn orphan_taxonomies_applies_the_pending_links() {
+        let now = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        let past = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        let items = vec![
+            (1, "unlinked".to_string()),
+            (2, "deleted".to_string()),
+            (3, "kept".to_string()),
+            (4, "relinked".to_string()),
+        ];
+        let links = vec![
+            (1, "a".to_string(), past),
+            (2, "gone".to_string(), past),
+            (3, "a".to_string(), past),
+        ];
+        let declared = HashSet::new();
+        let taxonomies = Taxonomies {
+            kind: TaxonomyKind::Tag,
+            items,
+            links: &links,
+            declared: &declared,
+        };
+        let pending = PendingLinks {
+            deleted_articles: HashSet::from(["gone".to_string()]),
+            removed: HashSet::from([
+                (TaxonomyKind::Tag, "a".to_string(), "unlinked".to_string()),
+                (TaxonomyKind::Category, "a".to_string(), "kept".to_string()),
+            ]),
+            added: HashSet::from([(TaxonomyKind::Tag, "relinked".to_string())]),
+        };
+
+        let slugs: Vec<String> = orphan_taxonomies(taxonomies, &pending, now)
+            .into_iter()
+            .map(|orphan| orphan.slug)
+            .collect();
+        assert_eq!(slugs, vec!["unlinked", "deleted"]);
+    }
+
+    #[test]
+    fn orphan_report_display_lists_each_kind() {
+        let report = OrphanReport {
+            tags: vec![OrphanTaxonomy {
+                id: 1,
+                slug: "old".to_string(),
+                scheduled: false,
+            }],
+            categories: vec![],
+            fixed_contents: vec![super::OrphanFixedContent {
+                id: 2,
+                slug: "contact".to_string(),
+            }],
+        };
+        assert!(!report.is_empty());
+        assert_eq!(
+            report.to_string(),
+            "  - tag: old\n  - fixed content: contact (source file missing)\n"
+        );
+    }
+}
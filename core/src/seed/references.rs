@@ -0,0 +1,216 @@
+use crate::seed::{
+    error::{SeedError, SeedErrorReport},
+    image::ImageConfig,
+};
+use crate::utils::markdown::resolve_relative_url;
+use pulldown_cmark::{Event, Parser, Tag};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageRef {
+    pub src: String,
+    pub alt: String,
+}
+
+/// Images of a Markdown body with their alt text, in document order.
+pub fn markdown_images(body: &str) -> Vec<ImageRef> {
+    let mut images = Vec::new();
+    let mut open: Vec<ImageRef> = Vec::new();
+    for event in Parser::new(body) {
+        match event {
+            Event::Start(Tag::Image(_, dest, _)) => open.push(ImageRef {
+                src: dest.to_string(),
+                alt: String::new(),
+            }),
+            Event::End(Tag::Image(..)) => images.extend(open.pop()),
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(image) = open.first_mut() {
+                    image.alt.push_str(&text);
+                }
+            }
+            _ => {}
+        }
+    }
+    images
+}
+
+/// Resolves site URLs of local files to the sources seed reads, so that typos in
+/// `icatch_path` or image links fail the seed instead of shipping as broken images.
+pub struct ReferenceChecker<'a> {
+    pub images: &'a ImageConfig,
+    pub article_dir: &'a Path,
+}
+
+impl ReferenceChecker<'_> {
+    /// `None` for URLs that are not served from local content, such as external images.
+    pub fn resolve(&self, url: &str) -> Option<PathBuf> {
+        let path = url.split(['?', '#']).next().unwrap_or_default();
+        if let Some(rest) = path.strip_prefix("/image/") {
+            return Some(Path::new(&self.images.image_path).join(rest));
+        }
+        if let Some(rest) = path.strip_prefix("/icon/") {
+            return Some(Path::new(&self.images.icon_path).join(rest));
+        }
+        let (slug, rest) = path.strip_prefix("/posts/")?.split_once('/')?;
+        Some(self.article_dir.join(slug).join(rest))
+    }
+
+    /// Reports `url` when it points at a local file that does not exist. Returns `false` then.
+    pub fn check_path(
+        &self,
+        source: &Path,
+        field: Option<&str>,
+        url: &str,
+        report: &mut SeedErrorReport,
+    ) -> bool {
+        match self.resolve(url) {
+            Some(file) if !file.is_file() => {
+                report.push(SeedError::new(
+                    source.to_path_buf(),
+                    field.map(str::to_string),
+                    format!("image not found: {url} ({})", file.display()),
+                ));
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Checks every image of `body`. Missing files are errors, missing alt text is a warning.
+    /// Relative paths are resolved against `base_url` and skipped without one.
+    pub fn check_markdown(
+        &self,
+        source: &Path,
+        body: &str,
+        base_url: Option<&str>,
+        report: &mut SeedErrorReport,
+    ) -> bool {
+        let mut ok = true;
+        for image in markdown_images(body) {
+            if image.alt.trim().is_empty() {
+                report.push_warning(SeedError::new(
+                    source.to_path_buf(),
+                    None,
+                    format!("image without alt text: {}", image.src),
+                ));
+            }
+            let url = match resolve_relative_url(&image.src, base_url.unwrap_or_default()) {
+                None => image.src,
+                Some(url) if base_url.is_some() => url,
+                // Fixed contents have no bundle to resolve a relative path against.
+                Some(_) => continue,
+            };
+            ok &= self.check_path(source, None, &url, report);
+        }
+        ok
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ImageRef, ReferenceChecker, markdown_images};
+    use crate::seed::{error::SeedErrorReport, image::ImageConfig};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn create_temp_dir() -> PathBuf {
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time before UNIX_EPOCH")
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!(
+            "seed_references_test_{}_{}",
+            std::process::id(),
+            ts
+        ));
+        fs::create_dir_all(&dir).expect("failed to create temp dir");
+        dir
+    }
+
+    fn image_config(dir: &Path) -> ImageConfig {
+        ImageConfig::new(
+            Some(dir.join("image").to_string_lossy().to_string()),
+            Some(dir.join("icon").to_string_lossy().to_string()),
+            None,
+        )
+    }
+
+    #[test]
+    fn markdown_images_collects_src_and_alt() {
+        assert_eq!(
+            markdown_images("![A *b*](/image/a.png) text ![](b.png)"),
+            vec![
+                ImageRef {
+                    src: "/image/a.png".to_string(),
+                    alt: "A b".to_string(),
+                },
+                ImageRef {
+                    src: "b.png".to_string(),
+                    alt: String::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn resolve_maps_image_icon_and_bundle_urls() {
+        let dir = create_temp_dir();
+        let images = image_config(&dir);
+        let article_dir = dir.join("articles");
+        let checker = ReferenceChecker {
+            images: &images,
+            article_dir: &article_dir,
+        };
+        assert_eq!(
+            checker.resolve("/image/a.png?v=1"),
+            Some(dir.join("image").join("a.png"))
+        );
+        assert_eq!(
+            checker.resolve("/icon/fav.png"),
+            Some(dir.join("icon").join("fav.png"))
+        );
+        assert_eq!(
+            checker.resolve("/posts/hello/cover.png"),
+            Some(article_dir.join("hello").join("cover.png"))
+        );
+        assert_eq!(checker.resolve("https://example.com/a.png"), None);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn check_markdown_reports_missing_files_and_alt_text() {
+        let dir = create_temp_dir();
+        fs::create_dir_all(dir.join("image")).expect("failed to create image dir");
+        fs::write(dir.join("image/ok.png"), "").expect("failed to write ok.png");
+        let images = image_config(&dir);
+        let article_dir = dir.join("articles");
+        let checker = ReferenceChecker {
+            images: &images,
+            article_dir: &article_dir,
+        };
+        let mut report = SeedErrorReport::default();
+        let body = "![ok](/image/ok.png) ![](/image/typo.png) ![x](cover.png) ![y](https://example.com/y.png)";
+
+        assert!(!checker.check_markdown(Path::new("a.md"), body, Some("/posts/a/"), &mut report));
+        let messages: Vec<&str> = report.errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].starts_with("image not found: /image/typo.png"));
+        assert!(messages[1].starts_with("image not found: /posts/a/cover.png"));
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!(
+            report.warnings[0].message,
+            "image without alt text: /image/typo.png"
+        );
+
+        let mut report = SeedErrorReport::default();
+        assert!(checker.check_markdown(
+            Path::new("about.md"),
+            "![x](cover.png)",
+            None,
+            &mut report
+        ));
+        assert!(report.is_empty());
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::{slug_config::SlugConfig, utils::front_matter::FrontMatter};
+use anyhow::Context;
+use std::{collections::HashSet, fmt};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TaxonomyKind {
+    Tag,
+    Category,
+}
+
+impl fmt::Display for TaxonomyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaxonomyKind::Tag => write!(f, "tag"),
+            TaxonomyKind::Category => write!(f, "category"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaxonomyViolation {
+    pub kind: TaxonomyKind,
+    pub value: String,
+}
+
+/// Tag and category slugs declared in the `[tags]` / `[categories]` tables of the config TOML.
+#[derive(Debug, Default)]
+pub struct DeclaredTaxonomy {
+    pub tags: HashSet<String>,
+    pub categories: HashSet<String>,
+}
+
+impl DeclaredTaxonomy {
+    pub fn from_toml_file(toml_path: &str) -> anyhow::Result<Self> {
+        let tags = SlugConfig::from_toml_file_key(toml_path, "tags")
+            .with_context(|| format!("failed to read tags from {}", toml_path))?;
+        let categories = SlugConfig::from_toml_file_key(toml_path, "categories")
+            .with_context(|| format!("failed to read categories from {}", toml_path))?;
+        Ok(Self {
+            tags: tags.map.into_values().collect(),
+            categories: categories.map.into_values().collect(),
+        })
+    }
+
+    pub fn undeclared(&self, front_matter: &FrontMatter) -> Vec<TaxonomyViolation> {
+        let tags = front_matter
+            .tags
+            .iter()
+            .filter(|slug| !self.tags.contains(slug.as_str()))
+            .map(|slug| TaxonomyViolation {
+                kind: TaxonomyKind::Tag,
+                value: slug.clone(),
+            });
+        let categories = front_matter
+            .categories
+            .iter()
+            .filter(|slug| !self.categories.contains(slug.as_str()))
+            .map(|slug| TaxonomyViolation {
+                kind: TaxonomyKind::Category,
+                value: slug.clone(),
+            });
+        tags.chain(categories).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DeclaredTaxonomy, TaxonomyKind, TaxonomyViolation};
+    use crate::utils::front_matter::FrontMatter;
+
+    fn front_matter(tags: &[&str], categories: &[&str]) -> FrontMatter {
+        FrontMatter::new(
+            "title".to_string(),
+            "slug".to_string(),
+            false,
+            None,
+            None,
+            None,
+            tags.iter().map(|s| s.to_string()).collect(),
+            categories.iter().map(|s| s.to_string()).collect(),
+        )
+    }
+
+    fn declared() -> DeclaredTaxonomy {
+        DeclaredTaxonomy {
+            tags: ["rust".to_string()].into_iter().collect(),
+            categories: ["development".to_string()].into_iter().collect(),
+        }
+    }
+
+    #[test]
+    fn undeclared_returns_empty_when_all_slugs_are_declared() {
+        let fm = front_matter(&["rust"], &["development"]);
+        assert!(declared().undeclared(&fm).is_empty());
+    }
+
+    #[test]
+    fn undeclared_lists_unknown_tags_and_categories() {
+        let fm = front_matter(&["rust", "blog"], &["diary"]);
+        assert_eq!(
+            declared().undeclared(&fm),
+            vec![
+                TaxonomyViolation {
+                    kind: TaxonomyKind::Tag,
+                    value: "blog".to_string(),
+                },
+                TaxonomyViolation {
+                    kind: TaxonomyKind::Category,
+                    value: "diary".to_string(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use crate::seed::{
+    SeedScope,
+    config::PathConfig,
+    config::env::{load_env, load_image_config},
+    image::is_raster_image,
+    run, run_all,
+};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use rocket::tokio::{sync::mpsc, time};
+use sea_orm::DatabaseConnection;
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Editors often write a file in several steps, so events are collected for this long.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Runs the seed once, then again for the Markdown files and images that change. A change
+/// to the config TOML, or a removed Markdown file, reruns the whole seed.
+pub async fn watch(db: DatabaseConnection, dry_run: bool) -> anyhow::Result<()> {
+    let config = load_env();
+    let image_path = load_image_config().image_path;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+        let _ = tx.send(res);
+    })?;
+    let mut targets = watch_targets(&config);
+    targets.push((PathBuf::from(&image_path), RecursiveMode::Recursive));
+    for (path, mode) in targets {
+        if path.exists() {
+            watcher.watch(&path, mode)?;
+            println!("👀 {} を監視しています", path.display());
+        } else {
+            eprintln!("⚠️ {} が存在しないため監視しません", path.display());
+        }
+    }
+
+    if let Err(e) = run_all(&db, dry_run).await {
+        eprintln!("❌ {e:#}");
+    }
+    while let Some(res) = rx.recv().await {
+        let mut changed = BTreeSet::new();
+        collect_changes(res, &config, &image_path, &mut changed);
+        time::sleep(DEBOUNCE).await;
+        while let Ok(res) = rx.try_recv() {
+            collect_changes(res, &config, &image_path, &mut changed);
+        }
+        if changed.is_empty() {
+            continue;
+        }
+        for path in &changed {
+            println!("✏️ {}", path.display());
+        }
+        let scope = if needs_full_run(&changed, &config) {
+            SeedScope::All
+        } else {
+            SeedScope::Changed(&changed)
+        };
+        if let Err(e) = run(&db, dry_run, scope).await {
+            eprintln!("❌ {e:#}");
+        }
+    }
+    Ok(())
+}
+
+/// The config TOML declares the taxonomies, and a removed Markdown file can only be
+/// pruned with every slug at hand.
+fn needs_full_run(changed: &BTreeSet<PathBuf>, config: &PathConfig) -> bool {
+    let config_name = Path::new(&config.config_toml_path).file_name();
+    changed.iter().any(|path| {
+        path.file_name() == config_name
+            || (path.extension().and_then(|ext| ext.to_str()) == Some("md") && !path.exists())
+    })
+}
+
+fn watch_targets(config: &PathConfig) -> Vec<(PathBuf, RecursiveMode)> {
+    // The config file is watched through its directory because editors replace it on save.
+    let config_dir = match Path::new(&config.config_toml_path).parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    vec![
+        (
+            PathBuf::from(&config.article_path),
+            RecursiveMode::Recursive,
+        ),
+        (
+            PathBuf::from(&config.fixed_content_path),
+            RecursiveMode::Recursive,
+        ),
+        (config_dir, RecursiveMode::NonRecursive),
+    ]
+}
+
+fn collect_changes(
+    res: notify::Result<Event>,
+    config: &PathConfig,
+    image_path: &str,
+    changed: &mut BTreeSet<PathBuf>,
+) {
+    match res {
+        Ok(event) if !matches!(event.kind, EventKind::Access(_)) => changed.extend(
+            event
+                .paths
+                .into_iter()
+                .filter(|path| is_seed_source(path, config, image_path)),
+        ),
+        Ok(_) => {}
+        Err(e) => eprintln!("watch error: {e}"),
+    }
+}
+
+/// Markdown under the content directories, images under the article and image
+/// directories, and the config TOML. The config directory may hold anything else.
+fn is_seed_source(path: &Path, config: &PathConfig, image_path: &str) -> bool {
+    if path.extension().and_then(|ext| ext.to_str()) == Some("md") {
+        return is_under(path, &config.article_path) || is_under(path, &config.fixed_content_path);
+    }
+    if is_raster_image(path) {
+        return is_under(path, &config.article_path) || is_under(path, image_path);
+    }
+    path.file_name() == Path::new(&config.config_toml_path).file_name()
+}
+
+/// Events carry absolute paths while the configured directories are usually relative.
+fn is_under(path: &Path, dir: &str) -> bool {
+    let dir = Path::new(dir);
+    path.starts_with(dir)
+        || std::path::absolute(dir).is_ok_and(|dir| path.starts_with(dir))
+        || dir.canonicalize().is_ok_and(|dir| path.starts_with(dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_seed_source, needs_full_run, watch_targets};
+    use crate::seed::config::{PathConfig, PathConfigTrait};
+    use notify::RecursiveMode;
+    use std::collections::BTreeSet;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn is_seed_source_accepts_markdown_images_and_config_only() {
+        let config = PathConfig::new(None, None, None);
+        let is_source = |path: &str| is_seed_source(Path::new(path), &config, "content/image");
+        assert!(is_source("content/articles/a.md"));
+        assert!(is_source("content/fixed_contents/about.md"));
+        assert!(is_source("content/articles/post/figure.png"));
+        assert!(is_source("content/image/a.png"));
+        assert!(is_source("./blog_config.toml"));
+        let absolute = std::path::absolute("content/articles/a.md").expect("absolute path");
+        assert!(is_seed_source(&absolute, &config, "content/image"));
+        assert!(!is_source("content/articles/.a.md.swp"));
+        assert!(!is_source("README.md"));
+        assert!(!is_source("docs/testing.md"));
+        assert!(!is_source("screenshot.png"));
+        assert!(!is_source("Cargo.toml"));
+    }
+
+    #[test]
+    fn needs_full_run_only_for_the_config_and_removed_markdown() {
+        let config = PathConfig::new(None, None, None);
+        let dir = std::env::temp_dir().join(format!("seed_watch_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("failed to create temp dir");
+        let article = dir.join("a.md");
+        std::fs::write(&article, "---\n---\n").expect("failed to write article");
+        let changed =
+            |paths: &[PathBuf]| needs_full_run(&BTreeSet::from_iter(paths.to_vec()), &config);
+
+        assert!(!changed(&[article.clone(), dir.join("figure.png")]));
+        assert!(changed(&[article.clone(), dir.join("blog_config.toml")]));
+        assert!(changed(&[dir.join("removed.md")]));
+        std::fs::remove_dir_all(&dir).expect("failed to remove temp dir");
+    }
+
+    #[test]
+    fn watch_targets_uses_config_directory() {
+        let config = PathConfig::new(None, None, Some("config/blog.toml".to_string()));
+        let targets = watch_targets(&config);
+        assert_eq!(
+            targets[2],
+            (PathBuf::from("config"), RecursiveMode::NonRecursive)
+        );
+
+        let config = PathConfig::new(None, None, None);
+        assert_eq!(watch_targets(&config)[2].0, PathBuf::from("."));
+    }
+}
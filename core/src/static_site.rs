@@ -1,43 +1,119 @@
+pub mod link_check;
+pub mod manifest;
+pub mod minify;
+pub mod precompress;
+
 use std::{
+    borrow::Cow,
     collections::HashMap,
-    fs,
+    fmt, fs,
+    num::NonZeroUsize,
     path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::{Duration, Instant},
 };
 
 use anyhow::{Context as AnyhowContext, Result};
-use chrono::{Datelike, Utc};
-use sea_orm::DatabaseConnection;
-use serde_json::json;
+use chrono::{DateTime, Utc};
+use sea_orm::{DatabaseConnection, DbErr};
+use serde_json::Value;
 use tera::{Context, Tera};
 use walkdir::WalkDir;
 
+use self::{
+    manifest::{ExportSummary, OutputTree, hash_parts},
+    minify::{minify_css, minify_html},
+};
 use crate::{
-    domain::page::Page,
+    domain::page::{Page, PageInfo},
+    entity::{article, category, fixed_content, tag},
     repository::{
         article::{
-            ArticlePeriod, get_all_articles, get_all_published_articles, get_article_periods,
-            get_articles_by_tag_slug, get_article_by_category_slug, get_latest_articles,
+            ArticlePeriod, ArticleTerms, get_all_articles, get_all_published_articles,
+            get_article_by_category_slug, get_article_periods, get_article_terms,
+            get_articles_by_tag_slug, get_latest_articles,
         },
-        category::{get_all_categories, get_categories_by_article},
+        category::get_all_categories,
         fixed_content::get_all_fixed_contents,
-        tag::{get_all_tags, get_tags_by_article},
+        image::get_image_index,
+        tag::get_all_tags,
     },
+    seed::image::{image_sources, restore_variants},
     utils::{
-        config::CommonConfig, cut_out_string,
-        markdown::{markdown_to_html, markdown_to_text},
-        utc_to_jst,
+        asset::{ASSET_URL_FUNCTION, AssetManifest, AssetSource},
+        bundle::{bundle_assets, bundle_dir},
+        config::CommonConfig,
     },
+    view::{StaticUrls, Taxonomy, UrlStrategy, View},
 };
 
 const PAGE_SIZE: u64 = 10;
-const BULMA_CSS: &[u8] = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/bulma.min.css"));
-const SITE_CSS: &[u8] = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/site.css"));
-const NAV_JS: &[u8] = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/nav.js"));
 
 #[derive(Debug, Clone)]
 pub struct ExportPaths {
     pub templates_dir: PathBuf,
     pub content_dir: PathBuf,
+    pub article_dir: PathBuf,
+    pub variant_dir: PathBuf,
+}
+
+/// Where the exported site is served from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExportTarget {
+    /// Compresses responses itself, so the export has no `.gz` / `.br` siblings.
+    #[default]
+    CloudflarePages,
+    /// Serves the `.gz` / `.br` siblings through `docker/nginx-static.conf`.
+    Nginx,
+}
+
+impl ExportTarget {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "cloudflare" | "cloudflare-pages" => Some(Self::CloudflarePages),
+            "nginx" => Some(Self::Nginx),
+            _ => None,
+        }
+    }
+}
+
+/// Switches of the export that don't depend on where things are.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExportOptions {
+    /// Strip comments and insignificant whitespace from pages and stylesheets.
+    pub minify: bool,
+    pub target: ExportTarget,
+}
+
+/// Result of an export: what happened to the files and how long each phase took.
+#[derive(Debug, Clone)]
+pub struct ExportReport {
+    pub summary: ExportSummary,
+    pub pages: usize,
+    pub timings: PhaseTimings,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PhaseTimings(pub Vec<(&'static str, Duration)>);
+
+impl PhaseTimings {
+    fn record(&mut self, phase: &'static str, started: Instant) {
+        self.0.push((phase, started.elapsed()));
+    }
+
+    pub fn total(&self) -> Duration {
+        self.0.iter().map(|(_, elapsed)| *elapsed).sum()
+    }
+}
+
+impl fmt::Display for PhaseTimings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (phase, elapsed) in &self.0 {
+            write!(f, "{phase} {:.1?}, ", elapsed)?;
+        }
+        write!(f, "total {:.1?}", self.total())
+    }
 }
 
 pub async fn export_site(
@@ -45,490 +121,469 @@ pub async fn export_site(
     config_map: &HashMap<String, String>,
     out_dir: impl AsRef<Path>,
     paths: &ExportPaths,
-) -> Result<()> {
-    let out_dir = out_dir.as_ref();
-    reset_output_dir(out_dir)?;
-    write_static_assets(out_dir, &paths.content_dir)?;
+    options: &ExportOptions,
+) -> Result<ExportReport> {
+    let mut timings = PhaseTimings::default();
 
+    let started = Instant::now();
+    let mut output = OutputTree::open(out_dir.as_ref(), options.target == ExportTarget::Nginx)?;
     let config = CommonConfig {
         site_name: config_map.get("site_name").cloned(),
         default_icatch_path: config_map.get("default_icatch_path").cloned(),
         favicon_path: config_map.get("favicon_path").cloned(),
     };
-    let tera = load_templates(&paths.templates_dir)?;
-
-    export_index_pages(&tera, db, &config, out_dir).await?;
-    export_article_pages(&tera, db, &config, out_dir).await?;
-    export_fixed_content_pages(&tera, db, &config, out_dir).await?;
-    export_tag_pages(&tera, db, &config, out_dir).await?;
-    export_category_pages(&tera, db, &config, out_dir).await?;
-    export_error_page(&tera, &config, out_dir, "404", "404.html")?;
-    write_cloudflare_support_files(out_dir)?;
-
-    Ok(())
-}
-
-async fn export_index_pages(
-    tera: &Tera,
-    db: &DatabaseConnection,
-    config: &CommonConfig,
-    out_dir: &Path,
-) -> Result<()> {
-    let periods = get_article_periods(db, None).await?;
-    export_index_variant(tera, db, config, out_dir, None, &periods).await?;
-    for period in &periods {
-        export_index_variant(tera, db, config, out_dir, Some(*period), &periods).await?;
+    let (mut tera, templates_hash) = load_templates(&paths.templates_dir)?;
+    let assets = AssetManifest::embedded_with(|url, bytes| embedded_output(url, bytes, options))
+        .with_images(&paths.content_dir.join("image"))
+        .context("failed to fingerprint assets")?;
+    tera.register_function(ASSET_URL_FUNCTION, assets.clone());
+    let images = get_image_index(db).await?;
+    let site = SiteData::load(db).await?;
+    timings.record("load", started);
+
+    let started = Instant::now();
+    let view = View::new(&config, &images, &assets, &StaticUrls);
+    let mut image_sizes: Vec<_> = images.iter().collect();
+    image_sizes.sort_by_key(|(url, _)| *url);
+    // Pages link to the fingerprinted URLs, so they change along with any asset.
+    let site_hash = hash_parts([
+        templates_hash.as_bytes(),
+        hash_parts(assets.iter().map(|(url, _)| url.as_bytes())).as_bytes(),
+        format!("{config:?}").as_bytes(),
+        format!("{image_sizes:?}").as_bytes(),
+        if options.minify { b"minify" } else { b"" },
+    ]);
+    let renderer = Renderer {
+        tera: &tera,
+        site_hash: &site_hash,
+        assets: &assets,
+        view: &view,
+        site: &site,
+        minify: options.minify,
+    };
+    let sources = site.pages();
+    let rendered = render_parallel(&sources, |source| renderer.render(source, &output))?;
+    timings.record("render", started);
+
+    let started = Instant::now();
+    for page in &rendered {
+        match &page.html {
+            Some(html) => output.write(&page.path, html.as_bytes(), Some(&page.inputs))?,
+            None => output.keep(&page.path),
+        }
     }
-    Ok(())
+    timings.record("write", started);
+
+    let started = Instant::now();
+    write_static_assets(&mut output, &assets, &paths.content_dir, options)?;
+    let image_sources = image_sources(&paths.content_dir.join("image"), &paths.article_dir);
+    restore_variants(&images, &image_sources, &paths.variant_dir)
+        .context("failed to restore image variants")?;
+    copy_dir_recursive(&mut output, &paths.variant_dir, "variants")?;
+    for article in &site.articles {
+        copy_bundle_assets(&mut output, &paths.article_dir, &article.slug)?;
+    }
+    write_cloudflare_support_files(&mut output)?;
+    let summary = output.finish()?;
+    timings.record("assets", started);
+
+    Ok(ExportReport {
+        summary,
+        pages: rendered.len(),
+        timings,
+    })
+}
+
+/// Everything the export reads from the database, loaded before any rendering.
+struct SiteData {
+    periods: Vec<ArticlePeriod>,
+    latest_articles: Vec<article::Model>,
+    articles: Vec<article::Model>,
+    /// Tags and categories of every published article, which also covers the
+    /// articles in `listings`.
+    terms: ArticleTerms,
+    fixed_contents: Vec<fixed_content::Model>,
+    tags: Vec<tag::Model>,
+    categories: Vec<category::Model>,
+    listings: Vec<Listing>,
+}
+
+/// One page of a paginated article list.
+struct Listing {
+    kind: ListingKind,
+    articles: Vec<article::Model>,
+    page_info: PageInfo,
 }
 
-async fn export_index_variant(
-    tera: &Tera,
-    db: &DatabaseConnection,
-    config: &CommonConfig,
-    out_dir: &Path,
-    period: Option<ArticlePeriod>,
-    all_periods: &[ArticlePeriod],
-) -> Result<()> {
-    let (_, first_page_info) = get_all_articles(
-        db,
-        Page {
-            number: 1,
-            per: PAGE_SIZE,
-        },
-        period,
-    )
-    .await?;
+#[derive(Debug, Clone)]
+enum ListingKind {
+    Index(Option<ArticlePeriod>),
+    Term {
+        kind: Taxonomy,
+        slug: String,
+        sort_key: &'static str,
+    },
+}
 
-    for page_number in 1..=first_page_info.total_pages {
-        let page = Page {
-            number: page_number,
-            per: PAGE_SIZE,
-        };
-        let (models, page_info) = get_all_articles(db, page, period).await?;
-        let default_icatch_path = config.default_icatch_path.clone().unwrap_or_default();
-        let period_links: Vec<_> = all_periods
-            .iter()
-            .map(|p| {
-                json!({
-                    "label": format!("{}/{:02}", p.year, p.month),
-                    "href": static_index_url(1, Some(*p)),
-                    "is_selected": period == Some(*p),
-                })
+enum PageSource<'a> {
+    Listing(&'a Listing),
+    Article(&'a article::Model),
+    FixedContent(&'a fixed_content::Model),
+    TagList,
+    CategoryList,
+    NotFound,
+}
+
+impl SiteData {
+    async fn load(db: &DatabaseConnection) -> Result<Self> {
+        let periods = get_article_periods(db, None).await?;
+        let latest_articles = get_latest_articles(db, 5).await?;
+        let articles = get_all_published_articles(db).await?;
+        let terms = get_article_terms(db, &articles).await?;
+        let fixed_contents = get_all_fixed_contents(db).await?;
+        let tags = get_all_tags(db).await?;
+        let categories = get_all_categories(db).await?;
+
+        let mut listings = Vec::new();
+        for period in std::iter::once(None).chain(periods.iter().copied().map(Some)) {
+            load_listing(&mut listings, ListingKind::Index(period), |page| {
+                get_all_articles(db, page, period)
             })
-            .collect();
-        let articles: Vec<_> = models
-            .into_iter()
-            .map(|m| {
-                let excerpt = match m.excerpt.as_ref() {
-                    Some(value) => value.clone(),
-                    None => cut_out_string(&markdown_to_text(&m.content), 100),
+            .await?;
+        }
+        let term_slugs = tags
+            .iter()
+            .map(|tag| (Taxonomy::Tag, tag.slug.clone()))
+            .chain(
+                categories
+                    .iter()
+                    .map(|category| (Taxonomy::Category, category.slug.clone())),
+            );
+        for (kind, slug) in term_slugs {
+            for sort_key in ["created_at", "updated_at"] {
+                let listing = ListingKind::Term {
+                    kind,
+                    slug: slug.clone(),
+                    sort_key,
                 };
-                let icatch_path = m
-                    .icatch_path
-                    .clone()
-                    .unwrap_or_else(|| default_icatch_path.clone());
-                json!({
-                    "title": m.title,
-                    "slug": m.slug.clone(),
-                    "url": static_article_url(&m.slug),
-                    "excerpt": excerpt,
-                    "icatch_path": icatch_path,
-                    "created_at": utc_to_jst(m.created_at),
-                    "updated_at": utc_to_jst(m.updated_at),
+                load_listing(&mut listings, listing, |page| {
+                    get_term_articles(db, kind, page, &slug, sort_key)
                 })
-            })
-            .collect();
-
-        let mut ctx = base_context(config);
-        ctx.insert("articles", &articles);
-        ctx.insert("page", &page_info.current_page);
-        ctx.insert("per", &page_info.per);
-        ctx.insert("total_pages", &page_info.total_pages);
-        ctx.insert("has_prev", &page_info.has_prev);
-        ctx.insert("has_next", &page_info.has_next);
-        ctx.insert("prev_page", &page_info.prev_page);
-        ctx.insert("next_page", &page_info.next_page);
-        ctx.insert("prev_url", &static_index_url(page_info.prev_page, period));
-        ctx.insert("next_url", &static_index_url(page_info.next_page, period));
-        ctx.insert(
-            "selected_period",
-            &period.map(|p| format!("{}/{:02}", p.year, p.month)),
-        );
-        ctx.insert("period_links", &period_links);
-
-        render_to_path(
-            tera,
-            "index",
-            &ctx,
-            &out_dir.join(static_index_output_path(page_info.current_page, period)),
-        )?;
-    }
-
-    Ok(())
-}
-
-async fn export_article_pages(
-    tera: &Tera,
-    db: &DatabaseConnection,
-    config: &CommonConfig,
-    out_dir: &Path,
-) -> Result<()> {
-    let latest_articles = latest_articles_json(db).await?;
-    let articles = get_all_published_articles(db).await?;
-    for article in articles {
-        let tags: Vec<_> = get_tags_by_article(db, &article)
-            .await?
-            .into_iter()
-            .map(|tag| {
-                let slug = tag.slug;
-                json!({ "name": tag.name, "slug": slug.clone(), "url": static_tag_url(&slug, "created_at", 1) })
-            })
-            .collect();
-        let categories: Vec<_> = get_categories_by_article(db, &article)
-            .await?
-            .into_iter()
-            .map(|category| {
-                let slug = category.slug;
-                json!({ "name": category.name, "slug": slug.clone(), "url": static_category_url(&slug, "created_at", 1) })
-            })
-            .collect();
+                .await?;
+            }
+        }
 
-        let mut ctx = base_context(config);
-        ctx.insert("title", &article.title);
-        ctx.insert("content_html", &markdown_to_html(&article.content));
-        ctx.insert("created_at", &utc_to_jst(article.created_at));
-        ctx.insert("updated_at", &utc_to_jst(article.updated_at));
-        ctx.insert("tags", &tags);
-        ctx.insert("categories", &categories);
-        ctx.insert("latest_articles", &latest_articles);
-
-        render_to_path(
-            tera,
-            "article_detail",
-            &ctx,
-            &out_dir.join(format!("posts/{}/index.html", article.slug)),
-        )?;
+        Ok(Self {
+            periods,
+            latest_articles,
+            articles,
+            terms,
+            fixed_contents,
+            tags,
+            categories,
+            listings,
+        })
     }
-    Ok(())
-}
-
-async fn export_fixed_content_pages(
-    tera: &Tera,
-    db: &DatabaseConnection,
-    config: &CommonConfig,
-    out_dir: &Path,
-) -> Result<()> {
-    let latest_articles = latest_articles_json(db).await?;
-    let fixed_contents = get_all_fixed_contents(db).await?;
-    for fixed_content in fixed_contents {
-        let excerpt = match fixed_content.excerpt.as_ref() {
-            Some(value) => value.clone(),
-            None => cut_out_string(&markdown_to_text(&fixed_content.content), 100),
-        };
 
-        let mut ctx = base_context(config);
-        ctx.insert("title", &fixed_content.title);
-        ctx.insert("excerpt", &excerpt);
-        ctx.insert("content_html", &markdown_to_html(&fixed_content.content));
-        ctx.insert("created_at", &utc_to_jst(fixed_content.created_at));
-        ctx.insert("updated_at", &utc_to_jst(fixed_content.updated_at));
-        ctx.insert("latest_articles", &latest_articles);
-
-        render_to_path(
-            tera,
-            "about",
-            &ctx,
-            &out_dir.join(format!("{}/index.html", fixed_content.slug)),
-        )?;
+    fn pages(&self) -> Vec<PageSource<'_>> {
+        let mut pages: Vec<_> = self.listings.iter().map(PageSource::Listing).collect();
+        pages.extend(self.articles.iter().map(PageSource::Article));
+        pages.extend(self.fixed_contents.iter().map(PageSource::FixedContent));
+        pages.extend([
+            PageSource::TagList,
+            PageSource::CategoryList,
+            PageSource::NotFound,
+        ]);
+        pages
     }
-    Ok(())
 }
 
-async fn export_tag_pages(
-    tera: &Tera,
-    db: &DatabaseConnection,
-    config: &CommonConfig,
-    out_dir: &Path,
-) -> Result<()> {
-    let tags = get_all_tags(db).await?;
-    let tag_items: Vec<_> = tags
-        .iter()
-        .map(|tag| {
-            let slug = tag.slug.clone();
-            json!({ "name": tag.name, "slug": slug.clone(), "url": static_tag_url(&slug, "created_at", 1) })
+/// Fetches every page of one listing, reading the page count from the first.
+async fn load_listing<F, Fut, E>(
+    listings: &mut Vec<Listing>,
+    kind: ListingKind,
+    fetch: F,
+) -> Result<()>
+where
+    F: Fn(Page) -> Fut,
+    Fut: Future<Output = Result<(Vec<article::Model>, PageInfo), E>>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let mut page_number = 1;
+    loop {
+        let (articles, page_info) = fetch(Page {
+            number: page_number,
+            per: PAGE_SIZE,
         })
-        .collect();
-
-    let mut list_ctx = base_context(config);
-    list_ctx.insert("tags", &tag_items);
-    render_to_path(tera, "tags", &list_ctx, &out_dir.join("tags/index.html"))?;
-
-    for tag in tags {
-        for sort_key in ["created_at", "updated_at"] {
-            export_tag_variant(tera, db, config, out_dir, &tag.slug, sort_key).await?;
+        .await?;
+        let is_last = page_number >= page_info.total_pages;
+        listings.push(Listing {
+            kind: kind.clone(),
+            articles,
+            page_info,
+        });
+        if is_last {
+            return Ok(());
         }
+        page_number += 1;
     }
-
-    Ok(())
 }
 
-async fn export_tag_variant(
-    tera: &Tera,
+async fn get_term_articles(
     db: &DatabaseConnection,
-    config: &CommonConfig,
-    out_dir: &Path,
+    kind: Taxonomy,
+    page: Page,
     slug: &str,
     sort_key: &str,
-) -> Result<()> {
-    let (_, first_page_info) = get_articles_by_tag_slug(
-        db,
-        Page {
-            number: 1,
-            per: PAGE_SIZE,
-        },
-        slug,
-        sort_key,
-    )
-    .await?;
-
-    for page_number in 1..=first_page_info.total_pages {
-        let (articles, page_info) = get_articles_by_tag_slug(
-            db,
-            Page {
-                number: page_number,
-                per: PAGE_SIZE,
-            },
-            slug,
-            sort_key,
-        )
-        .await?;
-        let default_icatch_path = config.default_icatch_path.clone().unwrap_or_default();
-
-        let article_items: Vec<_> = articles
-            .iter()
-            .map(|article| {
-                let icatch_path = article
-                    .icatch_path
-                    .clone()
-                    .unwrap_or_else(|| default_icatch_path.clone());
-                let excerpt = match article.excerpt.as_ref() {
-                    Some(value) => value.clone(),
-                    None => cut_out_string(&markdown_to_text(&article.content), 100),
-                };
-                let slug = article.slug.clone();
-                json!({
-                    "title": article.title.clone(),
-                    "slug": slug.clone(),
-                    "url": static_article_url(&slug),
-                    "icatch_path": icatch_path,
-                    "excerpt": excerpt,
-                    "created_at": utc_to_jst(article.created_at),
-                })
-            })
-            .collect();
-
-        let mut ctx = base_context(config);
-        ctx.insert("tag_slug", &slug);
-        ctx.insert("sort_key", &sort_key);
-        ctx.insert("sort_created_url", &static_tag_url(slug, "created_at", 1));
-        ctx.insert("sort_updated_url", &static_tag_url(slug, "updated_at", 1));
-        ctx.insert("articles", &article_items);
-        ctx.insert("page", &page_info.current_page);
-        ctx.insert("per", &page_info.per);
-        ctx.insert("total_pages", &page_info.total_pages);
-        ctx.insert("has_prev", &page_info.has_prev);
-        ctx.insert("has_next", &page_info.has_next);
-        ctx.insert("prev_page", &page_info.prev_page);
-        ctx.insert("next_page", &page_info.next_page);
-        ctx.insert(
-            "prev_url",
-            &static_tag_url(slug, sort_key, page_info.prev_page),
-        );
-        ctx.insert(
-            "next_url",
-            &static_tag_url(slug, sort_key, page_info.next_page),
-        );
-
-        render_to_path(
-            tera,
-            "tag",
-            &ctx,
-            &out_dir.join(static_tag_output_path(slug, sort_key, page_info.current_page)),
-        )?;
+) -> Result<(Vec<article::Model>, PageInfo), DbErr> {
+    match kind {
+        Taxonomy::Tag => get_articles_by_tag_slug(db, page, slug, sort_key).await,
+        Taxonomy::Category => get_article_by_category_slug(db, page, slug, sort_key).await,
     }
-    Ok(())
 }
 
-async fn export_category_pages(
-    tera: &Tera,
-    db: &DatabaseConnection,
-    config: &CommonConfig,
-    out_dir: &Path,
-) -> Result<()> {
-    let categories = get_all_categories(db).await?;
-    let category_items: Vec<_> = categories
-        .iter()
-        .map(|category| {
-            let slug = category.slug.clone();
-            json!({ "name": category.name, "slug": slug.clone(), "url": static_category_url(&slug, "created_at", 1) })
-        })
-        .collect();
-
-    let mut list_ctx = base_context(config);
-    list_ctx.insert("categories", &category_items);
-    render_to_path(
-        tera,
-        "categories",
-        &list_ctx,
-        &out_dir.join("categories/index.html"),
-    )?;
-
-    for category in categories {
-        for sort_key in ["created_at", "updated_at"] {
-            export_category_variant(tera, db, config, out_dir, &category.slug, sort_key).await?;
+struct RenderedPage {
+    path: String,
+    inputs: String,
+    /// `None` when the page from the last export can be kept.
+    html: Option<String>,
+}
+
+struct Renderer<'a> {
+    tera: &'a Tera,
+    /// Templates, assets, config and image sizes, which every page depends on.
+    site_hash: &'a str,
+    assets: &'a AssetManifest,
+    view: &'a View<'a>,
+    site: &'a SiteData,
+    minify: bool,
+}
+
+impl Renderer<'_> {
+    /// The template context, which converts every article body, is only built
+    /// for pages whose inputs changed since the last export.
+    fn render(&self, source: &PageSource<'_>, output: &OutputTree) -> Result<RenderedPage> {
+        let (template, path) = target(source);
+        let inputs = hash_parts([
+            self.site_hash.as_bytes(),
+            template.as_bytes(),
+            self.inputs(source).as_bytes(),
+        ]);
+        if output.is_fresh(&path, &inputs) {
+            return Ok(RenderedPage {
+                path,
+                inputs,
+                html: None,
+            });
         }
+        let data = self.context(source)?;
+        let ctx = Context::from_value(data)
+            .with_context(|| format!("failed to build context for {path}"))?;
+        let html = self
+            .tera
+            .render(template, &ctx)
+            .with_context(|| format!("failed to render template {template} for {path}"))?;
+        let html = self.assets.rewrite_urls(&html);
+        let html = if self.minify {
+            minify_html(&html)
+        } else {
+            html
+        };
+        Ok(RenderedPage {
+            path,
+            inputs,
+            html: Some(html),
+        })
     }
 
-    Ok(())
-}
+    /// The rows a page shows, as stored by the seeder.
+    fn inputs(&self, source: &PageSource<'_>) -> String {
+        let site = self.site;
+        let terms_of = |article: &article::Model| {
+            format!(
+                "{:?}{:?}",
+                site.terms.tags_of(article.id),
+                site.terms.categories_of(article.id)
+            )
+        };
+        let latest_articles = || site.latest_articles.iter().map(article_revision);
+        let parts: Vec<String> = match source {
+            PageSource::Listing(listing) => {
+                let mut parts = vec![
+                    format!("{:?}", listing.kind),
+                    format!("{:?}", listing.page_info),
+                ];
+                for article in &listing.articles {
+                    parts.push(article_revision(article));
+                    if let ListingKind::Index(_) = listing.kind {
+                        parts.push(terms_of(article));
+                    }
+                }
+                if let ListingKind::Index(_) = listing.kind {
+                    parts.push(format!("{:?}", site.periods));
+                }
+                parts
+            }
+            PageSource::Article(article) => [article_revision(article), terms_of(article)]
+                .into_iter()
+                .chain(latest_articles())
+                .collect(),
+            PageSource::FixedContent(fixed_content) => [revision(
+                &fixed_content.slug,
+                fixed_content.content_hash.as_deref(),
+                &fixed_content.content,
+                fixed_content.updated_at,
+            )]
+            .into_iter()
+            .chain(latest_articles())
+            .collect(),
+            PageSource::TagList => vec![format!("{:?}", site.tags)],
+            PageSource::CategoryList => vec![format!("{:?}", site.categories)],
+            PageSource::NotFound => Vec::new(),
+        };
+        hash_parts(parts.iter().map(String::as_bytes))
+    }
 
-async fn export_category_variant(
-    tera: &Tera,
-    db: &DatabaseConnection,
-    config: &CommonConfig,
-    out_dir: &Path,
-    slug: &str,
-    sort_key: &str,
-) -> Result<()> {
-    let (_, first_page_info) = get_article_by_category_slug(
-        db,
-        Page {
-            number: 1,
-            per: PAGE_SIZE,
-        },
-        slug,
-        sort_key,
-    )
-    .await?;
-
-    for page_number in 1..=first_page_info.total_pages {
-        let (articles, page_info) = get_article_by_category_slug(
-            db,
-            Page {
-                number: page_number,
-                per: PAGE_SIZE,
+    fn context(&self, source: &PageSource<'_>) -> Result<Value> {
+        let view = self.view;
+        let site = self.site;
+        let data = match source {
+            PageSource::Listing(listing) => match &listing.kind {
+                ListingKind::Index(period) => serde_json::to_value(view.index(
+                    &listing.articles,
+                    &site.terms,
+                    &listing.page_info,
+                    *period,
+                    &site.periods,
+                ))?,
+                ListingKind::Term {
+                    kind,
+                    slug,
+                    sort_key,
+                } => serde_json::to_value(view.term(
+                    *kind,
+                    slug,
+                    sort_key,
+                    &listing.articles,
+                    &listing.page_info,
+                ))?,
             },
-            slug,
-            sort_key,
-        )
-        .await?;
-        let default_icatch_path = config.default_icatch_path.clone().unwrap_or_default();
-
-        let article_items: Vec<_> = articles
-            .iter()
-            .map(|article| {
-                let icatch_path = article
-                    .icatch_path
-                    .clone()
-                    .unwrap_or_else(|| default_icatch_path.clone());
-                let excerpt = match article.excerpt.as_ref() {
-                    Some(value) => value.clone(),
-                    None => cut_out_string(&markdown_to_text(&article.content), 100),
-                };
-                let slug = article.slug.clone();
-                json!({
-                    "title": article.title.clone(),
-                    "slug": slug.clone(),
-                    "url": static_article_url(&slug),
-                    "icatch_path": icatch_path,
-                    "excerpt": excerpt,
-                    "created_at": utc_to_jst(article.created_at),
-                })
-            })
-            .collect();
-
-        let mut ctx = base_context(config);
-        ctx.insert("category_slug", &slug);
-        ctx.insert("sort_key", &sort_key);
-        ctx.insert("sort_created_url", &static_category_url(slug, "created_at", 1));
-        ctx.insert("sort_updated_url", &static_category_url(slug, "updated_at", 1));
-        ctx.insert("articles", &article_items);
-        ctx.insert("page", &page_info.current_page);
-        ctx.insert("per", &page_info.per);
-        ctx.insert("total_pages", &page_info.total_pages);
-        ctx.insert("has_prev", &page_info.has_prev);
-        ctx.insert("has_next", &page_info.has_next);
-        ctx.insert("prev_page", &page_info.prev_page);
-        ctx.insert("next_page", &page_info.next_page);
-        ctx.insert(
-            "prev_url",
-            &static_category_url(slug, sort_key, page_info.prev_page),
-        );
-        ctx.insert(
-            "next_url",
-            &static_category_url(slug, sort_key, page_info.next_page),
-        );
-
-        render_to_path(
-            tera,
-            "category",
-            &ctx,
-            &out_dir.join(static_category_output_path(slug, sort_key, page_info.current_page)),
-        )?;
+            PageSource::Article(article) => serde_json::to_value(view.article_detail(
+                article,
+                site.terms.tags_of(article.id),
+                site.terms.categories_of(article.id),
+                &site.latest_articles,
+            ))?,
+            PageSource::FixedContent(fixed_content) => {
+                serde_json::to_value(view.fixed_content(fixed_content, &site.latest_articles))?
+            }
+            PageSource::TagList => serde_json::to_value(view.tag_list(&site.tags))?,
+            PageSource::CategoryList => serde_json::to_value(view.category_list(&site.categories))?,
+            PageSource::NotFound => serde_json::to_value(view.layout())?,
+        };
+        Ok(data)
     }
-    Ok(())
 }
 
-fn export_error_page(
-    tera: &Tera,
-    config: &CommonConfig,
-    out_dir: &Path,
-    template_name: &str,
-    output_name: &str,
-) -> Result<()> {
-    let ctx = base_context(config);
-    render_to_path(tera, template_name, &ctx, &out_dir.join(output_name))
+/// Template and output path of a page.
+fn target(source: &PageSource<'_>) -> (&'static str, String) {
+    let (template, url) = match source {
+        PageSource::Listing(listing) => match &listing.kind {
+            ListingKind::Index(period) => (
+                "index",
+                StaticUrls.index(listing.page_info.current_page, PAGE_SIZE, *period),
+            ),
+            ListingKind::Term {
+                kind,
+                slug,
+                sort_key,
+            } => (
+                match kind {
+                    Taxonomy::Tag => "tag",
+                    Taxonomy::Category => "category",
+                },
+                StaticUrls.term_page(
+                    *kind,
+                    slug,
+                    sort_key,
+                    listing.page_info.current_page,
+                    PAGE_SIZE,
+                ),
+            ),
+        },
+        PageSource::Article(article) => ("article_detail", StaticUrls.article(&article.slug)),
+        PageSource::FixedContent(fixed_content) => {
+            ("about", StaticUrls.fixed_content(&fixed_content.slug))
+        }
+        PageSource::TagList => ("tags", StaticUrls.term_list(Taxonomy::Tag)),
+        PageSource::CategoryList => ("categories", StaticUrls.term_list(Taxonomy::Category)),
+        PageSource::NotFound => return ("404", "404.html".to_string()),
+    };
+    (template, StaticUrls::output_path(&url))
 }
 
-async fn latest_articles_json(db: &DatabaseConnection) -> Result<Vec<serde_json::Value>> {
-    Ok(get_latest_articles(db, 5)
-        .await?
-        .into_iter()
-        .map(|model| {
-            let slug = model.slug;
-            json!({
-                "title": model.title,
-                "slug": slug.clone(),
-                "url": static_article_url(&slug),
+fn article_revision(article: &article::Model) -> String {
+    let revision = revision(
+        &article.slug,
+        article.content_hash.as_deref(),
+        &article.content,
+        article.updated_at,
+    );
+    format!("{revision}@{}", article.created_at.to_rfc3339())
+}
+
+/// A seeded row: the seeder's content hash covers its front matter and body,
+/// so the body is only read for rows stored without one.
+fn revision(
+    slug: &str,
+    content_hash: Option<&str>,
+    content: &str,
+    updated_at: DateTime<Utc>,
+) -> String {
+    let content = content_hash.map_or_else(|| hash_parts([content.as_bytes()]), str::to_string);
+    format!("{slug}@{content}@{}", updated_at.to_rfc3339())
+}
+
+/// Runs `render` over `items` on every available core, keeping the input order.
+fn render_parallel<T, R, F>(items: &[T], render: F) -> Result<Vec<R>>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> Result<R> + Sync,
+{
+    let workers = thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(items.len())
+        .max(1);
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<(usize, R)> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| -> Result<Vec<(usize, R)>> {
+                    let mut done = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(index) else {
+                            return Ok(done);
+                        };
+                        done.push((index, render(item)?));
+                    }
+                })
             })
-        })
-        .collect())
-}
-
-fn base_context(config: &CommonConfig) -> Context {
-    let mut ctx = Context::new();
-    ctx.insert("site_name", &config.site_name);
-    ctx.insert("favicon_path", &config.favicon_path);
-    ctx.insert("year", &Utc::now().year());
-    ctx.insert("tags_url", "/tags/");
-    ctx.insert("categories_url", "/categories/");
-    ctx.insert("about_url", "/about/");
-    ctx
-}
-
-fn render_to_path(tera: &Tera, template: &str, ctx: &Context, output: &Path) -> Result<()> {
-    let rendered = tera
-        .render(template, ctx)
-        .with_context(|| format!("failed to render template {template}"))?;
-    if let Some(parent) = output.parent() {
-        fs::create_dir_all(parent)?;
-    }
-    fs::write(output, rendered).with_context(|| format!("failed to write {:?}", output))?;
-    Ok(())
+            .collect();
+        let mut results = Vec::with_capacity(items.len());
+        for handle in handles {
+            results.extend(handle.join().expect("render worker panicked")?);
+        }
+        Ok::<_, anyhow::Error>(results)
+    })?;
+    results.sort_by_key(|(index, _)| *index);
+    Ok(results.into_iter().map(|(_, result)| result).collect())
 }
 
-fn load_templates(root: &Path) -> Result<Tera> {
+/// Also returns a hash over every template, so that pages get re-rendered
+/// whenever any of them changes.
+fn load_templates(root: &Path) -> Result<(Tera, String)> {
     let mut templates = Vec::new();
     for entry in WalkDir::new(root) {
         let entry = entry?;
@@ -544,10 +599,16 @@ fn load_templates(root: &Path) -> Result<Tera> {
             fs::read_to_string(path).with_context(|| format!("failed to read {:?}", path))?;
         templates.push((name, contents));
     }
+    templates.sort();
+    let templates_hash = hash_parts(
+        templates
+            .iter()
+            .flat_map(|(name, contents)| [name.as_bytes(), contents.as_bytes()]),
+    );
     let mut tera = Tera::default();
     tera.add_raw_templates(templates)
         .context("failed to register templates")?;
-    Ok(tera)
+    Ok((tera, templates_hash))
 }
 
 fn normalize_template_name(path: &Path) -> String {
@@ -558,35 +619,46 @@ fn normalize_template_name(path: &Path) -> String {
         .to_string()
 }
 
-fn reset_output_dir(out_dir: &Path) -> Result<()> {
-    if out_dir.exists() {
-        fs::remove_dir_all(out_dir)
-            .with_context(|| format!("failed to clear output dir {:?}", out_dir))?;
+/// Bytes written for an embedded asset, which is also what it is fingerprinted by.
+fn embedded_output(url: &str, bytes: &'static [u8], options: &ExportOptions) -> Cow<'static, [u8]> {
+    if options.minify && url.ends_with(".css") {
+        Cow::Owned(minify_css(&String::from_utf8_lossy(bytes)).into_bytes())
+    } else {
+        Cow::Borrowed(bytes)
     }
-    fs::create_dir_all(out_dir)?;
-    Ok(())
 }
 
-fn write_static_assets(out_dir: &Path, content_dir: &Path) -> Result<()> {
-    write_embedded_asset_file(out_dir.join("css/bulma.min.css"), BULMA_CSS)?;
-    write_embedded_asset_file(out_dir.join("css/site.css"), SITE_CSS)?;
-    write_embedded_asset_file(out_dir.join("js/nav.js"), NAV_JS)?;
-    copy_dir_recursive(&content_dir.join("image"), &out_dir.join("image"))?;
-    copy_dir_recursive(&content_dir.join("icon"), &out_dir.join("icon"))?;
+/// Stylesheets, scripts and images are only written under their fingerprinted
+/// URL, which pages are rewritten to reference.
+fn write_static_assets(
+    output: &mut OutputTree,
+    assets: &AssetManifest,
+    content_dir: &Path,
+    options: &ExportOptions,
+) -> Result<()> {
+    for (url, asset) in assets.iter() {
+        let path = url.trim_start_matches('/');
+        match &asset.source {
+            AssetSource::Embedded(bytes) => {
+                output.write(path, &embedded_output(&asset.url, bytes, options), None)?
+            }
+            AssetSource::File(file) => output.copy(file, path)?,
+        }
+    }
+    copy_dir_recursive(output, &content_dir.join("icon"), "icon")?;
     Ok(())
 }
 
-fn write_cloudflare_support_files(out_dir: &Path) -> Result<()> {
-    fs::write(out_dir.join("_headers"), build_headers_file())
-        .with_context(|| format!("failed to write {:?}", out_dir.join("_headers")))?;
-    fs::write(out_dir.join("_redirects"), build_redirects_file(out_dir)?)
-        .with_context(|| format!("failed to write {:?}", out_dir.join("_redirects")))?;
-    Ok(())
+fn write_cloudflare_support_files(output: &mut OutputTree) -> Result<()> {
+    output.write("_headers", build_headers_file().as_bytes(), None)?;
+    let redirects = build_redirects_file(output.paths());
+    output.write("_redirects", redirects.as_bytes(), None)
 }
 
 fn build_headers_file() -> String {
     [
         "/*",
+        "  Vary: Accept-Encoding",
         "  Content-Security-Policy: default-src 'self'; script-src 'self'; style-src 'self'; img-src 'self' https: data:; object-src 'none'; base-uri 'self'; frame-ancestors 'none'; form-action 'self'",
         "  Referrer-Policy: strict-origin-when-cross-origin",
         "  X-Content-Type-Options: nosniff",
@@ -603,14 +675,15 @@ fn build_headers_file() -> String {
         "/image/*",
         "  Cache-Control: public, max-age=31556952, immutable",
         "",
+        // Icons keep their plain names, so they may change under the same URL.
         "/icon/*",
-        "  Cache-Control: public, max-age=31556952, immutable",
+        "  Cache-Control: public, max-age=86400",
         "",
     ]
     .join("\n")
 }
 
-fn build_redirects_file(out_dir: &Path) -> Result<String> {
+fn build_redirects_file<'a>(paths: impl IntoIterator<Item = &'a str>) -> String {
     let mut redirects = vec![
         "/page/:page /page/:page/ 308".to_string(),
         "/archive/:year/:month /archive/:year/:month/ 308".to_string(),
@@ -628,188 +701,206 @@ fn build_redirects_file(out_dir: &Path) -> Result<String> {
         "/category/:slug/updated/page/:page /category/:slug/updated/page/:page/ 308".to_string(),
     ];
 
-    redirects.extend(discover_fixed_content_redirects(out_dir)?);
+    redirects.extend(discover_fixed_content_redirects(paths));
     redirects.sort();
     redirects.dedup();
     redirects.push(String::new());
-    Ok(redirects.join("\n"))
-}
-
-fn write_embedded_asset_file(target: PathBuf, contents: &[u8]) -> Result<()> {
-    if let Some(parent) = target.parent() {
-        fs::create_dir_all(parent)?;
-    }
-    fs::write(&target, contents).with_context(|| format!("failed to write {:?}", target))?;
-    Ok(())
+    redirects.join("\n")
 }
 
-fn copy_dir_recursive(source: &Path, target: &Path) -> Result<()> {
+fn copy_dir_recursive(output: &mut OutputTree, source: &Path, target: &str) -> Result<()> {
     if !source.exists() {
         return Ok(());
     }
     for entry in WalkDir::new(source) {
         let entry = entry?;
-        let relative = entry.path().strip_prefix(source)?;
-        let destination = target.join(relative);
-        if entry.file_type().is_dir() {
-            fs::create_dir_all(&destination)?;
-        } else {
-            if let Some(parent) = destination.parent() {
-                fs::create_dir_all(parent)?;
-            }
-            fs::copy(entry.path(), &destination).with_context(|| {
-                format!(
-                    "failed to copy static asset from {:?} to {:?}",
-                    entry.path(),
-                    destination
-                )
-            })?;
+        if !entry.file_type().is_file() {
+            continue;
         }
+        let relative = entry.path().strip_prefix(source)?;
+        output.copy(entry.path(), &output_path_for(target, relative))?;
     }
     Ok(())
 }
 
-fn static_index_output_path(page: u64, period: Option<ArticlePeriod>) -> PathBuf {
-    match period {
-        None if page <= 1 => PathBuf::from("index.html"),
-        None => PathBuf::from(format!("page/{page}/index.html")),
-        Some(period) if page <= 1 => {
-            PathBuf::from(format!("archive/{}/{:02}/index.html", period.year, period.month))
-        }
-        Some(period) => PathBuf::from(format!(
-            "archive/{}/{:02}/page/{page}/index.html",
-            period.year, period.month
-        )),
-    }
-}
-
-fn static_index_url(page: u64, period: Option<ArticlePeriod>) -> String {
-    match period {
-        None if page <= 1 => "/".to_string(),
-        None => format!("/page/{page}/"),
-        Some(period) if page <= 1 => format!("/archive/{}/{:02}/", period.year, period.month),
-        Some(period) => format!("/archive/{}/{:02}/page/{page}/", period.year, period.month),
-    }
-}
-
-fn static_article_url(slug: &str) -> String {
-    format!("/posts/{slug}/")
-}
-
-fn static_tag_output_path(slug: &str, sort_key: &str, page: u64) -> PathBuf {
-    let sort_segment = if sort_key == "updated_at" {
-        "updated/"
-    } else {
-        ""
-    };
-    if page <= 1 {
-        PathBuf::from(format!("tag/{slug}/{sort_segment}index.html"))
-    } else {
-        PathBuf::from(format!("tag/{slug}/{sort_segment}page/{page}/index.html"))
-    }
-}
-
-fn static_tag_url(slug: &str, sort_key: &str, page: u64) -> String {
-    let sort_segment = if sort_key == "updated_at" {
-        "updated/"
-    } else {
-        ""
-    };
-    if page <= 1 {
-        format!("/tag/{slug}/{sort_segment}")
-    } else {
-        format!("/tag/{slug}/{sort_segment}page/{page}/")
-    }
-}
-
-fn static_category_output_path(slug: &str, sort_key: &str, page: u64) -> PathBuf {
-    let sort_segment = if sort_key == "updated_at" {
-        "updated/"
-    } else {
-        ""
-    };
-    if page <= 1 {
-        PathBuf::from(format!("category/{slug}/{sort_segment}index.html"))
-    } else {
-        PathBuf::from(format!(
-            "category/{slug}/{sort_segment}page/{page}/index.html"
-        ))
+fn copy_bundle_assets(output: &mut OutputTree, article_dir: &Path, slug: &str) -> Result<()> {
+    let source = bundle_dir(article_dir, slug);
+    for relative in bundle_assets(article_dir, slug) {
+        output.copy(
+            &source.join(&relative),
+            &output_path_for(&format!("posts/{slug}"), &relative),
+        )?;
     }
+    Ok(())
 }
 
-fn static_category_url(slug: &str, sort_key: &str, page: u64) -> String {
-    let sort_segment = if sort_key == "updated_at" {
-        "updated/"
-    } else {
-        ""
-    };
-    if page <= 1 {
-        format!("/category/{slug}/{sort_segment}")
-    } else {
-        format!("/category/{slug}/{sort_segment}page/{page}/")
-    }
+fn output_path_for(target: &str, relative: &Path) -> String {
+    format!("{target}/{}", relative.to_string_lossy().replace('\\', "/"))
 }
 
-fn discover_fixed_content_redirects(out_dir: &Path) -> Result<Vec<String>> {
-    let mut redirects = Vec::new();
-    for entry in fs::read_dir(out_dir)? {
-        let entry = entry?;
-        if !entry.file_type()?.is_dir() {
-            continue;
-        }
-        let name = entry.file_name();
-        let name = name.to_string_lossy();
-        if is_reserved_root_dir(&name) {
-            continue;
-        }
-        if entry.path().join("index.html").exists() {
-            redirects.push(format!("/{name} /{name}/ 308"));
-        }
-    }
-    Ok(redirects)
+/// Top-level directories holding an `index.html`, other than the generated
+/// listings, are fixed contents.
+fn discover_fixed_content_redirects<'a>(paths: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    paths
+        .into_iter()
+        .filter_map(|path| path.strip_suffix("/index.html"))
+        .filter(|name| !name.contains('/') && !is_reserved_root_dir(name))
+        .map(|name| format!("/{name} /{name}/ 308"))
+        .collect()
 }
 
 fn is_reserved_root_dir(name: &str) -> bool {
     matches!(
         name,
-        "archive" | "category" | "categories" | "css" | "icon" | "image" | "js" | "page"
-            | "posts" | "tag" | "tags"
+        "archive"
+            | "category"
+            | "categories"
+            | "css"
+            | "icon"
+            | "image"
+            | "js"
+            | "page"
+            | "posts"
+            | "tag"
+            | "tags"
     )
 }
 
 #[cfg(test)]
 mod tests {
-    use super::build_redirects_file;
-    use std::{fs, path::PathBuf, time::{SystemTime, UNIX_EPOCH}};
-
-    fn temp_export_dir() -> PathBuf {
-        let unique = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("system time before unix epoch")
-            .as_nanos();
-        std::env::temp_dir().join(format!("rust-blog-static-site-{unique}"))
+    use super::{
+        ExportOptions, ExportTarget, article_revision, build_headers_file, build_redirects_file,
+        embedded_output, minify::minify_css, render_parallel,
+    };
+    use crate::entity::article;
+    use crate::utils::asset::{AssetManifest, EMBEDDED_ASSETS, fingerprint};
+    use anyhow::bail;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn article_revision_reads_the_body_only_without_a_content_hash() {
+        let at = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        let article = article::Model {
+            id: 1,
+            title: "Title".to_string(),
+            slug: "post".to_string(),
+            excerpt: None,
+            content: "body".to_string(),
+            created_at: at,
+            updated_at: at,
+            icatch_path: None,
+            content_hash: Some("hash".to_string()),
+        };
+        let revision = article_revision(&article);
+
+        let truncated = article::Model {
+            content: "bo".to_string(),
+            ..article.clone()
+        };
+        assert_eq!(article_revision(&truncated), revision);
+        let edited = article::Model {
+            content_hash: Some("other".to_string()),
+            ..article.clone()
+        };
+        assert_ne!(article_revision(&edited), revision);
+        let unhashed = article::Model {
+            content_hash: None,
+            ..article.clone()
+        };
+        assert_ne!(
+            article_revision(&unhashed),
+            article_revision(&article::Model {
+                content: "edited".to_string(),
+                ..unhashed.clone()
+            })
+        );
     }
 
     #[test]
     fn build_redirects_file_uses_pattern_rules_instead_of_per_directory_rules() {
-        let out_dir = temp_export_dir();
-        fs::create_dir_all(out_dir.join("posts/one")).expect("failed to create posts/one");
-        fs::create_dir_all(out_dir.join("posts/two")).expect("failed to create posts/two");
-        fs::create_dir_all(out_dir.join("about")).expect("failed to create about");
-        fs::create_dir_all(out_dir.join("css")).expect("failed to create css");
-        fs::write(out_dir.join("posts/one/index.html"), "").expect("failed to write one");
-        fs::write(out_dir.join("posts/two/index.html"), "").expect("failed to write two");
-        fs::write(out_dir.join("about/index.html"), "").expect("failed to write about");
-
-        let redirects = build_redirects_file(&out_dir).expect("failed to build redirects");
+        let redirects = build_redirects_file([
+            "posts/one/index.html",
+            "posts/two/index.html",
+            "about/index.html",
+            "css/site.css",
+        ]);
 
         assert!(redirects.contains("/posts/:slug /posts/:slug/ 308"));
         assert!(redirects.contains("/about /about/ 308"));
-        assert_eq!(redirects.matches("/posts/:slug /posts/:slug/ 308").count(), 1);
+        assert_eq!(
+            redirects.matches("/posts/:slug /posts/:slug/ 308").count(),
+            1
+        );
         assert!(!redirects.contains("/posts/one /posts/one/ 308"));
         assert!(!redirects.contains("/posts/two /posts/two/ 308"));
         assert!(!redirects.contains("/css /css/ 308"));
+        assert!(!redirects.contains(" 302"));
+    }
+
+    #[test]
+    fn build_headers_file_marks_only_fingerprinted_assets_immutable() {
+        let headers = build_headers_file();
+        let rules: Vec<&str> = headers.split("\n\n").collect();
+        let cache_control = |prefix: &str| {
+            rules
+                .iter()
+                .find(|rule| rule.starts_with(prefix))
+                .and_then(|rule| rule.lines().find(|line| line.contains("Cache-Control")))
+                .expect("rule should set Cache-Control")
+        };
+        assert!(cache_control("/css/*").contains("immutable"));
+        assert!(cache_control("/image/*").contains("immutable"));
+        assert!(!cache_control("/icon/*").contains("immutable"));
+    }
+
+    #[test]
+    fn minified_stylesheets_are_fingerprinted_by_the_bytes_written() {
+        let options = ExportOptions {
+            minify: true,
+            ..ExportOptions::default()
+        };
+        let assets =
+            AssetManifest::embedded_with(|url, bytes| embedded_output(url, bytes, &options));
+        let site_css = EMBEDDED_ASSETS
+            .iter()
+            .find(|asset| asset.url == "/css/site.css")
+            .expect("site.css is embedded");
+        let minified = minify_css(&String::from_utf8_lossy(site_css.bytes));
+
+        assert_ne!(minified.as_bytes(), site_css.bytes);
+        assert_eq!(
+            assets.url("/css/site.css"),
+            fingerprint("/css/site.css", minified.as_bytes())
+        );
+        assert_eq!(
+            AssetManifest::embedded().url("/js/nav.js"),
+            assets.url("/js/nav.js")
+        );
+    }
 
-        fs::remove_dir_all(out_dir).expect("failed to remove temp export dir");
+    #[test]
+    fn export_target_parse_accepts_known_values() {
+        assert_eq!(ExportTarget::parse("nginx"), Some(ExportTarget::Nginx));
+        assert_eq!(
+            ExportTarget::parse(" Cloudflare "),
+            Some(ExportTarget::CloudflarePages)
+        );
+        assert_eq!(ExportTarget::parse("netlify"), None);
+    }
+
+    #[test]
+    fn render_parallel_keeps_input_order_and_stops_on_errors() {
+        let items: Vec<u64> = (0..100).collect();
+        let doubled = render_parallel(&items, |n| Ok(n * 2)).expect("rendering failed");
+        assert_eq!(doubled, items.iter().map(|n| n * 2).collect::<Vec<_>>());
+
+        let err = render_parallel(&items, |n| {
+            if *n == 42 {
+                bail!("broken page {n}");
+            }
+            Ok(*n)
+        })
+        .expect_err("should fail");
+        assert_eq!(err.to_string(), "broken page 42");
     }
 }
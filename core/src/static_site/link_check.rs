@@ -0,0 +1,523 @@
+use anyhow::{Context, Result};
+use html5ever::tendril::StrTendril;
+use html5ever::tokenizer::{
+    BufferQueue, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer, TokenizerOpts,
+};
+use percent_encoding::percent_decode_str;
+use std::{
+    cell::RefCell,
+    collections::{BTreeSet, HashMap, VecDeque},
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+/// Pages a visitor can land on without following a link.
+const ENTRY_PAGES: [&str; 2] = ["/index.html", "/404.html"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkKind {
+    /// `<a href>` and `<option data-href>`: navigation to another page.
+    Page,
+    /// `src`, `srcset` and `<link href>`: files the page loads.
+    Asset,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    pub kind: LinkKind,
+    pub url: String,
+    pub line: u64,
+}
+
+struct LinkCollector {
+    links: RefCell<Vec<Link>>,
+}
+
+impl TokenSink for LinkCollector {
+    type Handle = ();
+
+    fn process_token(&self, token: Token, line_number: u64) -> TokenSinkResult<()> {
+        let Token::TagToken(tag) = token else {
+            return TokenSinkResult::Continue;
+        };
+        if tag.kind != TagKind::StartTag {
+            return TokenSinkResult::Continue;
+        }
+        let mut links = self.links.borrow_mut();
+        for attr in &tag.attrs {
+            let Some(kind) = link_kind(&tag.name, &attr.name.local) else {
+                continue;
+            };
+            let urls: Vec<&str> = if &*attr.name.local == "srcset" {
+                attr.value
+                    .split(',')
+                    .filter_map(|candidate| candidate.split_whitespace().next())
+                    .collect()
+            } else {
+                vec![attr.value.trim()]
+            };
+            links.extend(urls.into_iter().map(|url| Link {
+                kind,
+                url: url.to_string(),
+                line: line_number,
+            }));
+        }
+        TokenSinkResult::Continue
+    }
+}
+
+fn link_kind(tag: &str, attr: &str) -> Option<LinkKind> {
+    match (tag, attr) {
+        ("a" | "area", "href") => Some(LinkKind::Page),
+        // The period filter navigates to the selected option with `nav.js`.
+        ("option", "data-href") => Some(LinkKind::Page),
+        ("link", "href") | ("img" | "source", "srcset") => Some(LinkKind::Asset),
+        ("img" | "script" | "source" | "iframe" | "video" | "audio", "src") => {
+            Some(LinkKind::Asset)
+        }
+        _ => None,
+    }
+}
+
+/// `href` / `src` / `srcset` values of `html` with the line they appear on.
+pub fn extract_links(html: &str) -> Vec<Link> {
+    let input = BufferQueue::default();
+    input.push_back(StrTendril::from_slice(html));
+    let tokenizer = Tokenizer::new(
+        LinkCollector {
+            links: RefCell::new(Vec::new()),
+        },
+        TokenizerOpts::default(),
+    );
+    let _ = tokenizer.feed(&input);
+    tokenizer.end();
+    tokenizer.sink.links.take()
+}
+
+/// Site path of a local `url` found on the page at `page_url`, without query and fragment.
+/// `None` for external URLs, `mailto:` and the like.
+pub fn resolve_link(page_url: &str, url: &str) -> Option<String> {
+    if url.is_empty() || url.starts_with("//") {
+        return None;
+    }
+    let has_scheme = url
+        .split_once(':')
+        .is_some_and(|(scheme, _)| !scheme.contains(['/', '?', '#']));
+    if has_scheme {
+        return None;
+    }
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let joined = if path.is_empty() {
+        page_url.to_string()
+    } else if path.starts_with('/') {
+        path.to_string()
+    } else {
+        let dir = &page_url[..=page_url.rfind('/').unwrap_or(0)];
+        format!("{dir}{path}")
+    };
+
+    let parts: Vec<&str> = joined.split('/').skip(1).collect();
+    let mut segments: Vec<&str> = Vec::new();
+    for part in &parts {
+        match *part {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            part => segments.push(part),
+        }
+    }
+    // `./` and `../` point at a directory, so keep the trailing slash.
+    if matches!(parts.last(), Some(&"." | &"..")) {
+        segments.push("");
+    }
+    let normalized = format!("/{}", segments.join("/"));
+    Some(
+        percent_decode_str(&normalized)
+            .decode_utf8_lossy()
+            .into_owned(),
+    )
+}
+
+/// One `from to status` line of a Cloudflare Pages `_redirects` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redirect {
+    pub from: String,
+    pub to: String,
+}
+
+pub fn parse_redirects(text: &str) -> Vec<Redirect> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            Some(Redirect {
+                from: parts.next()?.to_string(),
+                to: parts.next()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+impl Redirect {
+    /// Target of `path` when it matches `from`, filling in `:placeholders` and the `*` splat.
+    pub fn apply(&self, path: &str) -> Option<String> {
+        let pattern: Vec<&str> = self.from.split('/').collect();
+        let segments: Vec<&str> = path.split('/').collect();
+        let mut captures: Vec<(String, String)> = Vec::new();
+        for (index, part) in pattern.iter().enumerate() {
+            if *part == "*" {
+                captures.push((":splat".to_string(), segments.get(index..)?.join("/")));
+                return Some(substitute(&self.to, &captures));
+            }
+            let segment = segments.get(index)?;
+            if let Some(name) = part.strip_prefix(':') {
+                if segment.is_empty() {
+                    return None;
+                }
+                captures.push((format!(":{name}"), segment.to_string()));
+            } else if part != segment {
+                return None;
+            }
+        }
+        (pattern.len() == segments.len()).then(|| substitute(&self.to, &captures))
+    }
+}
+
+fn substitute(to: &str, captures: &[(String, String)]) -> String {
+    to.split('/')
+        .map(|part| {
+            captures
+                .iter()
+                .find(|(name, _)| name == part)
+                .map_or(part, |(_, value)| value.as_str())
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkIssue {
+    /// An `<a href>` to a page that does not exist.
+    Dangling {
+        file: String,
+        line: u64,
+        url: String,
+    },
+    MissingAsset {
+        file: String,
+        line: u64,
+        url: String,
+    },
+    /// A page that cannot be reached by following links from the top or the 404 page.
+    Orphan { file: String },
+}
+
+impl fmt::Display for LinkIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinkIssue::Dangling { file, line, url } => {
+                write!(f, "{file}:{line}: dangling link {url}")
+            }
+            LinkIssue::MissingAsset { file, line, url } => {
+                write!(f, "{file}:{line}: missing asset {url}")
+            }
+            LinkIssue::Orphan { file } => write!(f, "{file}: orphan page"),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct LinkReport {
+    pub pages: usize,
+    pub links: usize,
+    pub issues: Vec<LinkIssue>,
+}
+
+impl LinkReport {
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Dangling links and missing assets. Orphan pages are reachable by URL, so they do not count.
+    pub fn broken_count(&self) -> usize {
+        self.issues
+            .iter()
+            .filter(|issue| !matches!(issue, LinkIssue::Orphan { .. }))
+            .count()
+    }
+}
+
+impl fmt::Display for LinkReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for issue in &self.issues {
+            writeln!(f, "  - {issue}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Files of an exported site keyed by the path they are served at, plus its `_redirects`.
+struct SiteTree {
+    files: BTreeSet<String>,
+    redirects: Vec<Redirect>,
+}
+
+impl SiteTree {
+    fn load(out_dir: &Path) -> Result<Self> {
+        let files = WalkDir::new(out_dir)
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_file())
+            .filter_map(|entry| {
+                let relative = entry.path().strip_prefix(out_dir).ok()?;
+                Some(site_path(relative))
+            })
+            .collect();
+        let redirects_path = out_dir.join("_redirects");
+        let redirects = if redirects_path.is_file() {
+            let text = fs::read_to_string(&redirects_path)
+                .with_context(|| format!("failed to read {redirects_path:?}"))?;
+            parse_redirects(&text)
+        } else {
+            Vec::new()
+        };
+        Ok(SiteTree { files, redirects })
+    }
+
+    /// The file served at `path`, following at most one redirect.
+    fn resolve(&self, path: &str) -> Option<String> {
+        self.file_at(path).or_else(|| {
+            self.redirects
+                .iter()
+                .find_map(|redirect| redirect.apply(path))
+                .and_then(|target| self.file_at(&target))
+        })
+    }
+
+    fn file_at(&self, path: &str) -> Option<String> {
+        let file = if path.ends_with('/') {
+            format!("{path}index.html")
+        } else {
+            path.to_string()
+        };
+        self.files.contains(&file).then_some(file)
+    }
+}
+
+fn site_path(relative: &Path) -> String {
+    let parts: Vec<String> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    format!("/{}", parts.join("/"))
+}
+
+fn is_html(path: &str) -> bool {
+    path.ends_with(".html")
+}
+
+/// Crawls every HTML file under `out_dir` and reports links and assets that do not resolve
+/// against the output tree and `_redirects`, and pages unreachable from the top page.
+pub fn check_site(out_dir: impl AsRef<Path>) -> Result<LinkReport> {
+    let out_dir = out_dir.as_ref();
+    let tree = SiteTree::load(out_dir)?;
+    let mut report = LinkReport::default();
+    let mut linked_pages: Vec<(String, Vec<String>)> = Vec::new();
+
+    for page in tree.files.iter().filter(|file| is_html(file)) {
+        let source = out_dir.join(PathBuf::from(page.trim_start_matches('/')));
+        let html =
+            fs::read_to_string(&source).with_context(|| format!("failed to read {source:?}"))?;
+        let page_url = page.strip_suffix("index.html").unwrap_or(page);
+        let file = page.trim_start_matches('/').to_string();
+        let mut targets = Vec::new();
+        for link in extract_links(&html) {
+            let Some(path) = resolve_link(page_url, &link.url) else {
+                continue;
+            };
+            report.links += 1;
+            match tree.resolve(&path) {
+                Some(target) => targets.push(target),
+                None => report.issues.push(match link.kind {
+                    LinkKind::Page => LinkIssue::Dangling {
+                        file: file.clone(),
+                        line: link.line,
+                        url: link.url,
+                    },
+                    LinkKind::Asset => LinkIssue::MissingAsset {
+                        file: file.clone(),
+                        line: link.line,
+                        url: link.url,
+                    },
+                }),
+            }
+        }
+        report.pages += 1;
+        linked_pages.push((page.clone(), targets));
+    }
+
+    let targets_by_page: HashMap<&str, &[String]> = linked_pages
+        .iter()
+        .map(|(page, targets)| (page.as_str(), targets.as_slice()))
+        .collect();
+    let mut reached: BTreeSet<&str> = BTreeSet::new();
+    let mut queue: VecDeque<&str> = ENTRY_PAGES
+        .into_iter()
+        .filter(|page| tree.files.contains(*page))
+        .collect();
+    while let Some(page) = queue.pop_front() {
+        if !reached.insert(page) {
+            continue;
+        }
+        let targets = targets_by_page.get(page).copied().unwrap_or_default();
+        queue.extend(
+            targets
+                .iter()
+                .map(String::as_str)
+                .filter(|target| is_html(target)),
+        );
+    }
+    report.issues.extend(
+        linked_pages
+            .iter()
+            .filter(|(page, _)| !reached.contains(page.as_str()))
+            .map(|(page, _)| LinkIssue::Orphan {
+                file: page.trim_start_matches('/').to_string(),
+            }),
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        LinkIssue, LinkKind, Redirect, check_site, extract_links, parse_redirects, resolve_link,
+    };
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn create_temp_dir() -> PathBuf {
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time before UNIX_EPOCH")
+            .as_nanos();
+        let dir =
+            std::env::temp_dir().join(format!("link_check_test_{}_{}", std::process::id(), ts));
+        fs::create_dir_all(&dir).expect("failed to create temp dir");
+        dir
+    }
+
+    fn write(dir: &Path, relative: &str, contents: &str) {
+        let path = dir.join(relative);
+        fs::create_dir_all(path.parent().expect("file has a parent"))
+            .expect("failed to create dir");
+        fs::write(path, contents).expect("failed to write file");
+    }
+
+    #[test]
+    fn extract_links_reads_href_src_and_srcset_with_lines() {
+        let html = "<a href=\"/posts/a/\">a</a>\n<img src=\"/image/a.png\"\n srcset=\"/v/a.480w.webp 480w, /image/a.png 1200w\">\n<link rel=\"stylesheet\" href=\"/css/site.css\">";
+        let links = extract_links(html);
+        let links: Vec<(LinkKind, &str, u64)> = links
+            .iter()
+            .map(|link| (link.kind, link.url.as_str(), link.line))
+            .collect();
+        assert_eq!(links.len(), 5);
+        assert_eq!(links[0], (LinkKind::Page, "/posts/a/", 1));
+        assert_eq!(links[1].1, "/image/a.png");
+        assert_eq!(links[2].1, "/v/a.480w.webp");
+        assert_eq!(links[3].1, "/image/a.png");
+        assert_eq!(links[4], (LinkKind::Asset, "/css/site.css", 4));
+    }
+
+    #[test]
+    fn resolve_link_handles_relative_paths_and_skips_external_urls() {
+        assert_eq!(
+            resolve_link("/posts/a/", "cover.png"),
+            Some("/posts/a/cover.png".to_string())
+        );
+        assert_eq!(
+            resolve_link("/posts/a/", "../b/"),
+            Some("/posts/b/".to_string())
+        );
+        assert_eq!(
+            resolve_link("/tag/x/", "?sort_key=updated_at"),
+            Some("/tag/x/".to_string())
+        );
+        assert_eq!(
+            resolve_link("/", "/tag/%E6%97%A5%E8%A8%98/#top"),
+            Some("/tag/日記/".to_string())
+        );
+        assert_eq!(resolve_link("/", "https://example.com/"), None);
+        assert_eq!(resolve_link("/", "//cdn.example.com/a.js"), None);
+        assert_eq!(resolve_link("/", "mailto:me@example.com"), None);
+    }
+
+    #[test]
+    fn redirects_fill_placeholders_and_splats() {
+        let redirects =
+            parse_redirects("/posts/:slug /posts/:slug/ 308\n# comment\n/old/* /new/:splat 301\n");
+        assert_eq!(
+            redirects[0],
+            Redirect {
+                from: "/posts/:slug".to_string(),
+                to: "/posts/:slug/".to_string(),
+            }
+        );
+        assert_eq!(
+            redirects[0].apply("/posts/hello"),
+            Some("/posts/hello/".to_string())
+        );
+        assert_eq!(redirects[0].apply("/posts/hello/"), None);
+        assert_eq!(redirects[0].apply("/posts"), None);
+        assert_eq!(
+            redirects[1].apply("/old/a/b.png"),
+            Some("/new/a/b.png".to_string())
+        );
+    }
+
+    #[test]
+    fn check_site_reports_dangling_links_missing_assets_and_orphans() {
+        let dir = create_temp_dir();
+        write(
+            &dir,
+            "index.html",
+            "<a href=\"/posts/a\">a</a>\n<a href=\"/posts/gone/\">gone</a>\n<img src=\"/image/missing.png\">",
+        );
+        write(
+            &dir,
+            "posts/a/index.html",
+            "<img src=\"cover.png\"><a href=\"/\">top</a>",
+        );
+        write(&dir, "posts/a/cover.png", "");
+        write(&dir, "posts/lonely/index.html", "<a href=\"/\">top</a>");
+        write(&dir, "_redirects", "/posts/:slug /posts/:slug/ 308\n");
+
+        let report = check_site(&dir).expect("failed to check site");
+        assert_eq!(report.pages, 3);
+        assert_eq!(report.broken_count(), 2);
+        assert_eq!(
+            report.issues,
+            vec![
+                LinkIssue::Dangling {
+                    file: "index.html".to_string(),
+                    line: 2,
+                    url: "/posts/gone/".to_string(),
+                },
+                LinkIssue::MissingAsset {
+                    file: "index.html".to_string(),
+                    line: 3,
+                    url: "/image/missing.png".to_string(),
+                },
+                LinkIssue::Orphan {
+                    file: "posts/lonely/index.html".to_string(),
+                },
+            ]
+        );
+        let _ = fs::remove_dir_all(dir);
+    }
+}
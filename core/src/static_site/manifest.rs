@@ -0,0 +1,375 @@
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::precompress::{ENCODINGS, is_compressible, sibling_paths};
+
+/// Kept next to the output directory so the next export can tell what it
+/// wrote without the manifest itself being deployed.
+pub const MANIFEST_FILE: &str = ".export-manifest.json";
+
+/// `dist` is tracked by `.dist.export-manifest.json` in the same parent directory.
+pub fn manifest_path(out_dir: &Path) -> PathBuf {
+    let out_dir = std::path::absolute(out_dir).unwrap_or_else(|_| out_dir.to_path_buf());
+    match out_dir.file_name() {
+        Some(name) => out_dir.with_file_name(format!(".{}{MANIFEST_FILE}", name.to_string_lossy())),
+        None => out_dir.join(MANIFEST_FILE),
+    }
+}
+
+/// Hex encoded SHA-256 of `parts`, each terminated by a NUL byte.
+pub fn hash_parts<'a>(parts: impl IntoIterator<Item = &'a [u8]>) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part);
+        hasher.update([0]);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+fn hash_bytes(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Hash of the file as written.
+    pub hash: String,
+    /// Hash of what the file was rendered from, for pages that can skip rendering.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inputs: Option<String>,
+}
+
+/// Relative output path (with `/` separators) to the entry written for it.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportManifest {
+    #[serde(default)]
+    pub files: BTreeMap<String, ManifestEntry>,
+}
+
+impl ExportManifest {
+    /// `None` when there is no manifest yet or it can't be read.
+    pub fn load(out_dir: &Path) -> Option<Self> {
+        let contents = fs::read(manifest_path(out_dir)).ok()?;
+        serde_json::from_slice(&contents).ok()
+    }
+
+    pub fn save(&self, out_dir: &Path) -> Result<()> {
+        let path = manifest_path(out_dir);
+        fs::write(&path, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("failed to write {:?}", path))
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ExportSummary {
+    pub written: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+}
+
+impl fmt::Display for ExportSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "written {}, unchanged {}, removed {}",
+            self.written, self.unchanged, self.removed
+        )
+    }
+}
+
+/// Writes export files, leaving the ones whose contents are unchanged untouched.
+/// With `precompress`, text files also get `.gz` / `.br` siblings, recorded with
+/// the hash of the file they were compressed from as their inputs.
+pub struct OutputTree {
+    out_dir: PathBuf,
+    precompress: bool,
+    previous: ExportManifest,
+    current: ExportManifest,
+    summary: ExportSummary,
+}
+
+impl OutputTree {
+    /// Without a manifest from an earlier export the directory is cleared, as
+    /// there is no telling which of its files are stale.
+    pub fn open(out_dir: &Path, precompress: bool) -> Result<Self> {
+        let previous = match ExportManifest::load(out_dir) {
+            Some(manifest) => manifest,
+            None => {
+                if out_dir.exists() {
+                    fs::remove_dir_all(out_dir)
+                        .with_context(|| format!("failed to clear output dir {:?}", out_dir))?;
+                }
+                ExportManifest::default()
+            }
+        };
+        fs::create_dir_all(out_dir)?;
+        Ok(Self {
+            out_dir: out_dir.to_path_buf(),
+            precompress,
+            previous,
+            current: ExportManifest::default(),
+            summary: ExportSummary::default(),
+        })
+    }
+
+    /// Paths written (or kept) by this export so far.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.current.files.keys().map(String::as_str)
+    }
+
+    /// Whether the last export produced `path` (and its compressed siblings)
+    /// from the same `inputs` and the files on disk still match, so that
+    /// rendering it again can be skipped.
+    pub fn is_fresh(&self, path: &str, inputs: &str) -> bool {
+        self.previous.files.get(path).is_some_and(|entry| {
+            self.is_fresh_entry(path, inputs)
+                && (!self.has_siblings(path)
+                    || sibling_paths(path)
+                        .all(|sibling| self.is_fresh_entry(&sibling, &entry.hash)))
+        })
+    }
+
+    fn is_fresh_entry(&self, path: &str, inputs: &str) -> bool {
+        self.previous.files.get(path).is_some_and(|entry| {
+            entry.inputs.as_deref() == Some(inputs) && self.matches_disk(path, &entry.hash)
+        })
+    }
+
+    /// Carries the entry of a fresh file and its siblings over from the last export.
+    pub fn keep(&mut self, path: &str) {
+        self.keep_entry(path);
+        if self.has_siblings(path) {
+            for sibling in sibling_paths(path) {
+                self.keep_entry(&sibling);
+            }
+        }
+    }
+
+    fn has_siblings(&self, path: &str) -> bool {
+        self.precompress && is_compressible(path)
+    }
+
+    fn keep_entry(&mut self, path: &str) {
+        if let Some(entry) = self.previous.files.get(path) {
+            self.current.files.insert(path.to_string(), entry.clone());
+            self.summary.unchanged += 1;
+        }
+    }
+
+    pub fn write(&mut self, path: &str, contents: &[u8], inputs: Option<&str>) -> Result<()> {
+        let hash = hash_bytes(contents);
+        if self.matches_disk(path, &hash) {
+            self.summary.unchanged += 1;
+        } else {
+            let target = self.out_dir.join(path);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&target, contents)
+                .with_context(|| format!("failed to write {:?}", target))?;
+            self.summary.written += 1;
+        }
+        if self.has_siblings(path) {
+            self.write_siblings(path, contents, &hash)?;
+        }
+        self.current.files.insert(
+            path.to_string(),
+            ManifestEntry {
+                hash,
+                inputs: inputs.map(str::to_string),
+            },
+        );
+        Ok(())
+    }
+
+    /// Compresses `contents` again only when it changed since the last export.
+    fn write_siblings(&mut self, path: &str, contents: &[u8], hash: &str) -> Result<()> {
+        for encoding in &ENCODINGS {
+            let sibling = format!("{path}.{}", encoding.extension);
+            if self.is_fresh_entry(&sibling, hash) {
+                self.keep_entry(&sibling);
+            } else {
+                let compressed = (encoding.compress)(contents)
+                    .with_context(|| format!("failed to compress {path}"))?;
+                self.write(&sibling, &compressed, Some(hash))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn copy(&mut self, source: &Path, path: &str) -> Result<()> {
+        let contents = fs::read(source).with_context(|| format!("failed to read {:?}", source))?;
+        self.write(path, &contents, None)
+    }
+
+    /// Deletes files the previous export wrote and this one did not, then saves
+    /// the manifest.
+    pub fn finish(mut self) -> Result<ExportSummary> {
+        let stale: Vec<String> = self
+            .previous
+            .files
+            .keys()
+            .filter(|path| !self.current.files.contains_key(*path))
+            .cloned()
+            .collect();
+        for path in stale {
+            let target = self.out_dir.join(&path);
+            if target.exists() {
+                fs::remove_file(&target)
+                    .with_context(|| format!("failed to remove {:?}", target))?;
+                self.summary.removed += 1;
+            }
+            self.remove_empty_parents(&target);
+        }
+        self.current.save(&self.out_dir)?;
+        Ok(self.summary)
+    }
+
+    fn matches_disk(&self, path: &str, hash: &str) -> bool {
+        fs::read(self.out_dir.join(path)).is_ok_and(|contents| hash_bytes(&contents) == hash)
+    }
+
+    fn remove_empty_parents(&self, file: &Path) {
+        let mut dir = file.parent();
+        while let Some(current) = dir {
+            if current == self.out_dir || fs::remove_dir(current).is_err() {
+                break;
+            }
+            dir = current.parent();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExportSummary, OutputTree, manifest_path};
+    use std::{
+        fs,
+        path::PathBuf,
+        time::{SystemTime, UNIX_EPOCH},
+    };
+
+    fn temp_out_dir() -> PathBuf {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time before unix epoch")
+            .as_nanos();
+        std::env::temp_dir().join(format!("rust-blog-export-manifest-{unique}"))
+    }
+
+    #[test]
+    fn first_export_clears_files_left_without_a_manifest() {
+        let out_dir = temp_out_dir();
+        fs::create_dir_all(out_dir.join("old")).expect("failed to create old dir");
+        fs::write(out_dir.join("old/index.html"), "old").expect("failed to write old page");
+
+        let mut output = OutputTree::open(&out_dir, true).expect("failed to open output");
+        output
+            .write("index.html", b"home", Some("v1"))
+            .expect("failed to write page");
+        let summary = output.finish().expect("failed to finish");
+
+        assert_eq!(
+            summary,
+            ExportSummary {
+                written: 3,
+                unchanged: 0,
+                removed: 0
+            }
+        );
+        assert!(out_dir.join("index.html.gz").exists());
+        assert!(out_dir.join("index.html.br").exists());
+        assert!(!out_dir.join("old").exists());
+        assert!(manifest_path(&out_dir).exists());
+        assert_eq!(manifest_path(&out_dir).parent(), out_dir.parent());
+        fs::remove_dir_all(&out_dir).expect("failed to remove temp output dir");
+        fs::remove_file(manifest_path(&out_dir)).expect("failed to remove manifest");
+    }
+
+    #[test]
+    fn later_exports_skip_fresh_pages_and_remove_stale_ones() {
+        let out_dir = temp_out_dir();
+        let mut output = OutputTree::open(&out_dir, true).expect("failed to open output");
+        output
+            .write("index.html", b"home", Some("v1"))
+            .expect("failed to write home");
+        output
+            .write("posts/gone/index.html", b"gone", Some("v1"))
+            .expect("failed to write post");
+        output
+            .write("css/site.css", b"css", None)
+            .expect("failed to write css");
+        output.finish().expect("failed to finish");
+
+        let mut output = OutputTree::open(&out_dir, true).expect("failed to reopen output");
+        assert!(output.is_fresh("index.html", "v1"));
+        assert!(!output.is_fresh("css/site.css", "v1"));
+        output.keep("index.html");
+        output
+            .write("css/site.css", b"css", None)
+            .expect("failed to write css");
+        output
+            .write("posts/new/index.html", b"new", Some("v1"))
+            .expect("failed to write new post");
+        let summary = output.finish().expect("failed to finish");
+
+        assert_eq!(
+            summary,
+            ExportSummary {
+                written: 3,
+                unchanged: 6,
+                removed: 3
+            }
+        );
+        assert!(!out_dir.join("posts/gone").exists());
+        assert!(out_dir.join("posts/new/index.html").exists());
+
+        let output = OutputTree::open(&out_dir, true).expect("failed to reopen output");
+        fs::write(out_dir.join("posts/new/index.html.br"), "edited").expect("failed to edit page");
+        assert!(!output.is_fresh("posts/new/index.html", "v1"));
+        fs::write(out_dir.join("index.html"), "edited").expect("failed to edit page");
+        assert!(!output.is_fresh("index.html", "v1"));
+        assert!(!output.is_fresh("posts/new/index.html", "v2"));
+        fs::remove_dir_all(&out_dir).expect("failed to remove temp output dir");
+        fs::remove_file(manifest_path(&out_dir)).expect("failed to remove manifest");
+    }
+
+    #[test]
+    fn exports_without_precompress_write_no_siblings_and_drop_old_ones() {
+        let out_dir = temp_out_dir();
+        let mut output = OutputTree::open(&out_dir, true).expect("failed to open output");
+        output
+            .write("index.html", b"home", Some("v1"))
+            .expect("failed to write home");
+        output.finish().expect("failed to finish");
+
+        let mut output = OutputTree::open(&out_dir, false).expect("failed to reopen output");
+        assert!(output.is_fresh("index.html", "v1"));
+        output.keep("index.html");
+        output
+            .write("css/site.css", b"css", None)
+            .expect("failed to write css");
+        let summary = output.finish().expect("failed to finish");
+
+        assert_eq!(
+            summary,
+            ExportSummary {
+                written: 1,
+                unchanged: 1,
+                removed: 2
+            }
+        );
+        assert!(out_dir.join("index.html").exists());
+        assert!(!out_dir.join("index.html.gz").exists());
+        assert!(!out_dir.join("css/site.css.br").exists());
+        fs::remove_dir_all(&out_dir).expect("failed to remove temp output dir");
+        fs::remove_file(manifest_path(&out_dir)).expect("failed to remove manifest");
+    }
+}
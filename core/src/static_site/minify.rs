@@ -0,0 +1,187 @@
+//! Conservative whitespace and comment removal for the exported HTML and CSS.
+//!
+//! Nothing is reordered or rewritten, so the output renders exactly like the
+//! input as long as only `pre`, `textarea`, `script` and `style` depend on
+//! their whitespace, which holds for the bundled stylesheets.
+
+/// Elements whose contents are copied untouched.
+const RAW_ELEMENTS: [&str; 4] = ["pre", "textarea", "script", "style"];
+
+/// Collapses runs of whitespace between tags and drops comments.
+pub fn minify_html(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while !rest.is_empty() {
+        let Some(start) = rest.find('<') else {
+            push_collapsed(&mut out, rest);
+            break;
+        };
+        push_collapsed(&mut out, &rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            let end = comment.find("-->").map_or(rest.len(), |end| end + 7);
+            // Conditional comments still mean something to old browsers.
+            if comment.starts_with("[if") {
+                out.push_str(&rest[..end]);
+            }
+            rest = &rest[end..];
+            continue;
+        }
+
+        let end = tag_end(rest);
+        let tag = &rest[..end];
+        out.push_str(tag);
+        rest = &rest[end..];
+        if let Some(name) = raw_element(tag) {
+            let close = find_ignore_ascii_case(rest, &format!("</{name}")).unwrap_or(rest.len());
+            out.push_str(&rest[..close]);
+            rest = &rest[close..];
+        }
+    }
+    out
+}
+
+/// Whitespace runs keep one character, a newline if they had one. Runs on
+/// either side of a dropped comment count as one.
+fn push_collapsed(out: &mut String, text: &str) {
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if !c.is_ascii_whitespace() {
+            out.push(c);
+            continue;
+        }
+        let mut newline = c == '\n';
+        while let Some(next) = chars.next_if(char::is_ascii_whitespace) {
+            newline |= next == '\n';
+        }
+        if !out.ends_with(|c: char| c.is_ascii_whitespace()) {
+            out.push(if newline { '\n' } else { ' ' });
+        }
+    }
+}
+
+/// Byte offset just past the `>` closing the tag at the start of `html`,
+/// skipping `>` inside quoted attribute values.
+fn tag_end(html: &str) -> usize {
+    let mut quote = None;
+    for (index, c) in html.char_indices().skip(1) {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return index + 1,
+            (None, _) => {}
+        }
+    }
+    html.len()
+}
+
+fn raw_element(tag: &str) -> Option<&'static str> {
+    let name: String = tag[1..]
+        .chars()
+        .take_while(char::is_ascii_alphanumeric)
+        .collect();
+    RAW_ELEMENTS
+        .into_iter()
+        .find(|raw| raw.eq_ignore_ascii_case(&name))
+}
+
+fn find_ignore_ascii_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+/// Drops comments (except `/*! ... */` license headers) and the whitespace
+/// that carries no meaning around `{`, `}`, `;`, `,` and `>`.
+pub fn minify_css(css: &str) -> String {
+    let mut out = String::with_capacity(css.len());
+    let mut chars = css.chars().peekable();
+    let mut pending_space = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let keep = chars.peek() == Some(&'!');
+                let mut comment = String::from("/*");
+                let mut previous = '\0';
+                for c in chars.by_ref() {
+                    comment.push(c);
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+                if keep {
+                    out.push_str(&comment);
+                } else {
+                    pending_space = true;
+                }
+            }
+            c if c.is_ascii_whitespace() => pending_space = true,
+            _ => {
+                if pending_space
+                    && !out.is_empty()
+                    && !out.ends_with(['{', '}', ';', ',', '>', ':', '('])
+                    && !matches!(c, '{' | '}' | ';' | ',' | '>' | ')')
+                {
+                    out.push(' ');
+                }
+                pending_space = false;
+                if c == '}' && out.ends_with(';') {
+                    out.pop();
+                }
+                out.push(c);
+                if c == '"' || c == '\'' {
+                    let mut escaped = false;
+                    for s in chars.by_ref() {
+                        out.push(s);
+                        if escaped {
+                            escaped = false;
+                        } else if s == '\\' {
+                            escaped = true;
+                        } else if s == c {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{minify_css, minify_html};
+
+    #[test]
+    fn minify_html_collapses_whitespace_outside_raw_elements() {
+        let html = "<!doctype html>\n<html>\n  <head>\n    <title>A  title</title>\n  </head>\n  <!-- layout -->\n  <body>\n    <a href=\"/x\" title=\"a > b\">x</a>  <a href=\"/y\">y</a>\n    <pre><code>fn main() {\n    run();\n}</code></pre>\n    <SCRIPT>if (a  <  b) {}</SCRIPT>\n  </body>\n</html>\n";
+        assert_eq!(
+            minify_html(html),
+            "<!doctype html>\n<html>\n<head>\n<title>A title</title>\n</head>\n<body>\n<a href=\"/x\" title=\"a > b\">x</a> <a href=\"/y\">y</a>\n<pre><code>fn main() {\n    run();\n}</code></pre>\n<SCRIPT>if (a  <  b) {}</SCRIPT>\n</body>\n</html>\n"
+        );
+    }
+
+    #[test]
+    fn minify_html_keeps_conditional_comments_and_unclosed_input() {
+        assert_eq!(
+            minify_html("<!--[if IE]><p>old</p><![endif]-->  <p>new</p>"),
+            "<!--[if IE]><p>old</p><![endif]--> <p>new</p>"
+        );
+        assert_eq!(minify_html("<p>a <!-- open"), "<p>a ");
+        assert_eq!(minify_html("<textarea>  a  "), "<textarea>  a  ");
+    }
+
+    #[test]
+    fn minify_css_keeps_meaningful_spaces_strings_and_license_comments() {
+        let css = "/*! license */\n/* note */\n.content a :hover ,\n.nav > li {\n  margin : 0 auto;\n  width: calc( 100% - 2px );\n  content: \"a ; b\";\n}\n@media screen and (max-width: 768px) {\n  .x { color: red; }\n}\n";
+        assert_eq!(
+            minify_css(css),
+            "/*! license */ .content a :hover,.nav>li{margin :0 auto;width:calc(100% - 2px);content:\"a ; b\"}@media screen and (max-width:768px){.x{color:red}}"
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use anyhow::Result;
+
+use crate::utils::compression;
+
+/// Extensions of the files that get `.gz` / `.br` siblings. The nginx config in
+/// `docker/nginx-static.conf` has a location for each of them.
+const COMPRESSIBLE_EXTENSIONS: [&str; 4] = ["html", "css", "js", "svg"];
+
+pub struct Encoding {
+    /// Appended to the original path, e.g. `index.html.gz`.
+    pub extension: &'static str,
+    pub compress: fn(&[u8]) -> Result<Vec<u8>>,
+}
+
+pub const ENCODINGS: [Encoding; 2] = [
+    Encoding {
+        extension: "gz",
+        compress: gzip,
+    },
+    Encoding {
+        extension: "br",
+        compress: brotli,
+    },
+];
+
+pub fn is_compressible(path: &str) -> bool {
+    path.rsplit_once('.').is_some_and(|(_, extension)| {
+        COMPRESSIBLE_EXTENSIONS
+            .iter()
+            .any(|compressible| compressible.eq_ignore_ascii_case(extension))
+    })
+}
+
+/// Paths of the compressed siblings of `path`.
+pub fn sibling_paths(path: &str) -> impl Iterator<Item = String> + '_ {
+    ENCODINGS
+        .iter()
+        .map(move |encoding| format!("{path}.{}", encoding.extension))
+}
+
+/// Exported files are compressed once, so they get the smallest output.
+fn gzip(bytes: &[u8]) -> Result<Vec<u8>> {
+    Ok(compression::gzip(bytes, 9)?)
+}
+
+fn brotli(bytes: &[u8]) -> Result<Vec<u8>> {
+    Ok(compression::brotli(bytes, 11)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ENCODINGS, is_compressible, sibling_paths};
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn only_text_assets_are_compressible() {
+        assert!(is_compressible("index.html"));
+        assert!(is_compressible("css/site.0123456789abcdef.css"));
+        assert!(is_compressible("image/logo.SVG"));
+        assert!(!is_compressible("image/a.png"));
+        assert!(!is_compressible("index.html.gz"));
+        assert!(!is_compressible("_headers"));
+        assert_eq!(
+            sibling_paths("index.html").collect::<Vec<_>>(),
+            ["index.html.gz", "index.html.br"]
+        );
+    }
+
+    #[test]
+    fn compressed_siblings_decode_to_the_original() {
+        let original = "<p>hello</p>\n".repeat(100);
+        let [gzip, brotli] = ENCODINGS
+            .map(|encoding| (encoding.compress)(original.as_bytes()).expect("failed to compress"));
+
+        let mut decoded = String::new();
+        GzDecoder::new(gzip.as_slice())
+            .read_to_string(&mut decoded)
+            .expect("failed to gunzip");
+        assert_eq!(decoded, original);
+
+        let mut decoded = String::new();
+        brotli::Decompressor::new(brotli.as_slice(), 4096)
+            .read_to_string(&mut decoded)
+            .expect("failed to decompress brotli");
+        assert_eq!(decoded, original);
+        assert!(brotli.len() < original.len());
+    }
+}
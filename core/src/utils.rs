@@ -1,8 +1,12 @@
 use chrono::{DateTime, FixedOffset, Utc};
 
+pub mod asset;
+pub mod bundle;
+pub mod compression;
 pub mod config;
 pub mod fixed_content_matter;
 pub mod front_matter;
+pub mod image;
 pub mod markdown;
 
 pub fn cut_out_string(base: &str, limit: usize) -> String {
@@ -21,7 +25,7 @@ pub fn utc_to_jst(utc: DateTime<Utc>) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::utils::markdown::markdown_to_html;
+    use crate::utils::markdown::{HtmlOptions, markdown_to_html};
 
     #[test]
     fn test_cut_out_string_basic() {
@@ -51,7 +55,7 @@ mod tests {
     #[test]
     fn test_markdown_to_html_basic() {
         let md = "# Title\nHello **Rust**!";
-        let html = markdown_to_html(md);
+        let html = markdown_to_html(md, &HtmlOptions::default());
 
         assert!(
             html.contains("<h1>Title</h1>"),
@@ -0,0 +1,289 @@
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+/// Name of the Tera function templates call as `asset_url(path="/css/site.css")`.
+pub const ASSET_URL_FUNCTION: &str = "asset_url";
+
+/// URL prefix of the content images, both live and in the export.
+pub const IMAGE_URL_PREFIX: &str = "/image";
+
+/// Hex characters of the content hash kept in fingerprinted file names.
+const FINGERPRINT_LENGTH: usize = 16;
+
+pub struct EmbeddedAsset {
+    pub url: &'static str,
+    pub bytes: &'static [u8],
+}
+
+/// Stylesheets and scripts compiled into the binary.
+pub const EMBEDDED_ASSETS: &[EmbeddedAsset] = &[
+    EmbeddedAsset {
+        url: "/css/bulma.min.css",
+        bytes: include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/bulma.min.css")),
+    },
+    EmbeddedAsset {
+        url: "/css/site.css",
+        bytes: include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/site.css")),
+    },
+    EmbeddedAsset {
+        url: "/js/nav.js",
+        bytes: include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/nav.js")),
+    },
+];
+
+/// Puts a hash of `bytes` in front of the extension of `url`, e.g.
+/// `/css/site.css` becomes `/css/site.0123456789abcdef.css`.
+pub fn fingerprint(url: &str, bytes: &[u8]) -> String {
+    let hash = format!("{:x}", Sha256::digest(bytes));
+    let hash = &hash[..FINGERPRINT_LENGTH];
+    let name_start = url.rfind('/').map_or(0, |index| index + 1);
+    match url[name_start..].rfind('.') {
+        Some(dot) if dot > 0 => {
+            let dot = name_start + dot;
+            format!("{}.{hash}{}", &url[..dot], &url[dot..])
+        }
+        _ => format!("{url}.{hash}"),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetSource {
+    Embedded(&'static [u8]),
+    File(PathBuf),
+}
+
+#[derive(Debug, Clone)]
+pub struct Asset {
+    /// URL pages used before fingerprinting, e.g. `/css/site.css`.
+    pub url: String,
+    pub source: AssetSource,
+}
+
+/// Fingerprinted URLs of the stylesheets, scripts and content images.
+#[derive(Debug, Clone, Default)]
+pub struct AssetManifest {
+    urls: HashMap<String, String>,
+    assets: BTreeMap<String, Asset>,
+}
+
+impl AssetManifest {
+    /// Just the assets compiled into the binary.
+    pub fn embedded() -> Self {
+        Self::embedded_with(|_, bytes| Cow::Borrowed(bytes))
+    }
+
+    /// The assets compiled into the binary, fingerprinted by the bytes
+    /// `output` turns each of them into, such as a minified stylesheet.
+    pub fn embedded_with(output: impl Fn(&str, &'static [u8]) -> Cow<'static, [u8]>) -> Self {
+        let mut manifest = Self::default();
+        for asset in EMBEDDED_ASSETS {
+            manifest.insert(
+                asset.url,
+                &output(asset.url, asset.bytes),
+                AssetSource::Embedded(asset.bytes),
+            );
+        }
+        manifest
+    }
+
+    /// The embedded assets plus every file under `image_dir`, which may not exist.
+    pub fn load(image_dir: &Path) -> io::Result<Self> {
+        Self::embedded().with_images(image_dir)
+    }
+
+    /// Adds every file under `image_dir`, which may not exist.
+    pub fn with_images(mut self, image_dir: &Path) -> io::Result<Self> {
+        if !image_dir.exists() {
+            return Ok(self);
+        }
+        for entry in WalkDir::new(image_dir) {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let relative = entry
+                .path()
+                .strip_prefix(image_dir)
+                .map_err(io::Error::other)?;
+            let url = format!(
+                "{IMAGE_URL_PREFIX}/{}",
+                relative.to_string_lossy().replace('\\', "/")
+            );
+            let bytes = fs::read(entry.path())?;
+            self.insert(&url, &bytes, AssetSource::File(entry.path().to_path_buf()));
+        }
+        Ok(self)
+    }
+
+    pub fn insert(&mut self, url: &str, bytes: &[u8], source: AssetSource) {
+        let fingerprinted = fingerprint(url, bytes);
+        self.urls.insert(url.to_string(), fingerprinted.clone());
+        self.assets.insert(
+            fingerprinted,
+            Asset {
+                url: url.to_string(),
+                source,
+            },
+        );
+    }
+
+    /// Fingerprinted URL of `url`, or `url` itself when it is not a known asset.
+    pub fn url<'a>(&'a self, url: &'a str) -> &'a str {
+        self.urls.get(url).map_or(url, String::as_str)
+    }
+
+    /// The asset served under a fingerprinted URL.
+    pub fn get(&self, fingerprinted: &str) -> Option<&Asset> {
+        self.assets.get(fingerprinted)
+    }
+
+    /// Replaces plain asset URLs that start an attribute value, a `srcset`
+    /// candidate or a CSS `url()` in `html` with their fingerprinted URLs, for
+    /// references that didn't go through `asset_url`, such as raw HTML in Markdown.
+    // Used by the static export pipeline in the library target.
+    #[allow(dead_code)]
+    pub fn rewrite_urls(&self, html: &str) -> String {
+        let mut rewritten = String::with_capacity(html.len());
+        let mut rest = html;
+        while let Some(start) = rest.find('/') {
+            rewritten.push_str(&rest[..start]);
+            let tail = &rest[start..];
+            let end = tail
+                .find(|c: char| {
+                    c.is_whitespace()
+                        || matches!(c, '"' | '\'' | '(' | ')' | '<' | '>' | ',' | '?' | '#')
+                })
+                .unwrap_or(tail.len());
+            match self.urls.get(&tail[..end]) {
+                Some(url) if starts_url_value(&rewritten) => {
+                    rewritten.push_str(url);
+                    rest = &tail[end..];
+                }
+                _ => {
+                    rewritten.push('/');
+                    rest = &tail[1..];
+                }
+            }
+        }
+        rewritten.push_str(rest);
+        rewritten
+    }
+
+    /// Fingerprinted URLs with their assets, sorted by URL.
+    // Used by the static export pipeline in the library target.
+    #[allow(dead_code)]
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Asset)> {
+        self.assets.iter().map(|(url, asset)| (url.as_str(), asset))
+    }
+}
+
+/// Whether a URL right after `before` is a whole attribute value, `url()`
+/// argument or `srcset` candidate, rather than part of a longer path or text.
+fn starts_url_value(before: &str) -> bool {
+    match before.chars().next_back() {
+        Some(c) if c.is_whitespace() => before.trim_end().ends_with(','),
+        Some(c) => matches!(c, '"' | '\'' | '(' | '='),
+        None => false,
+    }
+}
+
+impl tera::Function for AssetManifest {
+    fn call(&self, args: &HashMap<String, Value>) -> tera::Result<Value> {
+        let path = args
+            .get("path")
+            .and_then(Value::as_str)
+            .ok_or_else(|| tera::Error::msg("asset_url expects a `path` string argument"))?;
+        Ok(Value::String(self.url(path).to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ASSET_URL_FUNCTION, AssetManifest, AssetSource, fingerprint};
+    use std::{
+        fs,
+        time::{SystemTime, UNIX_EPOCH},
+    };
+
+    #[test]
+    fn fingerprint_goes_before_the_last_extension() {
+        let url = fingerprint("/css/bulma.min.css", b"body {}");
+        let hash = url
+            .strip_prefix("/css/bulma.min.")
+            .and_then(|rest| rest.strip_suffix(".css"))
+            .expect("hash should sit before .css");
+        assert_eq!(hash.len(), 16);
+        assert_ne!(
+            url,
+            fingerprint("/css/bulma.min.css", b"body { margin: 0 }")
+        );
+        assert!(fingerprint("/image/.hidden/LICENSE", b"x").starts_with("/image/.hidden/LICENSE."));
+    }
+
+    #[test]
+    fn manifest_fingerprints_embedded_assets_and_images() {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time before unix epoch")
+            .as_nanos();
+        let image_dir = std::env::temp_dir().join(format!("rust-blog-assets-{unique}"));
+        fs::create_dir_all(image_dir.join("nested")).expect("failed to create image dir");
+        fs::write(image_dir.join("nested/a.png"), b"png").expect("failed to write image");
+
+        let manifest = AssetManifest::load(&image_dir).expect("failed to load assets");
+        let image_url = manifest.url("/image/nested/a.png");
+        assert_eq!(image_url, fingerprint("/image/nested/a.png", b"png"));
+        let asset = manifest.get(image_url).expect("image should be known");
+        assert_eq!(asset.url, "/image/nested/a.png");
+        assert_eq!(
+            asset.source,
+            AssetSource::File(image_dir.join("nested/a.png"))
+        );
+        assert_ne!(manifest.url("/css/site.css"), "/css/site.css");
+        assert_eq!(manifest.url("/icon/favicon.ico"), "/icon/favicon.ico");
+
+        let mut tera = tera::Tera::default();
+        tera.register_function(ASSET_URL_FUNCTION, manifest.clone());
+        let html = tera
+            .render_str(
+                r#"{{ asset_url(path="/image/nested/a.png") | safe }}"#,
+                &tera::Context::new(),
+            )
+            .expect("failed to render");
+        assert_eq!(html, image_url);
+        fs::remove_dir_all(image_dir).expect("failed to remove image dir");
+    }
+
+    #[test]
+    fn rewrite_urls_replaces_only_whole_url_values() {
+        let mut manifest = AssetManifest::default();
+        manifest.insert("/image/a.png", b"png", AssetSource::Embedded(b"png"));
+        let image_url = manifest.url("/image/a.png").to_string();
+
+        let html = concat!(
+            r#"<img src="/image/a.png" srcset="/image/a.png 1x, /image/a.png 2x">"#,
+            r#"<div style="background: url(/image/a.png)"></div>"#,
+            r#"<a href="/image/a.png?download">x</a>"#,
+            r#"<p>/image/a.png and /static/image/a.png</p><img src="/image/a.png.bak">"#,
+        );
+        assert_eq!(
+            manifest.rewrite_urls(html),
+            format!(
+                concat!(
+                    r#"<img src="{0}" srcset="{0} 1x, {0} 2x">"#,
+                    r#"<div style="background: url({0})"></div>"#,
+                    r#"<a href="{0}?download">x</a>"#,
+                    r#"<p>/image/a.png and /static/image/a.png</p><img src="/image/a.png.bak">"#,
+                ),
+                image_url
+            )
+        );
+    }
+}
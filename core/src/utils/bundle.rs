@@ -0,0 +1,111 @@
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+
+/// File name that turns an article directory into a page bundle.
+pub const BUNDLE_INDEX: &str = "index.md";
+
+/// `content/articles/<slug>/index.md` is a bundle whose directory name is the slug.
+// Used by seed and the static export pipeline in the library target.
+#[allow(dead_code)]
+pub fn bundle_slug(markdown_path: &Path) -> Option<&str> {
+    if markdown_path.file_name()? != BUNDLE_INDEX {
+        return None;
+    }
+    markdown_path.parent()?.file_name()?.to_str()
+}
+
+pub fn bundle_dir(article_dir: &Path, slug: &str) -> PathBuf {
+    article_dir.join(slug)
+}
+
+/// URL that relative links in a bundled article resolve against.
+pub fn bundle_url(slug: &str) -> String {
+    format!("/posts/{slug}/")
+}
+
+/// Colocated files that may be served or exported: everything but Markdown and dotfiles.
+/// An empty path would name the bundle directory itself.
+pub fn is_bundle_asset(relative: &Path) -> bool {
+    if relative.as_os_str().is_empty() {
+        return false;
+    }
+    let visible = relative.components().all(|component| match component {
+        Component::Normal(name) => !name.to_string_lossy().starts_with('.'),
+        _ => false,
+    });
+    visible && relative.extension().and_then(|ext| ext.to_str()) != Some("md")
+}
+
+/// Asset paths of a bundle relative to its directory. Empty when `slug` has no bundle.
+#[allow(dead_code)]
+pub fn bundle_assets(article_dir: &Path, slug: &str) -> Vec<PathBuf> {
+    let dir = bundle_dir(article_dir, slug);
+    if !dir.join(BUNDLE_INDEX).is_file() {
+        return Vec::new();
+    }
+    WalkDir::new(&dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| entry.path().strip_prefix(&dir).ok().map(Path::to_path_buf))
+        .filter(|relative| is_bundle_asset(relative))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bundle_assets, bundle_slug, is_bundle_asset};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn create_temp_dir() -> PathBuf {
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time before UNIX_EPOCH")
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("bundle_test_{}_{}", std::process::id(), ts));
+        fs::create_dir_all(&dir).expect("failed to create temp dir");
+        dir
+    }
+
+    #[test]
+    fn bundle_slug_reads_directory_of_index_md() {
+        assert_eq!(
+            bundle_slug(Path::new("content/articles/hello/index.md")),
+            Some("hello")
+        );
+        assert_eq!(bundle_slug(Path::new("content/articles/hello.md")), None);
+    }
+
+    #[test]
+    fn is_bundle_asset_skips_markdown_and_dotfiles() {
+        assert!(is_bundle_asset(Path::new("cover.png")));
+        assert!(is_bundle_asset(Path::new("img/diagram.svg")));
+        assert!(!is_bundle_asset(Path::new("index.md")));
+        assert!(!is_bundle_asset(Path::new(".DS_Store")));
+        assert!(!is_bundle_asset(Path::new("../secret.png")));
+        assert!(!is_bundle_asset(Path::new("")));
+    }
+
+    #[test]
+    fn bundle_assets_lists_files_only_for_bundles() {
+        let dir = create_temp_dir();
+        fs::create_dir_all(dir.join("hello/img")).expect("failed to create bundle");
+        fs::write(dir.join("hello/index.md"), "").expect("failed to write index.md");
+        fs::write(dir.join("hello/cover.png"), "").expect("failed to write cover.png");
+        fs::write(dir.join("hello/img/a.jpg"), "").expect("failed to write a.jpg");
+        fs::create_dir_all(dir.join("plain")).expect("failed to create plain dir");
+        fs::write(dir.join("plain/cover.png"), "").expect("failed to write cover.png");
+
+        let mut assets = bundle_assets(&dir, "hello");
+        assets.sort();
+        assert_eq!(
+            assets,
+            vec![PathBuf::from("cover.png"), PathBuf::from("img/a.jpg")]
+        );
+        assert!(bundle_assets(&dir, "plain").is_empty());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,20 @@
+use std::io::{self, Write};
+
+use flate2::{Compression, write::GzEncoder};
+
+/// `level` runs from 0 (store) to 9 (smallest).
+pub fn gzip(bytes: &[u8], level: u32) -> io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::new(level));
+    encoder.write_all(bytes)?;
+    encoder.finish()
+}
+
+/// `quality` runs from 0 (fastest) to 11 (smallest).
+pub fn brotli(bytes: &[u8], quality: u32) -> io::Result<Vec<u8>> {
+    let mut compressed = Vec::new();
+    {
+        let mut encoder = brotli::CompressorWriter::new(&mut compressed, 4096, quality, 22);
+        encoder.write_all(bytes)?;
+    }
+    Ok(compressed)
+}
@@ -1,6 +1,6 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 #[allow(dead_code)]
 pub struct FrontMatter {
     pub title: String,
@@ -9,6 +9,8 @@ pub struct FrontMatter {
     pub deleted: bool,
     #[serde(default, alias = "date")]
     pub created_at: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
     pub excerpt: Option<String>,
     pub icatch_path: Option<String>,
     pub tags: Vec<String>,
@@ -32,6 +34,7 @@ impl FrontMatter {
             slug,
             deleted,
             created_at,
+            updated_at: None,
             excerpt,
             icatch_path,
             tags,
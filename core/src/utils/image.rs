@@ -0,0 +1,113 @@
+use crate::utils::asset::AssetManifest;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+
+/// URL prefix the generated variants are served under, both live and in the export.
+pub const VARIANT_URL_PREFIX: &str = "/variants";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageVariant {
+    pub url: String,
+    pub width: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageInfo {
+    pub width: u32,
+    pub height: u32,
+    /// Resized copies narrower than the original, smallest first.
+    pub variants: Vec<ImageVariant>,
+}
+
+impl ImageInfo {
+    /// `srcset` listing the variants and the original at `src`. `None` when there are no variants.
+    pub fn srcset(&self, src: &str) -> Option<String> {
+        if self.variants.is_empty() {
+            return None;
+        }
+        let mut candidates: Vec<String> = self
+            .variants
+            .iter()
+            .map(|variant| format!("{} {}w", variant.url, variant.width))
+            .collect();
+        candidates.push(format!("{src} {}w", self.width));
+        Some(candidates.join(", "))
+    }
+}
+
+/// Processed images keyed by the URL pages reference them with, e.g. `/image/a.png`.
+pub type ImageIndex = HashMap<String, ImageInfo>;
+
+/// Template data for an `<img>`: `src`, plus `srcset` / `width` / `height` when known.
+/// `src` is fingerprinted when it is one of `assets`.
+pub fn image_attributes(
+    src: &str,
+    images: &ImageIndex,
+    assets: &AssetManifest,
+) -> serde_json::Value {
+    let info = images.get(src);
+    let url = assets.url(src);
+    json!({
+        "src": url,
+        "srcset": info.and_then(|info| info.srcset(url)),
+        "width": info.map(|info| info.width),
+        "height": info.map(|info| info.height),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ImageIndex, ImageInfo, ImageVariant, image_attributes};
+    use crate::utils::asset::AssetManifest;
+    use serde_json::json;
+
+    fn info() -> ImageInfo {
+        ImageInfo {
+            width: 1200,
+            height: 800,
+            variants: vec![
+                ImageVariant {
+                    url: "/variants/image/a-480.webp".to_string(),
+                    width: 480,
+                },
+                ImageVariant {
+                    url: "/variants/image/a-960.webp".to_string(),
+                    width: 960,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn srcset_lists_variants_then_original() {
+        assert_eq!(
+            info().srcset("/image/a.png"),
+            Some(
+                "/variants/image/a-480.webp 480w, /variants/image/a-960.webp 960w, /image/a.png 1200w"
+                    .to_string()
+            )
+        );
+        let small = ImageInfo {
+            width: 300,
+            height: 200,
+            variants: Vec::new(),
+        };
+        assert_eq!(small.srcset("/image/s.png"), None);
+    }
+
+    #[test]
+    fn image_attributes_fills_known_dimensions_only() {
+        let images = ImageIndex::from([("/image/a.png".to_string(), info())]);
+        let assets = AssetManifest::default();
+        let known = image_attributes("/image/a.png", &images, &assets);
+        assert_eq!(known["width"], 1200);
+        assert_eq!(known["height"], 800);
+        assert!(known["srcset"].as_str().is_some());
+
+        assert_eq!(
+            image_attributes("/image/missing.png", &images, &assets),
+            json!({ "src": "/image/missing.png", "srcset": null, "width": null, "height": null })
+        );
+    }
+}
@@ -1,25 +1,152 @@
 pub mod to_text;
+use crate::utils::{asset::AssetManifest, image::ImageIndex};
 use ammonia::Builder;
-use pulldown_cmark::{Event, Options, Parser, html};
+use pulldown_cmark::{
+    Event, Options, Parser, Tag,
+    escape::{escape_href, escape_html},
+    html,
+};
 use to_text::{end_tag, is_strikethrough, start_tag};
 
-pub fn markdown_to_html(input: &str) -> String {
+/// Characters of body text an article without its own excerpt is summarized by.
+pub const EXCERPT_LENGTH: usize = 100;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HtmlOptions<'a> {
+    /// Relative image paths are resolved against this URL so that images colocated
+    /// with a page bundle work from any URL.
+    pub base_url: Option<&'a str>,
+    /// Known images get `width` / `height` and a `srcset` of their resized variants.
+    pub images: Option<&'a ImageIndex>,
+    /// Known assets are linked under their fingerprinted URL.
+    pub assets: Option<&'a AssetManifest>,
+}
+
+pub fn markdown_to_html(input: &str, html_options: &HtmlOptions<'_>) -> String {
+    let mut events = Vec::new();
+    let mut image: Option<PendingImage> = None;
+    for event in Parser::new_ext(input, html_parser_options()) {
+        let Some(pending) = image.as_mut() else {
+            match event {
+                Event::Start(Tag::Image(_, dest, title)) => {
+                    let src = html_options
+                        .base_url
+                        .and_then(|base_url| resolve_relative_url(&dest, base_url))
+                        .unwrap_or_else(|| dest.to_string());
+                    image = Some(PendingImage {
+                        src,
+                        title: title.to_string(),
+                        alt: String::new(),
+                        depth: 0,
+                    });
+                }
+                event => events.push(event),
+            }
+            continue;
+        };
+        // Everything up to the matching end tag only contributes to the alt text.
+        match event {
+            Event::Start(Tag::Image(..)) => pending.depth += 1,
+            Event::End(Tag::Image(..)) if pending.depth > 0 => pending.depth -= 1,
+            Event::End(Tag::Image(..)) => {
+                events.push(Event::Html(img_tag(pending, html_options).into()));
+                image = None;
+            }
+            Event::Text(text) | Event::Code(text) => pending.alt.push_str(&text),
+            Event::SoftBreak | Event::HardBreak => pending.alt.push(' '),
+            _ => {}
+        }
+    }
+
+    let mut html_output = String::new();
+    html::push_html(&mut html_output, events.into_iter());
+
+    sanitize_html(&html_output)
+}
+
+/// URLs of the images `input` embeds, resolved the way `markdown_to_html` resolves them.
+pub fn image_urls(input: &str, base_url: Option<&str>) -> Vec<String> {
+    Parser::new_ext(input, html_parser_options())
+        .filter_map(|event| match event {
+            Event::Start(Tag::Image(_, dest, _)) => Some(
+                base_url
+                    .and_then(|base_url| resolve_relative_url(&dest, base_url))
+                    .unwrap_or_else(|| dest.to_string()),
+            ),
+            _ => None,
+        })
+        .collect()
+}
+
+fn html_parser_options() -> Options {
     let mut options = Options::empty();
     options.insert(Options::ENABLE_TABLES);
     options.insert(Options::ENABLE_FOOTNOTES);
     options.insert(Options::ENABLE_STRIKETHROUGH);
     options.insert(Options::ENABLE_TASKLISTS);
+    options
+}
 
-    let parser = Parser::new_ext(input, options);
+struct PendingImage {
+    src: String,
+    title: String,
+    alt: String,
+    depth: usize,
+}
 
-    let mut html_output = String::new();
-    html::push_html(&mut html_output, parser);
+fn img_tag(image: &PendingImage, html_options: &HtmlOptions<'_>) -> String {
+    let src = html_options
+        .assets
+        .map_or(image.src.as_str(), |assets| assets.url(&image.src));
+    // Writing into a String cannot fail.
+    let mut tag = String::from("<img src=\"");
+    let _ = escape_href(&mut tag, src);
+    tag.push_str("\" alt=\"");
+    let _ = escape_html(&mut tag, &image.alt);
+    tag.push('"');
+    if !image.title.is_empty() {
+        tag.push_str(" title=\"");
+        let _ = escape_html(&mut tag, &image.title);
+        tag.push('"');
+    }
+    if let Some(info) = html_options
+        .images
+        .and_then(|images| images.get(&image.src))
+    {
+        tag.push_str(&format!(
+            " width=\"{}\" height=\"{}\"",
+            info.width, info.height
+        ));
+        if let Some(srcset) = info.srcset(src) {
+            tag.push_str(" srcset=\"");
+            let _ = escape_html(&mut tag, &srcset);
+            tag.push('"');
+        }
+    }
+    tag.push_str(" loading=\"lazy\">");
+    tag
+}
 
-    sanitize_html(&html_output)
+/// Returns `None` for absolute paths, fragments and URLs with a scheme.
+pub fn resolve_relative_url(dest: &str, base_url: &str) -> Option<String> {
+    if dest.is_empty() || dest.starts_with(['/', '#', '?']) {
+        return None;
+    }
+    let has_scheme = dest
+        .split_once(':')
+        .is_some_and(|(scheme, _)| !scheme.contains(['/', '?', '#']));
+    if has_scheme {
+        return None;
+    }
+    let relative = dest.trim_start_matches("./");
+    Some(format!("{}/{relative}", base_url.trim_end_matches('/')))
 }
 
 fn sanitize_html(html: &str) -> String {
-    Builder::default().clean(html).to_string()
+    Builder::default()
+        .add_tag_attributes("img", &["srcset", "loading"])
+        .clean(html)
+        .to_string()
 }
 
 pub fn markdown_to_text(markdown: &str) -> String {
@@ -55,7 +182,11 @@ pub fn markdown_to_text(markdown: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::{markdown_to_html, markdown_to_text};
+    use super::{HtmlOptions, image_urls, markdown_to_html, markdown_to_text};
+    use crate::utils::{
+        asset::{AssetManifest, AssetSource},
+        image::{ImageIndex, ImageInfo, ImageVariant},
+    };
 
     #[test]
     fn basic_inline_strong() {
@@ -244,7 +375,7 @@ Paragraph 2.";
     #[test]
     fn strips_script_tag() {
         let markdown = r#"Hello<script>alert("xss")</script>World"#;
-        let html = markdown_to_html(markdown);
+        let html = markdown_to_html(markdown, &HtmlOptions::default());
         assert!(!html.contains("<script>"));
         assert!(html.contains("Hello"));
         assert!(html.contains("World"));
@@ -253,7 +384,93 @@ Paragraph 2.";
     #[test]
     fn strips_javascript_link() {
         let markdown = r#"[click](javascript:alert(1))"#;
-        let html = markdown_to_html(markdown);
+        let html = markdown_to_html(markdown, &HtmlOptions::default());
         assert!(!html.contains("javascript:"));
     }
+
+    #[test]
+    fn rewrites_relative_image_paths_against_base() {
+        let markdown =
+            "![a](cover.png) ![b](./img/b.jpg) ![c](/image/c.png) ![d](https://example.com/d.png)";
+        let options = HtmlOptions {
+            base_url: Some("/posts/hello/"),
+            images: None,
+            assets: None,
+        };
+        let html = markdown_to_html(markdown, &options);
+        assert!(html.contains(r#"src="/posts/hello/cover.png""#));
+        assert!(html.contains(r#"src="/posts/hello/img/b.jpg""#));
+        assert!(html.contains(r#"src="/image/c.png""#));
+        assert!(html.contains(r#"src="https://example.com/d.png""#));
+        assert_eq!(
+            image_urls(markdown, Some("/posts/hello/")),
+            vec![
+                "/posts/hello/cover.png",
+                "/posts/hello/img/b.jpg",
+                "/image/c.png",
+                "https://example.com/d.png"
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_relative_image_paths_without_base() {
+        let html = markdown_to_html("![a](cover.png)", &HtmlOptions::default());
+        assert!(html.contains(r#"src="cover.png""#));
+        assert!(html.contains(r#"loading="lazy""#));
+    }
+
+    #[test]
+    fn adds_dimensions_and_srcset_for_known_images() {
+        let images = ImageIndex::from([(
+            "/posts/hello/cover.png".to_string(),
+            ImageInfo {
+                width: 1200,
+                height: 800,
+                variants: vec![ImageVariant {
+                    url: "/variants/posts/hello/cover-480.webp".to_string(),
+                    width: 480,
+                }],
+            },
+        )]);
+        let options = HtmlOptions {
+            base_url: Some("/posts/hello/"),
+            images: Some(&images),
+            assets: None,
+        };
+        let html = markdown_to_html("![A *cover*](cover.png \"Title\")", &options);
+        assert_eq!(
+            html,
+            "<p><img src=\"/posts/hello/cover.png\" alt=\"A cover\" title=\"Title\" width=\"1200\" height=\"800\" srcset=\"/variants/posts/hello/cover-480.webp 480w, /posts/hello/cover.png 1200w\" loading=\"lazy\"></p>\n"
+        );
+    }
+
+    #[test]
+    fn links_known_images_under_their_fingerprinted_url() {
+        let images = ImageIndex::from([(
+            "/image/a.png".to_string(),
+            ImageInfo {
+                width: 1200,
+                height: 800,
+                variants: vec![ImageVariant {
+                    url: "/variants/image/a-480.webp".to_string(),
+                    width: 480,
+                }],
+            },
+        )]);
+        let mut assets = AssetManifest::default();
+        assets.insert("/image/a.png", b"png", AssetSource::Embedded(b"png"));
+        let options = HtmlOptions {
+            base_url: None,
+            images: Some(&images),
+            assets: Some(&assets),
+        };
+        let fingerprinted = assets.url("/image/a.png");
+        let html = markdown_to_html("![a](/image/a.png) ![b](/image/b.png)", &options);
+        assert!(html.contains(&format!(r#"src="{fingerprinted}""#)));
+        assert!(html.contains(&format!(
+            r#"srcset="/variants/image/a-480.webp 480w, {fingerprinted} 1200w""#
+        )));
+        assert!(html.contains(r#"src="/image/b.png""#));
+    }
 }
@@ -0,0 +1,709 @@
+//! Render data shared by the Rocket routes and the static export.
+//!
+//! Both delivery modes build their template contexts here; only the URL
+//! strategy differs between them.
+
+use chrono::{Datelike, Utc};
+use serde::Serialize;
+
+use crate::{
+    domain::page::PageInfo,
+    entity::{article, category, fixed_content, tag},
+    repository::article::{ArticlePeriod, ArticleTerms},
+    utils::{
+        asset::AssetManifest,
+        bundle::bundle_url,
+        config::CommonConfig,
+        cut_out_string,
+        image::{ImageIndex, image_attributes},
+        markdown::{EXCERPT_LENGTH, HtmlOptions, markdown_to_html, markdown_to_text},
+        utc_to_jst,
+    },
+};
+
+pub const DEFAULT_SORT_KEY: &str = "created_at";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Taxonomy {
+    Tag,
+    Category,
+}
+
+impl Taxonomy {
+    fn segment(self) -> &'static str {
+        match self {
+            Taxonomy::Tag => "tag",
+            Taxonomy::Category => "category",
+        }
+    }
+
+    fn list_segment(self) -> &'static str {
+        match self {
+            Taxonomy::Tag => "tags",
+            Taxonomy::Category => "categories",
+        }
+    }
+}
+
+/// How links are written for a delivery mode.
+pub trait UrlStrategy: Sync {
+    fn article(&self, slug: &str) -> String;
+    fn fixed_content(&self, slug: &str) -> String;
+    fn term_list(&self, kind: Taxonomy) -> String;
+    /// Entry page of a tag or category, optionally sorted by `sort_key`.
+    fn term(&self, kind: Taxonomy, slug: &str, sort_key: Option<&str>) -> String;
+    fn term_page(&self, kind: Taxonomy, slug: &str, sort_key: &str, page: u64, per: u64) -> String;
+    fn index(&self, page: u64, per: u64, period: Option<ArticlePeriod>) -> String;
+    fn archive(&self, period: ArticlePeriod) -> String;
+}
+
+/// URLs served by the Rocket routes.
+#[derive(Debug, Clone, Copy)]
+pub struct LiveUrls {
+    archive_paths: bool,
+}
+
+impl LiveUrls {
+    /// Index pagination uses `/?page=&per=&year=&month=`.
+    pub const QUERY: Self = Self {
+        archive_paths: false,
+    };
+    /// Index pagination stays on `/archive/...` paths.
+    pub const ARCHIVE: Self = Self {
+        archive_paths: true,
+    };
+
+    pub fn archive_paths(&self) -> bool {
+        self.archive_paths
+    }
+}
+
+impl UrlStrategy for LiveUrls {
+    fn article(&self, slug: &str) -> String {
+        format!("/posts/{slug}")
+    }
+
+    fn fixed_content(&self, slug: &str) -> String {
+        format!("/{slug}")
+    }
+
+    fn term_list(&self, kind: Taxonomy) -> String {
+        format!("/{}", kind.list_segment())
+    }
+
+    fn term(&self, kind: Taxonomy, slug: &str, sort_key: Option<&str>) -> String {
+        match sort_key {
+            Some(sort_key) => format!("/{}/{slug}?sort_key={sort_key}", kind.segment()),
+            None => format!("/{}/{slug}", kind.segment()),
+        }
+    }
+
+    fn term_page(&self, kind: Taxonomy, slug: &str, sort_key: &str, page: u64, per: u64) -> String {
+        format!(
+            "/{}/{slug}?page={page}&per={per}&sort_key={sort_key}",
+            kind.segment()
+        )
+    }
+
+    fn index(&self, page: u64, per: u64, period: Option<ArticlePeriod>) -> String {
+        match period {
+            Some(period) if self.archive_paths && page <= 1 => self.archive(period),
+            Some(period) if self.archive_paths => {
+                format!("/archive/{}/{:02}/page/{page}", period.year, period.month)
+            }
+            Some(period) => format!(
+                "/?page={page}&per={per}&year={}&month={}",
+                period.year, period.month
+            ),
+            None => format!("/?page={page}&per={per}"),
+        }
+    }
+
+    fn archive(&self, period: ArticlePeriod) -> String {
+        format!("/archive/{}/{:02}", period.year, period.month)
+    }
+}
+
+/// Directory-style URLs written by the static export. Every URL maps onto an
+/// `index.html` below the output directory (see [`StaticUrls::output_path`]).
+// Used by the static export pipeline in the library target.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StaticUrls;
+
+#[allow(dead_code)]
+impl StaticUrls {
+    pub fn output_path(url: &str) -> String {
+        format!("{}index.html", url.trim_start_matches('/'))
+    }
+}
+
+impl UrlStrategy for StaticUrls {
+    fn article(&self, slug: &str) -> String {
+        format!("/posts/{slug}/")
+    }
+
+    fn fixed_content(&self, slug: &str) -> String {
+        format!("/{slug}/")
+    }
+
+    fn term_list(&self, kind: Taxonomy) -> String {
+        format!("/{}/", kind.list_segment())
+    }
+
+    fn term(&self, kind: Taxonomy, slug: &str, sort_key: Option<&str>) -> String {
+        self.term_page(kind, slug, sort_key.unwrap_or(DEFAULT_SORT_KEY), 1, 0)
+    }
+
+    fn term_page(
+        &self,
+        kind: Taxonomy,
+        slug: &str,
+        sort_key: &str,
+        page: u64,
+        _per: u64,
+    ) -> String {
+        let sort_segment = if sort_key == "updated_at" {
+            "updated/"
+        } else {
+            ""
+        };
+        if page <= 1 {
+            format!("/{}/{slug}/{sort_segment}", kind.segment())
+        } else {
+            format!("/{}/{slug}/{sort_segment}page/{page}/", kind.segment())
+        }
+    }
+
+    fn index(&self, page: u64, _per: u64, period: Option<ArticlePeriod>) -> String {
+        match period {
+            None if page <= 1 => "/".to_string(),
+            None => format!("/page/{page}/"),
+            Some(period) if page <= 1 => self.archive(period),
+            Some(period) => format!("/archive/{}/{:02}/page/{page}/", period.year, period.month),
+        }
+    }
+
+    fn archive(&self, period: ArticlePeriod) -> String {
+        format!("/archive/{}/{:02}/", period.year, period.month)
+    }
+}
+
+/// Values every page template reads through `partial/base`.
+#[derive(Debug, Serialize)]
+pub struct Layout {
+    pub site_name: Option<String>,
+    pub favicon_path: Option<String>,
+    pub year: i32,
+    pub tags_url: String,
+    pub categories_url: String,
+    pub about_url: String,
+}
+
+impl Layout {
+    pub fn new(
+        site_name: Option<&str>,
+        favicon_path: Option<&str>,
+        urls: &dyn UrlStrategy,
+    ) -> Self {
+        Self {
+            site_name: site_name.map(str::to_string),
+            favicon_path: favicon_path.map(str::to_string),
+            year: Utc::now().year(),
+            tags_url: urls.term_list(Taxonomy::Tag),
+            categories_url: urls.term_list(Taxonomy::Category),
+            about_url: urls.fixed_content("about"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArticleLink {
+    pub title: String,
+    pub slug: String,
+    pub url: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArticleSummary {
+    pub title: String,
+    pub slug: String,
+    pub url: String,
+    pub excerpt: String,
+    pub icatch: serde_json::Value,
+    pub icatch_path: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub tags: Vec<TermLink>,
+    pub categories: Vec<TermLink>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TermLink {
+    pub name: String,
+    pub slug: String,
+    pub url: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PeriodLink {
+    pub label: String,
+    pub href: String,
+    pub is_selected: bool,
+}
+
+/// Fields read by `partial/page`. The URLs are empty when there is no
+/// previous or next page.
+#[derive(Debug, Serialize)]
+pub struct Pagination {
+    pub page: u64,
+    pub per: u64,
+    pub total_pages: u64,
+    pub has_prev: bool,
+    pub has_next: bool,
+    pub prev_page: u64,
+    pub next_page: u64,
+    pub prev_url: String,
+    pub next_url: String,
+}
+
+impl Pagination {
+    fn new(page_info: &PageInfo, url: impl Fn(u64) -> String) -> Self {
+        Self {
+            page: page_info.current_page,
+            per: page_info.per,
+            total_pages: page_info.total_pages,
+            has_prev: page_info.has_prev,
+            has_next: page_info.has_next,
+            prev_page: page_info.prev_page,
+            next_page: page_info.next_page,
+            prev_url: if page_info.has_prev {
+                url(page_info.prev_page)
+            } else {
+                String::new()
+            },
+            next_url: if page_info.has_next {
+                url(page_info.next_page)
+            } else {
+                String::new()
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct IndexPage {
+    #[serde(flatten)]
+    pub layout: Layout,
+    pub articles: Vec<ArticleSummary>,
+    #[serde(flatten)]
+    pub pagination: Pagination,
+    pub selected_period: Option<String>,
+    pub period_links: Vec<PeriodLink>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArticleDetailPage {
+    #[serde(flatten)]
+    pub layout: Layout,
+    pub title: String,
+    pub content_html: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub tags: Vec<TermLink>,
+    pub categories: Vec<TermLink>,
+    pub latest_articles: Vec<ArticleLink>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FixedContentPage {
+    #[serde(flatten)]
+    pub layout: Layout,
+    pub title: String,
+    pub excerpt: String,
+    pub content_html: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub latest_articles: Vec<ArticleLink>,
+}
+
+/// `tags` or `categories`, whichever the list template iterates.
+#[derive(Debug, Serialize)]
+pub struct TermListPage {
+    #[serde(flatten)]
+    pub layout: Layout,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<TermLink>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub categories: Option<Vec<TermLink>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TermPage {
+    #[serde(flatten)]
+    pub layout: Layout,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag_slug: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category_slug: Option<String>,
+    pub sort_key: String,
+    pub sort_created_url: String,
+    pub sort_updated_url: String,
+    pub articles: Vec<ArticleSummary>,
+    #[serde(flatten)]
+    pub pagination: Pagination,
+}
+
+/// Eyecatch of an article in listings, falling back to the site default.
+pub fn icatch_path(config: &CommonConfig, article: &article::Model) -> String {
+    article
+        .icatch_path
+        .clone()
+        .or_else(|| config.default_icatch_path.clone())
+        .unwrap_or_default()
+}
+
+/// Builds template contexts for one delivery mode.
+pub struct View<'a> {
+    config: &'a CommonConfig,
+    images: &'a ImageIndex,
+    assets: &'a AssetManifest,
+    urls: &'a dyn UrlStrategy,
+}
+
+impl<'a> View<'a> {
+    pub fn new(
+        config: &'a CommonConfig,
+        images: &'a ImageIndex,
+        assets: &'a AssetManifest,
+        urls: &'a dyn UrlStrategy,
+    ) -> Self {
+        Self {
+            config,
+            images,
+            assets,
+            urls,
+        }
+    }
+
+    pub fn layout(&self) -> Layout {
+        Layout::new(
+            self.config.site_name.as_deref(),
+            self.config.favicon_path.as_deref(),
+            self.urls,
+        )
+    }
+
+    pub fn index(
+        &self,
+        articles: &[article::Model],
+        terms: &ArticleTerms,
+        page_info: &PageInfo,
+        period: Option<ArticlePeriod>,
+        periods: &[ArticlePeriod],
+    ) -> IndexPage {
+        IndexPage {
+            layout: self.layout(),
+            articles: self.article_summaries(articles, terms),
+            pagination: Pagination::new(page_info, |page| {
+                self.urls.index(page, page_info.per, period)
+            }),
+            selected_period: period.map(period_label),
+            period_links: periods
+                .iter()
+                .map(|p| PeriodLink {
+                    label: period_label(*p),
+                    href: self.urls.archive(*p),
+                    is_selected: period == Some(*p),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn article_detail(
+        &self,
+        article: &article::Model,
+        tags: &[tag::Model],
+        categories: &[category::Model],
+        latest_articles: &[article::Model],
+    ) -> ArticleDetailPage {
+        ArticleDetailPage {
+            layout: self.layout(),
+            title: article.title.clone(),
+            content_html: markdown_to_html(
+                &article.content,
+                &HtmlOptions {
+                    base_url: Some(&bundle_url(&article.slug)),
+                    images: Some(self.images),
+                    assets: Some(self.assets),
+                },
+            ),
+            created_at: utc_to_jst(article.created_at),
+            updated_at: utc_to_jst(article.updated_at),
+            tags: self.tag_links(tags),
+            categories: self.category_links(categories),
+            latest_articles: self.article_links(latest_articles),
+        }
+    }
+
+    pub fn fixed_content(
+        &self,
+        fixed_content: &fixed_content::Model,
+        latest_articles: &[article::Model],
+    ) -> FixedContentPage {
+        FixedContentPage {
+            layout: self.layout(),
+            title: fixed_content.title.clone(),
+            excerpt: excerpt(fixed_content.excerpt.as_deref(), &fixed_content.content),
+            content_html: markdown_to_html(
+                &fixed_content.content,
+                &HtmlOptions {
+                    base_url: None,
+                    images: Some(self.images),
+                    assets: Some(self.assets),
+                },
+            ),
+            created_at: utc_to_jst(fixed_content.created_at),
+            updated_at: utc_to_jst(fixed_content.updated_at),
+            latest_articles: self.article_links(latest_articles),
+        }
+    }
+
+    pub fn tag_list(&self, tags: &[tag::Model]) -> TermListPage {
+        TermListPage {
+            layout: self.layout(),
+            tags: Some(self.tag_links(tags)),
+            categories: None,
+        }
+    }
+
+    pub fn category_list(&self, categories: &[category::Model]) -> TermListPage {
+        TermListPage {
+            layout: self.layout(),
+            tags: None,
+            categories: Some(self.category_links(categories)),
+        }
+    }
+
+    /// Tag and category pages don't show the terms of each listed article.
+    pub fn term(
+        &self,
+        kind: Taxonomy,
+        slug: &str,
+        sort_key: &str,
+        articles: &[article::Model],
+        page_info: &PageInfo,
+    ) -> TermPage {
+        TermPage {
+            layout: self.layout(),
+            tag_slug: (kind == Taxonomy::Tag).then(|| slug.to_string()),
+            category_slug: (kind == Taxonomy::Category).then(|| slug.to_string()),
+            sort_key: sort_key.to_string(),
+            sort_created_url: self.urls.term(kind, slug, Some("created_at")),
+            sort_updated_url: self.urls.term(kind, slug, Some("updated_at")),
+            articles: self.article_summaries(articles, &ArticleTerms::default()),
+            pagination: Pagination::new(page_info, |page| {
+                self.urls
+                    .term_page(kind, slug, sort_key, page, page_info.per)
+            }),
+        }
+    }
+
+    /// `articles` may come from a listing query, which loads only the start of
+    /// the body.
+    fn article_summaries(
+        &self,
+        articles: &[article::Model],
+        terms: &ArticleTerms,
+    ) -> Vec<ArticleSummary> {
+        articles
+            .iter()
+            .map(|article| {
+                let icatch_path = icatch_path(self.config, article);
+                ArticleSummary {
+                    title: article.title.clone(),
+                    slug: article.slug.clone(),
+                    url: self.urls.article(&article.slug),
+                    excerpt: excerpt(article.excerpt.as_deref(), &article.content),
+                    icatch: image_attributes(&icatch_path, self.images, self.assets),
+                    icatch_path,
+                    created_at: utc_to_jst(article.created_at),
+                    updated_at: utc_to_jst(article.updated_at),
+                    tags: self.tag_links(terms.tags_of(article.id)),
+                    categories: self.category_links(terms.categories_of(article.id)),
+                }
+            })
+            .collect()
+    }
+
+    fn article_links(&self, articles: &[article::Model]) -> Vec<ArticleLink> {
+        articles
+            .iter()
+            .map(|article| ArticleLink {
+                title: article.title.clone(),
+                slug: article.slug.clone(),
+                url: self.urls.article(&article.slug),
+            })
+            .collect()
+    }
+
+    fn tag_links(&self, tags: &[tag::Model]) -> Vec<TermLink> {
+        tags.iter()
+            .map(|tag| self.term_link(Taxonomy::Tag, &tag.name, &tag.slug))
+            .collect()
+    }
+
+    fn category_links(&self, categories: &[category::Model]) -> Vec<TermLink> {
+        categories
+            .iter()
+            .map(|category| self.term_link(Taxonomy::Category, &category.name, &category.slug))
+            .collect()
+    }
+
+    fn term_link(&self, kind: Taxonomy, name: &str, slug: &str) -> TermLink {
+        TermLink {
+            name: name.to_string(),
+            slug: slug.to_string(),
+            url: self.urls.term(kind, slug, None),
+        }
+    }
+}
+
+fn excerpt(excerpt: Option<&str>, content: &str) -> String {
+    match excerpt {
+        Some(value) => value.to_string(),
+        None => cut_out_string(&markdown_to_text(content), EXCERPT_LENGTH),
+    }
+}
+
+fn period_label(period: ArticlePeriod) -> String {
+    format!("{}/{:02}", period.year, period.month)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LiveUrls, Pagination, StaticUrls, Taxonomy, UrlStrategy, View};
+    use crate::{
+        domain::page::{Page, PageInfo},
+        entity::article,
+        repository::article::{ArticlePeriod, ArticleTerms},
+        utils::{asset::AssetManifest, config::CommonConfig, image::ImageIndex},
+    };
+    use chrono::{TimeZone, Utc};
+
+    fn config() -> CommonConfig {
+        CommonConfig {
+            site_name: Some("Blog".to_string()),
+            default_icatch_path: Some("/image/default.png".to_string()),
+            favicon_path: None,
+        }
+    }
+
+    fn article(slug: &str, excerpt: Option<&str>, icatch_path: Option<&str>) -> article::Model {
+        let at = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        article::Model {
+            id: 1,
+            title: slug.to_string(),
+            slug: slug.to_string(),
+            excerpt: excerpt.map(str::to_string),
+            content: "# Heading\n\nBody text".to_string(),
+            created_at: at,
+            updated_at: at,
+            icatch_path: icatch_path.map(str::to_string),
+            content_hash: None,
+        }
+    }
+
+    #[test]
+    fn live_term_pagination_keeps_sort_key() {
+        let info = PageInfo::new(Page { number: 2, per: 10 }, 50);
+        let pagination = Pagination::new(&info, |page| {
+            LiveUrls::QUERY.term_page(Taxonomy::Tag, "rust", "updated_at", page, info.per)
+        });
+        assert_eq!(
+            pagination.prev_url,
+            "/tag/rust?page=1&per=10&sort_key=updated_at"
+        );
+        assert_eq!(
+            pagination.next_url,
+            "/tag/rust?page=3&per=10&sort_key=updated_at"
+        );
+    }
+
+    #[test]
+    fn pagination_urls_are_empty_without_navigation() {
+        let info = PageInfo::new(Page { number: 1, per: 10 }, 5);
+        let pagination = Pagination::new(&info, |page| format!("/?page={page}"));
+        assert_eq!(pagination.prev_url, "");
+        assert_eq!(pagination.next_url, "");
+    }
+
+    #[test]
+    fn live_index_urls_follow_the_requested_style() {
+        let period = ArticlePeriod::new(2026, 4);
+        assert_eq!(LiveUrls::QUERY.index(2, 10, None), "/?page=2&per=10");
+        assert_eq!(
+            LiveUrls::QUERY.index(2, 10, period),
+            "/?page=2&per=10&year=2026&month=4"
+        );
+        assert_eq!(LiveUrls::ARCHIVE.index(1, 10, period), "/archive/2026/04");
+        assert_eq!(
+            LiveUrls::ARCHIVE.index(2, 10, period),
+            "/archive/2026/04/page/2"
+        );
+    }
+
+    #[test]
+    fn static_urls_map_onto_output_files() {
+        let urls = StaticUrls;
+        let cases = [
+            (urls.index(1, 10, None), "index.html"),
+            (urls.index(3, 10, None), "page/3/index.html"),
+            (
+                urls.index(2, 10, ArticlePeriod::new(2026, 4)),
+                "archive/2026/04/page/2/index.html",
+            ),
+            (urls.article("hello"), "posts/hello/index.html"),
+            (
+                urls.term(Taxonomy::Tag, "rust", None),
+                "tag/rust/index.html",
+            ),
+            (
+                urls.term_page(Taxonomy::Category, "dev", "updated_at", 2, 10),
+                "category/dev/updated/page/2/index.html",
+            ),
+        ];
+        for (url, path) in cases {
+            assert_eq!(StaticUrls::output_path(&url), path, "{url}");
+        }
+    }
+
+    #[test]
+    fn article_summaries_fall_back_to_default_icatch_and_excerpt() {
+        let config = config();
+        let images = ImageIndex::new();
+        let assets = AssetManifest::default();
+        let view = View::new(&config, &images, &assets, &StaticUrls);
+        let info = PageInfo::new(Page { number: 1, per: 10 }, 2);
+        let page = view.index(
+            &[
+                article("first", None, None),
+                article("second", Some("Given"), Some("/image/own.png")),
+            ],
+            &ArticleTerms::default(),
+            &info,
+            None,
+            &[],
+        );
+
+        let first = &page.articles[0];
+        assert_eq!(first.url, "/posts/first/");
+        assert_eq!(first.icatch_path, "/image/default.png");
+        assert_eq!(first.excerpt, "Heading\n\nBody text");
+        let second = &page.articles[1];
+        assert_eq!(second.icatch_path, "/image/own.png");
+        assert_eq!(second.excerpt, "Given");
+        assert_eq!(page.layout.tags_url, "/tags/");
+        assert_eq!(page.layout.about_url, "/about/");
+    }
+}
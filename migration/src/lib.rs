@@ -7,6 +7,9 @@ mod m20250706_145116_create_article_tag_table;
 mod m20250824_170452_create_article_category_table;
 mod m20251024_190826_create_fixed_content_table;
 mod m20251231_000001_create_article_icatch_path;
+mod m20261019_000001_add_content_hash;
+mod m20261020_000001_create_image_table;
+mod m20261021_000001_create_data_version_table;
 
 pub struct Migrator;
 
@@ -21,6 +24,9 @@ impl MigratorTrait for Migrator {
             Box::new(m20250824_170452_create_article_category_table::Migration),
             Box::new(m20251024_190826_create_fixed_content_table::Migration),
             Box::new(m20251231_000001_create_article_icatch_path::Migration),
+            Box::new(m20261019_000001_add_content_hash::Migration),
+            Box::new(m20261020_000001_create_image_table::Migration),
+            Box::new(m20261021_000001_create_data_version_table::Migration),
         ]
     }
 }
@@ -0,0 +1,41 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Image::Table)
+                    .if_not_exists()
+                    .col(pk_auto(Image::Id))
+                    .col(string_uniq(Image::Path).not_null())
+                    .col(integer(Image::Width).not_null())
+                    .col(integer(Image::Height).not_null())
+                    .col(text(Image::Variants).not_null())
+                    .col(string(Image::SourceHash).not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Image::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Image {
+    Table,
+    Id,
+    Path,
+    Width,
+    Height,
+    Variants,
+    SourceHash,
+}